@@ -0,0 +1,117 @@
+//! Load account dumps saved to disk.
+//!
+//! A dump is either the base64 account data alone, or the JSON returned by `getAccountInfo`
+//! (the whole response, its `value`, or the output of `solana account --output json`).
+use std::{fs, path::Path, str::FromStr};
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountFile {
+    /// unknown when the dump only has the account data
+    pub owner: Option<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+impl AccountFile {
+    /// Owner of the account, dumps without owner are only taken as owned by the dynamic
+    /// bonding curve program when `assume_program_owner` is set
+    pub fn get_owner(&self, assume_program_owner: bool) -> Result<Pubkey> {
+        match self.owner {
+            Some(owner) => Ok(owner),
+            None if assume_program_owner => Ok(dynamic_bonding_curve::ID),
+            None => bail!("account file has no owner, pass --assume-program-owner to use the dynamic bonding curve program"),
+        }
+    }
+}
+
+pub fn load_account(path: &Path) -> Result<AccountFile> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse_account(&content).with_context(|| format!("invalid account file {}", path.display()))
+}
+
+pub fn parse_account(content: &str) -> Result<AccountFile> {
+    let content = content.trim();
+    if !content.starts_with('{') {
+        return Ok(AccountFile {
+            owner: None,
+            data: STANDARD.decode(content).context("invalid base64 data")?,
+        });
+    }
+
+    let root: Value = serde_json::from_str(content)?;
+    let account = [
+        &root,
+        &root["result"]["value"],
+        &root["value"],
+        &root["account"],
+    ]
+    .into_iter()
+    .find(|value| value.get("data").is_some())
+    .context("account data not found")?;
+
+    let data = match &account["data"] {
+        Value::String(data) => STANDARD.decode(data),
+        Value::Array(data) => {
+            ensure!(
+                data.len() == 2 && data[1] == "base64",
+                "account data must be base64 encoded"
+            );
+            STANDARD.decode(data[0].as_str().context("invalid account data")?)
+        }
+        _ => bail!("invalid account data"),
+    }
+    .context("invalid base64 data")?;
+    let owner = match account.get("owner") {
+        Some(owner) => Some(
+            Pubkey::from_str(owner.as_str().context("invalid owner")?).context("invalid owner")?,
+        ),
+        None => None,
+    };
+
+    Ok(AccountFile { owner, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_account() {
+        let owner = Pubkey::new_unique();
+        let data = vec![1u8, 2, 3, 255];
+        let encoded = STANDARD.encode(&data);
+
+        let raw = parse_account(&format!("{encoded}\n")).unwrap();
+        assert_eq!(raw.owner, None);
+        assert_eq!(raw.data, data);
+        assert!(raw.get_owner(false).is_err());
+        assert_eq!(raw.get_owner(true).unwrap(), dynamic_bonding_curve::ID);
+
+        let expected = AccountFile {
+            owner: Some(owner),
+            data: data.clone(),
+        };
+        let value = format!(
+            r#"{{"data":["{encoded}","base64"],"executable":false,"lamports":1,"owner":"{owner}"}}"#
+        );
+        let rpc_response = format!(
+            r#"{{"jsonrpc":"2.0","result":{{"context":{{"slot":1}},"value":{value}}},"id":1}}"#
+        );
+        let cli_output = format!(r#"{{"pubkey":"{owner}","account":{value}}}"#);
+        for content in [&value, &rpc_response, &cli_output] {
+            assert_eq!(parse_account(content).unwrap(), expected);
+        }
+
+        assert!(parse_account(
+            r#"{"data":["AQID","base58"],"owner":"11111111111111111111111111111111"}"#
+        )
+        .is_err());
+        assert!(parse_account(r#"{"lamports":1}"#).is_err());
+        assert!(parse_account("not base64!").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, Discriminator};
+use anyhow::{bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dynamic_bonding_curve::{
+    params::swap::TradeDirection,
+    state::{
+        fee::FeeMode, ClaimFeeOperator, MigrationProgress, PartnerMetadata, PoolConfig,
+        VirtualPool, VirtualPoolMetadata,
+    },
+    MeteoraDammMigrationMetadata, MeteoraDammV2Metadata,
+};
+use dynamic_bonding_curve_sdk::{
+    accounts::{
+        decode_claim_fee_operator, decode_damm_v2_migration_metadata,
+        decode_meteora_damm_migration_metadata, decode_partner_metadata, decode_pool_config,
+        decode_virtual_pool, decode_virtual_pool_metadata,
+    },
+    analytics::get_curve_table,
+    design::CurveDesign,
+    price::get_price_from_sqrt_price,
+    quote::quote_exact_in,
+    validation::{validate_config_parameters, QuoteMintInfo},
+};
+use serde_json::{json, Value};
+
+use crate::{
+    account::AccountFile,
+    spec::{ConfigSpec, SwapDirection, SwapSpec},
+};
+
+pub fn design(spec: &ConfigSpec) -> Result<Value> {
+    let (design, _) = spec.to_config_parameters()?;
+    let CurveDesign {
+        sqrt_start_price,
+        curve,
+        migration_quote_threshold,
+        token_supply,
+        sqrt_migration_price,
+        swap_base_amount,
+        migration_base_amount,
+    } = design;
+    let curve: Vec<Value> = curve
+        .iter()
+        .map(|point| {
+            json!({
+                "sqrt_price": point.sqrt_price.to_string(),
+                "liquidity": point.liquidity.to_string(),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "sqrt_start_price": sqrt_start_price.to_string(),
+        "start_price": get_price_from_sqrt_price(
+            sqrt_start_price,
+            spec.token_base_decimal,
+            spec.token_quote_decimal,
+        ),
+        "sqrt_migration_price": sqrt_migration_price.to_string(),
+        "migration_price": get_price_from_sqrt_price(
+            sqrt_migration_price,
+            spec.token_base_decimal,
+            spec.token_quote_decimal,
+        ),
+        "migration_quote_threshold": migration_quote_threshold,
+        "pre_migration_token_supply": token_supply.pre_migration_token_supply,
+        "post_migration_token_supply": token_supply.post_migration_token_supply,
+        "swap_base_amount": swap_base_amount,
+        "migration_base_amount": migration_base_amount,
+        "curve": curve,
+    }))
+}
+
+/// Violated rules, one per line
+pub fn validate(
+    spec: &ConfigSpec,
+    quote_mint: Pubkey,
+    quote_mint_account: &AccountFile,
+    leftover_receiver: &Pubkey,
+) -> Result<Vec<String>> {
+    let owner = quote_mint_account
+        .owner
+        .context("quote mint account file must include the owner")?;
+    let quote_mint = QuoteMintInfo::from_account(quote_mint, owner, &quote_mint_account.data)?;
+    let (_, config_parameters) = spec.to_config_parameters()?;
+    Ok(
+        validate_config_parameters(&config_parameters, &quote_mint, leftover_receiver)
+            .into_iter()
+            .map(|violation| format!("{}: {}", violation.rule, violation.message))
+            .collect(),
+    )
+}
+
+/// Curve table with `samples` sqrt prices evenly spaced between the start and migration price
+pub fn analytics(
+    config: &PoolConfig,
+    token_quote_decimal: u8,
+    samples: u64,
+    json: bool,
+) -> Result<String> {
+    let (start, end) = (config.sqrt_start_price, config.migration_sqrt_price);
+    ensure!(
+        start < end,
+        "migration sqrt price must be above the start sqrt price"
+    );
+    let sample_sqrt_prices: Vec<u128> = (1..=samples)
+        .map(|i| start + (end - start) / u128::from(samples + 1) * u128::from(i))
+        .collect();
+    let table = get_curve_table(config, token_quote_decimal, &sample_sqrt_prices)?;
+    if json {
+        table.to_json()
+    } else {
+        Ok(table.to_csv())
+    }
+}
+
+/// Decode any dynamic bonding curve account, its type is found from the discriminator
+pub fn decode(account: &AccountFile, assume_program_owner: bool) -> Result<String> {
+    let owner = account.get_owner(assume_program_owner)?;
+    let data = &account.data;
+    ensure!(data.len() >= 8, "account data is too short");
+    let discriminator = &data[..8];
+    let decoded = if discriminator == VirtualPool::DISCRIMINATOR {
+        format!("{:#?}", decode_virtual_pool(&owner, data)?)
+    } else if discriminator == PoolConfig::DISCRIMINATOR {
+        format!("{:#?}", decode_pool_config(&owner, data)?)
+    } else if discriminator == PartnerMetadata::DISCRIMINATOR {
+        format!("{:#?}", decode_partner_metadata(&owner, data)?)
+    } else if discriminator == VirtualPoolMetadata::DISCRIMINATOR {
+        format!("{:#?}", decode_virtual_pool_metadata(&owner, data)?)
+    } else if discriminator == ClaimFeeOperator::DISCRIMINATOR {
+        format!("{:#?}", decode_claim_fee_operator(&owner, data)?)
+    } else if discriminator == MeteoraDammMigrationMetadata::DISCRIMINATOR {
+        format!(
+            "{:#?}",
+            decode_meteora_damm_migration_metadata(&owner, data)?
+        )
+    } else if discriminator == MeteoraDammV2Metadata::DISCRIMINATOR {
+        format!("{:#?}", decode_damm_v2_migration_metadata(&owner, data)?)
+    } else {
+        bail!("unknown account discriminator");
+    };
+    Ok(decoded)
+}
+
+/// Quote and apply `swaps` one after another, the pool is left as after the last swap
+pub fn simulate(
+    virtual_pool: &mut VirtualPool,
+    config: &PoolConfig,
+    swaps: &[SwapSpec],
+) -> Result<Vec<Value>> {
+    swaps
+        .iter()
+        .enumerate()
+        .map(|(i, swap)| {
+            simulate_swap(virtual_pool, config, swap).with_context(|| format!("swap {i} failed"))
+        })
+        .collect()
+}
+
+fn simulate_swap(
+    virtual_pool: &mut VirtualPool,
+    config: &PoolConfig,
+    swap: &SwapSpec,
+) -> Result<Value> {
+    let trade_direction = match swap.direction {
+        SwapDirection::Buy => TradeDirection::QuoteToBase,
+        SwapDirection::Sell => TradeDirection::BaseToQuote,
+    };
+    let swap_result = quote_exact_in(
+        virtual_pool,
+        config,
+        trade_direction == TradeDirection::BaseToQuote,
+        swap.timestamp,
+        swap.slot,
+        swap.amount_in,
+        swap.has_referral,
+        swap.is_wallet_tracked,
+    )?;
+
+    virtual_pool.update_pre_swap(config, swap.timestamp)?;
+    let fee_mode =
+        FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, swap.has_referral)?;
+    virtual_pool.apply_swap_result(
+        config,
+        &swap_result,
+        &fee_mode,
+        trade_direction,
+        swap.timestamp,
+    )?;
+    let curve_complete = virtual_pool.is_curve_complete(config.migration_quote_threshold);
+    if curve_complete {
+        virtual_pool.finish_curve_timestamp = swap.timestamp;
+        let has_vesting = config
+            .locked_vesting_config
+            .to_locked_vesting_params()
+            .has_vesting();
+        virtual_pool.set_migration_progress(if has_vesting {
+            MigrationProgress::PostBondingCurve.into()
+        } else {
+            MigrationProgress::LockedVesting.into()
+        });
+    }
+
+    Ok(json!({
+        "direction": if swap.direction == SwapDirection::Buy { "buy" } else { "sell" },
+        "amount_in": swap.amount_in,
+        "consumed_input_amount": swap_result.consumed_input_amount,
+        "refunded_input_amount": swap_result.refunded_input_amount,
+        "output_amount": swap_result.output_amount,
+        "trading_fee": swap_result.trading_fee,
+        "protocol_fee": swap_result.protocol_fee,
+        "referral_fee": swap_result.referral_fee,
+        "next_sqrt_price": swap_result.next_sqrt_price.to_string(),
+        "base_reserve": virtual_pool.base_reserve,
+        "quote_reserve": virtual_pool.quote_reserve,
+        "curve_complete": curve_complete,
+    }))
+}
+
+pub fn instruction_to_json(instruction: &Instruction) -> Value {
+    let accounts: Vec<Value> = instruction
+        .accounts
+        .iter()
+        .map(|account| {
+            json!({
+                "pubkey": account.pubkey.to_string(),
+                "is_signer": account.is_signer,
+                "is_writable": account.is_writable,
+            })
+        })
+        .collect();
+    json!({
+        "program_id": instruction.program_id.to_string(),
+        "accounts": accounts,
+        "data": STANDARD.encode(&instruction.data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::{
+        params::liquidity_distribution::LiquidityDistributionParameters, state::CollectFeeMode,
+    };
+    use dynamic_bonding_curve_sdk::instructions::create_config;
+
+    use super::*;
+
+    fn get_config_and_pool() -> (PoolConfig, VirtualPool) {
+        let mut config = PoolConfig {
+            migration_quote_threshold: 1_000_000_000,
+            sqrt_start_price: 2916686334356757,
+            migration_sqrt_price: 11666745337427032,
+            collect_fee_mode: CollectFeeMode::QuoteToken.into(),
+            ..Default::default()
+        };
+        config.pool_fees.base_fee.cliff_fee_numerator = 10_000_000;
+        config.curve[0] = LiquidityDistributionParameters {
+            sqrt_price: 11666745337427032,
+            liquidity: 3111132089980541388292920297291756,
+        }
+        .to_liquidity_distribution_config();
+        let pool = VirtualPool {
+            sqrt_price: config.sqrt_start_price,
+            base_reserve: u64::MAX / 2,
+            ..Default::default()
+        };
+        (config, pool)
+    }
+
+    #[test]
+    fn test_simulate() {
+        let (config, mut pool) = get_config_and_pool();
+        let swap = |direction, amount_in| SwapSpec {
+            direction,
+            amount_in,
+            timestamp: 1,
+            slot: 1,
+            has_referral: false,
+            is_wallet_tracked: false,
+        };
+        let swaps = [
+            swap(SwapDirection::Buy, 500_000_000),
+            swap(SwapDirection::Sell, 1_000_000),
+            swap(SwapDirection::Buy, 2_000_000_000),
+        ];
+        let steps = simulate(&mut pool, &config, &swaps).unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0]["curve_complete"], false);
+        assert_eq!(steps[2]["curve_complete"], true);
+        // exact in swaps may overshoot the migration threshold
+        assert!(pool.quote_reserve >= config.migration_quote_threshold);
+        assert_eq!(pool.finish_curve_timestamp, 1);
+
+        // no swap after the curve is completed
+        let err = simulate(&mut pool, &config, &swaps[..1]).unwrap_err();
+        assert_eq!(err.to_string(), "swap 0 failed");
+    }
+
+    #[test]
+    fn test_analytics_samples() {
+        let (config, _) = get_config_and_pool();
+        let csv = analytics(&config, 9, 3, false).unwrap();
+        // header, start, 3 samples and migration price
+        assert_eq!(csv.lines().count(), 6);
+        let json: Value = serde_json::from_str(&analytics(&config, 9, 0, true).unwrap()).unwrap();
+        assert_eq!(json["rows"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_decode() {
+        let (config, _) = get_config_and_pool();
+        let mut data = PoolConfig::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&config));
+        let account = AccountFile { owner: None, data };
+        assert!(decode(&account, false).is_err());
+        assert!(decode(&account, true).unwrap().starts_with("PoolConfig {"));
+
+        let account = AccountFile {
+            owner: Some(dynamic_bonding_curve::ID),
+            data: account.data,
+        };
+        assert!(decode(&account, false).unwrap().starts_with("PoolConfig {"));
+        let account = AccountFile {
+            owner: Some(Pubkey::new_unique()),
+            data: account.data,
+        };
+        assert!(decode(&account, true).is_err());
+        let account = AccountFile {
+            owner: None,
+            data: vec![0; 16],
+        };
+        assert!(decode(&account, true).is_err());
+    }
+
+    #[test]
+    fn test_instruction_to_json() {
+        let spec: ConfigSpec = toml::from_str(
+            r#"
+total_token_supply = 1_000_000_000
+percentage_supply_on_curve = 70
+initial_market_cap = 30
+migration_market_cap = 300
+token_base_decimal = 6
+token_quote_decimal = 9
+migration_option = 1
+
+[base_fee]
+cliff_fee_numerator = 10_000_000
+"#,
+        )
+        .unwrap();
+        let (_, config_parameters) = spec.to_config_parameters().unwrap();
+        let config = Pubkey::new_unique();
+        let instruction = create_config(
+            config,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            config_parameters,
+        );
+        let json = instruction_to_json(&instruction);
+        assert_eq!(json["program_id"], dynamic_bonding_curve::ID.to_string());
+        assert_eq!(json["accounts"][0]["pubkey"], config.to_string());
+        assert_eq!(json["accounts"][0]["is_signer"], true);
+        assert_eq!(
+            STANDARD.decode(json["data"].as_str().unwrap()).unwrap(),
+            instruction.data
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Offline dynamic bonding curve tool.
+//!
+//! Every command works from files saved to disk, no RPC is needed.
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use dynamic_bonding_curve::SwapParameters;
+use dynamic_bonding_curve_sdk::{
+    accounts::{decode_pool_config, decode_virtual_pool},
+    instructions::{create_config, swap, PoolKeys, SwapAccounts},
+};
+
+mod account;
+mod commands;
+mod spec;
+
+use account::load_account;
+use spec::{load_spec, ConfigSpec, SwapSequenceSpec};
+
+#[derive(Debug, Parser)]
+#[command(name = "dbc", version, about = "Offline dynamic bonding curve tool")]
+struct Cli {
+    /// take account dumps without owner as owned by the dynamic bonding curve program
+    #[arg(long, global = true)]
+    assume_program_owner: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Design a curve from a config spec (TOML, or JSON with a .json extension)
+    Design { spec: PathBuf },
+    /// Validate the config parameters built from a config spec
+    Validate {
+        spec: PathBuf,
+        #[arg(long)]
+        quote_mint: Pubkey,
+        /// quote mint account dump, must include the owner
+        #[arg(long)]
+        quote_mint_account: PathBuf,
+        #[arg(long)]
+        leftover_receiver: Pubkey,
+    },
+    /// Print the curve analytics table of a config account
+    Analytics {
+        /// config account dump
+        config: PathBuf,
+        #[arg(long)]
+        quote_decimal: u8,
+        /// number of samples between the start and the migration price
+        #[arg(long, default_value_t = 0)]
+        samples: u64,
+        #[arg(long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+    },
+    /// Decode an account dump of any dynamic bonding curve account
+    Decode { account: PathBuf },
+    /// Simulate a swap sequence on a pool
+    Simulate {
+        /// virtual pool account dump
+        #[arg(long)]
+        pool: PathBuf,
+        /// config account dump
+        #[arg(long)]
+        config: PathBuf,
+        /// swap sequence spec
+        swaps: PathBuf,
+    },
+    /// Print an unsigned instruction as JSON
+    #[command(subcommand)]
+    Instruction(InstructionCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum InstructionCommand {
+    /// Create a config from a config spec, `config` must sign the transaction
+    CreateConfig {
+        spec: PathBuf,
+        #[arg(long)]
+        config: Pubkey,
+        #[arg(long)]
+        fee_claimer: Pubkey,
+        #[arg(long)]
+        leftover_receiver: Pubkey,
+        #[arg(long)]
+        quote_mint: Pubkey,
+        #[arg(long)]
+        payer: Pubkey,
+    },
+    /// Swap on a pool, the direction follows the input and output token accounts
+    Swap {
+        #[arg(long)]
+        pool: Pubkey,
+        /// virtual pool account dump
+        #[arg(long)]
+        pool_account: PathBuf,
+        /// config account dump
+        #[arg(long)]
+        config_account: PathBuf,
+        #[arg(long)]
+        payer: Pubkey,
+        #[arg(long)]
+        input_token_account: Pubkey,
+        #[arg(long)]
+        output_token_account: Pubkey,
+        #[arg(long)]
+        referral_token_account: Option<Pubkey>,
+        #[arg(long)]
+        amount_in: u64,
+        #[arg(long, default_value_t = 0)]
+        minimum_amount_out: u64,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let assume_program_owner = cli.assume_program_owner;
+    match cli.command {
+        Command::Design { spec } => {
+            let spec: ConfigSpec = load_spec(&spec)?;
+            println!("{:#}", commands::design(&spec)?);
+        }
+        Command::Validate {
+            spec,
+            quote_mint,
+            quote_mint_account,
+            leftover_receiver,
+        } => {
+            let spec: ConfigSpec = load_spec(&spec)?;
+            let quote_mint_account = load_account(&quote_mint_account)?;
+            let violations =
+                commands::validate(&spec, quote_mint, &quote_mint_account, &leftover_receiver)?;
+            for violation in &violations {
+                println!("{violation}");
+            }
+            if !violations.is_empty() {
+                bail!("{} rule(s) violated", violations.len());
+            }
+            println!("config is valid");
+        }
+        Command::Analytics {
+            config,
+            quote_decimal,
+            samples,
+            format,
+        } => {
+            let account = load_account(&config)?;
+            let config =
+                decode_pool_config(&account.get_owner(assume_program_owner)?, &account.data)?;
+            let table = commands::analytics(
+                &config,
+                quote_decimal,
+                samples,
+                matches!(format, Format::Json),
+            )?;
+            println!("{table}");
+        }
+        Command::Decode { account } => {
+            println!(
+                "{}",
+                commands::decode(&load_account(&account)?, assume_program_owner)?
+            );
+        }
+        Command::Simulate {
+            pool,
+            config,
+            swaps,
+        } => {
+            let pool = load_account(&pool)?;
+            let mut virtual_pool =
+                decode_virtual_pool(&pool.get_owner(assume_program_owner)?, &pool.data)?;
+            let config = load_account(&config)?;
+            let config =
+                decode_pool_config(&config.get_owner(assume_program_owner)?, &config.data)?;
+            let swaps: SwapSequenceSpec = load_spec(&swaps)?;
+            let steps = commands::simulate(&mut virtual_pool, &config, &swaps.swaps)?;
+            println!("{:#}", serde_json::Value::Array(steps));
+        }
+        Command::Instruction(command) => {
+            let instruction = match command {
+                InstructionCommand::CreateConfig {
+                    spec,
+                    config,
+                    fee_claimer,
+                    leftover_receiver,
+                    quote_mint,
+                    payer,
+                } => {
+                    let spec: ConfigSpec = load_spec(&spec)?;
+                    let (_, config_parameters) = spec.to_config_parameters()?;
+                    create_config(
+                        config,
+                        fee_claimer,
+                        leftover_receiver,
+                        quote_mint,
+                        payer,
+                        config_parameters,
+                    )
+                }
+                InstructionCommand::Swap {
+                    pool,
+                    pool_account,
+                    config_account,
+                    payer,
+                    input_token_account,
+                    output_token_account,
+                    referral_token_account,
+                    amount_in,
+                    minimum_amount_out,
+                } => {
+                    let pool_account = load_account(&pool_account)?;
+                    let virtual_pool = decode_virtual_pool(
+                        &pool_account.get_owner(assume_program_owner)?,
+                        &pool_account.data,
+                    )?;
+                    let config_account = load_account(&config_account)?;
+                    let config = decode_pool_config(
+                        &config_account.get_owner(assume_program_owner)?,
+                        &config_account.data,
+                    )?;
+                    swap(
+                        &PoolKeys::from_state(pool, &virtual_pool, &config)?,
+                        &SwapAccounts {
+                            input_token_account,
+                            output_token_account,
+                            payer,
+                            referral_token_account,
+                        },
+                        SwapParameters {
+                            amount_in,
+                            minimum_amount_out,
+                        },
+                    )
+                }
+            };
+            println!("{:#}", commands::instruction_to_json(&instruction));
+        }
+    }
+    Ok(())
+}
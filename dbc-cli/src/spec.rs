@@ -0,0 +1,331 @@
+//! Config and swap sequence specs, written in TOML or JSON.
+//!
+//! Enum fields are the raw `u8` values of `ConfigParameters`, e.g. `migration_option = 1` for
+//! DAMM v2.
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use dynamic_bonding_curve::{
+    params::fee_parameters::{BaseFeeParameters, DynamicFeeParameters, PoolFeeParameters},
+    state::MigrationOption,
+    ConfigParameters, LockedVestingParams, MigrationFee,
+};
+use dynamic_bonding_curve_sdk::design::{design_curve, CurveDesign, CurveDesignParams};
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// Parse a TOML file, or a JSON file when the extension is `.json`
+pub fn load_spec<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let spec = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_str(&content)?
+    } else {
+        toml::from_str(&content)?
+    };
+    Ok(spec)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigSpec {
+    /// total supply, without decimals
+    pub total_token_supply: u64,
+    pub percentage_supply_on_curve: u8,
+    /// market caps in whole quote token, fractions are allowed (e.g. 30.5)
+    pub initial_market_cap: f64,
+    pub migration_market_cap: f64,
+    pub token_base_decimal: u8,
+    pub token_quote_decimal: u8,
+    pub migration_option: u8,
+    #[serde(default)]
+    pub migration_fee_percentage: u8,
+    #[serde(default)]
+    pub creator_migration_fee_percentage: u8,
+    #[serde(default)]
+    pub locked_vesting: LockedVestingSpec,
+    pub base_fee: BaseFeeSpec,
+    pub dynamic_fee: Option<DynamicFeeSpec>,
+    #[serde(default)]
+    pub collect_fee_mode: u8,
+    #[serde(default)]
+    pub activation_type: u8,
+    #[serde(default)]
+    pub token_type: u8,
+    #[serde(default)]
+    pub partner_lp_percentage: u8,
+    #[serde(default)]
+    pub partner_locked_lp_percentage: u8,
+    #[serde(default)]
+    pub creator_lp_percentage: u8,
+    #[serde(default)]
+    pub creator_locked_lp_percentage: u8,
+    #[serde(default)]
+    pub migration_fee_option: u8,
+    #[serde(default)]
+    pub creator_trading_fee_percentage: u8,
+    #[serde(default)]
+    pub token_update_authority: u8,
+    #[serde(default)]
+    pub migration_fill_mode: u8,
+    #[serde(default)]
+    pub max_activation_delay: u64,
+    #[serde(default)]
+    pub buy_limit_duration: u64,
+    #[serde(default)]
+    pub max_buy_quote_per_swap: u64,
+    #[serde(default)]
+    pub max_buy_quote_per_wallet: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockedVestingSpec {
+    pub amount_per_period: u64,
+    pub cliff_duration_from_migration_time: u64,
+    pub frequency: u64,
+    pub number_of_period: u64,
+    pub cliff_unlock_amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BaseFeeSpec {
+    pub cliff_fee_numerator: u64,
+    #[serde(default)]
+    pub number_of_period: u16,
+    #[serde(default)]
+    pub period_frequency: u64,
+    #[serde(default)]
+    pub reduction_factor: u64,
+    #[serde(default)]
+    pub fee_scheduler_mode: u8,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicFeeSpec {
+    pub bin_step: u16,
+    pub bin_step_u128: u128,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub max_volatility_accumulator: u32,
+    pub variable_fee_control: u32,
+}
+
+impl ConfigSpec {
+    pub fn to_curve_design_params(&self) -> Result<CurveDesignParams> {
+        let LockedVestingSpec {
+            amount_per_period,
+            cliff_duration_from_migration_time,
+            frequency,
+            number_of_period,
+            cliff_unlock_amount,
+        } = self.locked_vesting;
+        Ok(CurveDesignParams {
+            total_token_supply: self.total_token_supply,
+            percentage_supply_on_curve: self.percentage_supply_on_curve,
+            initial_market_cap: self.initial_market_cap,
+            migration_market_cap: self.migration_market_cap,
+            token_base_decimal: self.token_base_decimal,
+            token_quote_decimal: self.token_quote_decimal,
+            migration_option: MigrationOption::try_from(self.migration_option)
+                .context("invalid migration option")?,
+            migration_fee_percentage: self.migration_fee_percentage,
+            locked_vesting: LockedVestingParams {
+                amount_per_period,
+                cliff_duration_from_migration_time,
+                frequency,
+                number_of_period,
+                cliff_unlock_amount,
+            },
+        })
+    }
+
+    fn to_pool_fee_parameters(&self) -> PoolFeeParameters {
+        let BaseFeeSpec {
+            cliff_fee_numerator,
+            number_of_period,
+            period_frequency,
+            reduction_factor,
+            fee_scheduler_mode,
+        } = self.base_fee;
+        PoolFeeParameters {
+            base_fee: BaseFeeParameters {
+                cliff_fee_numerator,
+                number_of_period,
+                period_frequency,
+                reduction_factor,
+                fee_scheduler_mode,
+            },
+            dynamic_fee: self.dynamic_fee.map(|dynamic_fee| DynamicFeeParameters {
+                bin_step: dynamic_fee.bin_step,
+                bin_step_u128: dynamic_fee.bin_step_u128,
+                filter_period: dynamic_fee.filter_period,
+                decay_period: dynamic_fee.decay_period,
+                reduction_factor: dynamic_fee.reduction_factor,
+                max_volatility_accumulator: dynamic_fee.max_volatility_accumulator,
+                variable_fee_control: dynamic_fee.variable_fee_control,
+            }),
+        }
+    }
+
+    /// Design the curve and build the parameters of `create_config`
+    pub fn to_config_parameters(&self) -> Result<(CurveDesign, ConfigParameters)> {
+        let design_params = self.to_curve_design_params()?;
+        let design = design_curve(&design_params)?;
+        let mut config_parameters = ConfigParameters {
+            pool_fees: self.to_pool_fee_parameters(),
+            collect_fee_mode: self.collect_fee_mode,
+            migration_option: self.migration_option,
+            activation_type: self.activation_type,
+            token_type: self.token_type,
+            token_decimal: self.token_base_decimal,
+            partner_lp_percentage: self.partner_lp_percentage,
+            partner_locked_lp_percentage: self.partner_locked_lp_percentage,
+            creator_lp_percentage: self.creator_lp_percentage,
+            creator_locked_lp_percentage: self.creator_locked_lp_percentage,
+            migration_quote_threshold: 0,
+            sqrt_start_price: 0,
+            locked_vesting: LockedVestingParams::default(),
+            migration_fee_option: self.migration_fee_option,
+            token_supply: None,
+            creator_trading_fee_percentage: self.creator_trading_fee_percentage,
+            token_update_authority: self.token_update_authority,
+            migration_fee: MigrationFee {
+                fee_percentage: self.migration_fee_percentage,
+                creator_fee_percentage: self.creator_migration_fee_percentage,
+            },
+            migration_fill_mode: self.migration_fill_mode,
+            max_activation_delay: self.max_activation_delay,
+            buy_limit_duration: self.buy_limit_duration,
+            max_buy_quote_per_swap: self.max_buy_quote_per_swap,
+            max_buy_quote_per_wallet: self.max_buy_quote_per_wallet,
+            padding_0: [0; 3],
+            padding_1: [0; 3],
+            curve: vec![],
+        };
+        design.apply_to(&design_params, &mut config_parameters);
+        Ok((design, config_parameters))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapSequenceSpec {
+    pub swaps: Vec<SwapSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapDirection {
+    /// quote token to base token
+    Buy,
+    /// base token to quote token
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwapSpec {
+    pub direction: SwapDirection,
+    /// input amount after token 2022 transfer fee
+    pub amount_in: u64,
+    pub timestamp: u64,
+    pub slot: u64,
+    #[serde(default)]
+    pub has_referral: bool,
+    /// buy through `swap_with_buy_tracker`, required under a per wallet limit
+    #[serde(default)]
+    pub is_wallet_tracked: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_spl::token::spl_token;
+    use dynamic_bonding_curve_sdk::validation::{validate_config_parameters, QuoteMintInfo};
+
+    use super::*;
+
+    const CONFIG_SPEC: &str = r#"
+total_token_supply = 1_000_000_000
+percentage_supply_on_curve = 70
+initial_market_cap = 30
+migration_market_cap = 300
+token_base_decimal = 6
+token_quote_decimal = 9
+migration_option = 1
+migration_fee_percentage = 5
+creator_migration_fee_percentage = 50
+partner_locked_lp_percentage = 50
+creator_locked_lp_percentage = 50
+
+[base_fee]
+cliff_fee_numerator = 10_000_000
+"#;
+
+    #[test]
+    fn test_config_spec() {
+        let spec: ConfigSpec = toml::from_str(CONFIG_SPEC).unwrap();
+        let (design, config_parameters) = spec.to_config_parameters().unwrap();
+        assert_eq!(config_parameters.curve.len(), design.curve.len());
+        assert_eq!(config_parameters.sqrt_start_price, design.sqrt_start_price);
+        assert_eq!(
+            config_parameters.migration_quote_threshold,
+            design.migration_quote_threshold
+        );
+        assert_eq!(config_parameters.migration_fee.creator_fee_percentage, 50);
+
+        let quote_mint = QuoteMintInfo {
+            mint: spl_token::native_mint::ID,
+            owner: spl_token::ID,
+            extensions: vec![],
+        };
+        let violations =
+            validate_config_parameters(&config_parameters, &quote_mint, &Pubkey::new_unique());
+        assert!(violations.is_empty(), "{violations:?}");
+
+        let json = serde_json::json!({
+            "total_token_supply": 1_000_000_000u64,
+            "percentage_supply_on_curve": 70,
+            "initial_market_cap": 30,
+            "migration_market_cap": 300,
+            "token_base_decimal": 6,
+            "token_quote_decimal": 9,
+            "migration_option": 1,
+            "base_fee": { "cliff_fee_numerator": 10_000_000 },
+            "unknown_field": 1,
+        });
+        assert!(serde_json::from_value::<ConfigSpec>(json).is_err());
+    }
+
+    #[test]
+    fn test_swap_sequence_spec() {
+        let spec: SwapSequenceSpec = toml::from_str(
+            r#"
+[[swaps]]
+direction = "buy"
+amount_in = 1_000_000_000
+timestamp = 1
+slot = 1
+
+[[swaps]]
+direction = "sell"
+amount_in = 1_000
+timestamp = 2
+slot = 2
+has_referral = true
+"#,
+        )
+        .unwrap();
+        assert_eq!(spec.swaps.len(), 2);
+        assert_eq!(spec.swaps[0].direction, SwapDirection::Buy);
+        assert!(!spec.swaps[0].has_referral);
+        assert_eq!(spec.swaps[1].direction, SwapDirection::Sell);
+        assert!(spec.swaps[1].has_referral);
+    }
+}
@@ -0,0 +1,149 @@
+//! Decode raw account data of dynamic bonding curve accounts.
+//!
+//! Every decoder checks the owner program, the anchor discriminator and the account size
+//! before reading the data.
+use anchor_lang::{prelude::*, Discriminator, ZeroCopy};
+use anyhow::{ensure, Result};
+use dynamic_bonding_curve::{
+    state::{ClaimFeeOperator, PartnerMetadata, PoolConfig, VirtualPool, VirtualPoolMetadata},
+    MeteoraDammMigrationMetadata, MeteoraDammV2Metadata,
+};
+
+fn check_discriminator<T: Discriminator>(data: &[u8]) -> Result<()> {
+    ensure!(
+        data.len() >= T::DISCRIMINATOR.len() && &data[..T::DISCRIMINATOR.len()] == T::DISCRIMINATOR,
+        "invalid account discriminator"
+    );
+    Ok(())
+}
+
+/// Decode a zero copy account without checking its owner
+pub fn deserialize_zero_copy_account<T: ZeroCopy + Space>(data: &[u8]) -> Result<T> {
+    let disc_len = T::DISCRIMINATOR.len();
+    ensure!(
+        data.len() == disc_len + T::INIT_SPACE,
+        "invalid account size"
+    );
+    check_discriminator::<T>(data)?;
+    // account data is not guaranteed to be aligned
+    Ok(bytemuck::pod_read_unaligned(&data[disc_len..]))
+}
+
+/// Decode a zero copy account owned by the dynamic bonding curve program
+pub fn decode_zero_copy_account<T: ZeroCopy + Owner + Space>(
+    owner: &Pubkey,
+    data: &[u8],
+) -> Result<T> {
+    ensure!(*owner == T::owner(), "invalid account owner");
+    deserialize_zero_copy_account(data)
+}
+
+/// Decode a borsh account owned by the dynamic bonding curve program,
+/// the account must not have bytes left after its last field
+pub fn decode_borsh_account<T: AnchorDeserialize + Discriminator + Owner>(
+    owner: &Pubkey,
+    data: &[u8],
+) -> Result<T> {
+    ensure!(*owner == T::owner(), "invalid account owner");
+    check_discriminator::<T>(data)?;
+    let mut remaining = &data[T::DISCRIMINATOR.len()..];
+    let account = T::deserialize(&mut remaining)?;
+    ensure!(remaining.is_empty(), "invalid account size");
+    Ok(account)
+}
+
+pub fn decode_virtual_pool(owner: &Pubkey, data: &[u8]) -> Result<VirtualPool> {
+    decode_zero_copy_account(owner, data)
+}
+
+pub fn decode_pool_config(owner: &Pubkey, data: &[u8]) -> Result<PoolConfig> {
+    decode_zero_copy_account(owner, data)
+}
+
+pub fn decode_meteora_damm_migration_metadata(
+    owner: &Pubkey,
+    data: &[u8],
+) -> Result<MeteoraDammMigrationMetadata> {
+    decode_zero_copy_account(owner, data)
+}
+
+pub fn decode_damm_v2_migration_metadata(
+    owner: &Pubkey,
+    data: &[u8],
+) -> Result<MeteoraDammV2Metadata> {
+    decode_zero_copy_account(owner, data)
+}
+
+pub fn decode_claim_fee_operator(owner: &Pubkey, data: &[u8]) -> Result<ClaimFeeOperator> {
+    decode_zero_copy_account(owner, data)
+}
+
+pub fn decode_partner_metadata(owner: &Pubkey, data: &[u8]) -> Result<PartnerMetadata> {
+    decode_borsh_account(owner, data)
+}
+
+pub fn decode_virtual_pool_metadata(owner: &Pubkey, data: &[u8]) -> Result<VirtualPoolMetadata> {
+    decode_borsh_account(owner, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::state::MigrationProgress;
+
+    use super::*;
+
+    fn to_account_data<T: Discriminator + bytemuck::Pod>(account: &T) -> Vec<u8> {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(account));
+        data
+    }
+
+    #[test]
+    fn test_decode_zero_copy_account() {
+        let virtual_pool = VirtualPool {
+            base_reserve: 1_000,
+            migration_progress: MigrationProgress::PostBondingCurve.into(),
+            ..Default::default()
+        };
+        let data = to_account_data(&virtual_pool);
+
+        let decoded = decode_virtual_pool(&dynamic_bonding_curve::ID, &data).unwrap();
+        assert_eq!(decoded.base_reserve, 1_000);
+        assert_eq!(
+            decoded.get_migration_progress().unwrap(),
+            MigrationProgress::PostBondingCurve
+        );
+
+        // wrong owner
+        assert!(decode_virtual_pool(&Pubkey::new_unique(), &data).is_err());
+        // wrong size
+        assert!(decode_virtual_pool(&dynamic_bonding_curve::ID, &data[..data.len() - 1]).is_err());
+        // wrong account type
+        let config_data = to_account_data(&PoolConfig::default());
+        assert!(decode_virtual_pool(&dynamic_bonding_curve::ID, &config_data).is_err());
+        assert!(decode_pool_config(&dynamic_bonding_curve::ID, &config_data).is_ok());
+    }
+
+    #[test]
+    fn test_decode_borsh_account() {
+        let partner_metadata = PartnerMetadata {
+            fee_claimer: Pubkey::new_unique(),
+            name: "partner".to_string(),
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        partner_metadata.try_serialize(&mut data).unwrap();
+
+        let decoded = decode_partner_metadata(&dynamic_bonding_curve::ID, &data).unwrap();
+        assert_eq!(decoded.fee_claimer, partner_metadata.fee_claimer);
+        assert_eq!(decoded.name, partner_metadata.name);
+
+        // wrong owner
+        assert!(decode_partner_metadata(&Pubkey::new_unique(), &data).is_err());
+        // wrong account type
+        assert!(decode_virtual_pool_metadata(&dynamic_bonding_curve::ID, &data).is_err());
+        // trailing bytes
+        data.push(0);
+        assert!(decode_partner_metadata(&dynamic_bonding_curve::ID, &data).is_err());
+    }
+}
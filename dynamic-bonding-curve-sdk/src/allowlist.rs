@@ -0,0 +1,91 @@
+//! Merkle tree of the wallets allowed to buy in the allowlist phase of a pool.
+//!
+//! Hashing is done by the program functions, so the root and proofs match what
+//! `swap_allowlisted` verifies.
+use anchor_lang::prelude::*;
+use anyhow::{ensure, Result};
+use dynamic_bonding_curve::allowlist::{get_allowlist_leaf, hash_allowlist_nodes};
+
+#[derive(Debug, Clone)]
+pub struct AllowlistTree {
+    /// leaves first, the last layer only holds the root
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl AllowlistTree {
+    /// Duplicated wallets are only added once, the wallet order doesn't change the root
+    pub fn new(wallets: &[Pubkey]) -> Result<Self> {
+        ensure!(!wallets.is_empty(), "allowlist is empty");
+        let mut leaves = wallets.iter().map(get_allowlist_leaf).collect::<Vec<_>>();
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        let mut layers = vec![leaves];
+        while let Some(layer) = layers.last().filter(|layer| layer.len() > 1) {
+            // an odd node is moved up as is
+            let next_layer = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_allowlist_nodes(left, right),
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next_layer);
+        }
+        Ok(Self { layers })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    /// Proof to pass to `swap_allowlisted`, None if the wallet isn't in the allowlist
+    pub fn get_proof(&self, wallet: &Pubkey) -> Option<Vec<[u8; 32]>> {
+        let leaf = get_allowlist_leaf(wallet);
+        let mut index = self.layers[0].binary_search(&leaf).ok()?;
+        let mut proof = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::allowlist::verify_allowlist_proof;
+
+    use super::*;
+
+    #[test]
+    fn test_allowlist_proofs() {
+        for size in [1, 2, 5, 8] {
+            let wallets = (0..size).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+            let tree = AllowlistTree::new(&wallets).unwrap();
+            let root = tree.root();
+            for wallet in &wallets {
+                let proof = tree.get_proof(wallet).unwrap();
+                assert!(verify_allowlist_proof(&root, wallet, &proof));
+                assert!(!verify_allowlist_proof(
+                    &root,
+                    &Pubkey::new_unique(),
+                    &proof
+                ));
+            }
+            assert!(tree.get_proof(&Pubkey::new_unique()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_allowlist_root_ignores_order_and_duplicates() {
+        let wallets = (0..3).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let root = AllowlistTree::new(&wallets).unwrap().root();
+        let reordered = [wallets[2], wallets[0], wallets[1], wallets[0]];
+        assert_eq!(AllowlistTree::new(&reordered).unwrap().root(), root);
+        assert!(AllowlistTree::new(&[]).is_err());
+    }
+}
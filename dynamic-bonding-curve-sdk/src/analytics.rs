@@ -0,0 +1,242 @@
+//! Tabulate a config curve for review.
+//!
+//! Every row is the state of a pool after buying from `sqrt_start_price` up to `sqrt_price`,
+//! swap fees excluded. Rows are taken at the start price, at every curve breakpoint below the
+//! migration price, at the migration price and at the requested sample sqrt prices.
+use std::fmt::Write;
+
+use anyhow::{ensure, Context, Result};
+use dynamic_bonding_curve::{
+    curve::{get_delta_amount_base_unsigned_256, get_delta_amount_quote_unsigned_256},
+    state::PoolConfig,
+    u128x128_math::Rounding,
+};
+use ruint::aliases::U256;
+use serde::{Serialize, Serializer};
+
+use crate::price::{get_market_cap_from_sqrt_price, get_price_from_sqrt_price, PoolConfigPrice};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CurveRow {
+    /// serialized as a string, JSON numbers can't hold a u128
+    #[serde(serialize_with = "serialize_u128")]
+    pub sqrt_price: u128,
+    /// whether the row is the start price, a curve point or the migration price
+    pub breakpoint: bool,
+    /// cumulative base token sold, in base token units
+    pub base_sold: u64,
+    /// cumulative quote token raised, in quote token units
+    pub quote_raised: u64,
+    /// price of one whole base token in quote token
+    pub price: f64,
+    /// market cap of the pre migration supply in quote token
+    pub market_cap: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CurveTable {
+    pub rows: Vec<CurveRow>,
+}
+
+impl CurveTable {
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("sqrt_price,breakpoint,base_sold,quote_raised,price,market_cap\n");
+        for row in &self.rows {
+            // writing to a String never fails
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                row.sqrt_price,
+                row.breakpoint,
+                row.base_sold,
+                row.quote_raised,
+                row.price,
+                row.market_cap
+            );
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn serialize_u128<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Build the curve table of `config`, `sample_sqrt_prices` must be between the start and the
+/// migration sqrt price
+pub fn get_curve_table(
+    config: &PoolConfig,
+    token_quote_decimal: u8,
+    sample_sqrt_prices: &[u128],
+) -> Result<CurveTable> {
+    let sqrt_start_price = config.sqrt_start_price;
+    let migration_sqrt_price = config.migration_sqrt_price;
+    ensure!(
+        sqrt_start_price < migration_sqrt_price,
+        "migration sqrt price must be above the start sqrt price"
+    );
+
+    let mut points: Vec<(u128, bool)> =
+        vec![(sqrt_start_price, true), (migration_sqrt_price, true)];
+    points.extend(
+        config
+            .curve
+            .iter()
+            .take_while(|point| point.sqrt_price != 0)
+            .filter(|point| point.sqrt_price < migration_sqrt_price)
+            .map(|point| (point.sqrt_price, true)),
+    );
+    for &sqrt_price in sample_sqrt_prices {
+        ensure!(
+            (sqrt_start_price..=migration_sqrt_price).contains(&sqrt_price),
+            "sample sqrt price {} is out of the curve range",
+            sqrt_price
+        );
+        points.push((sqrt_price, false));
+    }
+    // a breakpoint sorts before a sample at the same sqrt price and is kept by dedup
+    points.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    points.dedup_by_key(|point| point.0);
+
+    let supply = config.get_pre_migration_supply()?;
+    let rows = points
+        .into_iter()
+        .map(|(sqrt_price, breakpoint)| {
+            let (base_sold, quote_raised) = get_amounts_to_sqrt_price(config, sqrt_price)?;
+            Ok(CurveRow {
+                sqrt_price,
+                breakpoint,
+                base_sold,
+                quote_raised,
+                price: get_price_from_sqrt_price(
+                    sqrt_price,
+                    config.token_decimal,
+                    token_quote_decimal,
+                ),
+                market_cap: get_market_cap_from_sqrt_price(
+                    sqrt_price,
+                    supply,
+                    config.token_decimal,
+                    token_quote_decimal,
+                ),
+            })
+        })
+        .collect::<Result<Vec<CurveRow>>>()?;
+
+    Ok(CurveTable { rows })
+}
+
+/// Base token bought and quote token paid to move the price from the start to `sqrt_price`
+fn get_amounts_to_sqrt_price(config: &PoolConfig, sqrt_price: u128) -> Result<(u64, u64)> {
+    let mut base_amount = U256::ZERO;
+    let mut quote_amount = U256::ZERO;
+    let mut lower_sqrt_price = config.sqrt_start_price;
+    for point in config.curve.iter() {
+        if point.sqrt_price == 0 || lower_sqrt_price >= sqrt_price {
+            break;
+        }
+        let upper_sqrt_price = point.sqrt_price.min(sqrt_price);
+        if point.liquidity > 0 && upper_sqrt_price > lower_sqrt_price {
+            base_amount += get_delta_amount_base_unsigned_256(
+                lower_sqrt_price,
+                upper_sqrt_price,
+                point.liquidity,
+                Rounding::Down,
+            )?;
+            quote_amount += get_delta_amount_quote_unsigned_256(
+                lower_sqrt_price,
+                upper_sqrt_price,
+                point.liquidity,
+                Rounding::Up,
+            )?;
+        }
+        lower_sqrt_price = point.sqrt_price;
+    }
+    Ok((
+        base_amount.try_into().context("base amount overflow")?,
+        quote_amount.try_into().context("quote amount overflow")?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::params::liquidity_distribution::LiquidityDistributionParameters;
+
+    use super::*;
+
+    fn get_config() -> PoolConfig {
+        let sqrt_start_price = 1u128 << 64;
+        let curve = [
+            LiquidityDistributionParameters {
+                sqrt_price: 2u128 << 64,
+                liquidity: 1_000_000u128 << 64,
+            },
+            LiquidityDistributionParameters {
+                sqrt_price: 4u128 << 64,
+                liquidity: 2_000_000u128 << 64,
+            },
+        ];
+        let mut config = PoolConfig {
+            token_decimal: 6,
+            sqrt_start_price,
+            migration_sqrt_price: 3u128 << 64,
+            fixed_token_supply_flag: 1,
+            pre_migration_token_supply: 10_000_000,
+            post_migration_token_supply: 10_000_000,
+            ..Default::default()
+        };
+        for (i, point) in curve.iter().enumerate() {
+            config.curve[i] = point.to_liquidity_distribution_config();
+        }
+        config
+    }
+
+    #[test]
+    fn test_curve_table() {
+        let config = get_config();
+        let table = get_curve_table(&config, 6, &[3u128 << 63, 2u128 << 64]).unwrap();
+        let sqrt_prices: Vec<u128> = table.rows.iter().map(|row| row.sqrt_price).collect();
+        assert_eq!(
+            sqrt_prices,
+            vec![1u128 << 64, 3u128 << 63, 2u128 << 64, 3u128 << 64]
+        );
+        let breakpoints: Vec<bool> = table.rows.iter().map(|row| row.breakpoint).collect();
+        assert_eq!(breakpoints, vec![true, false, true, true]);
+
+        // L * (1 / √P_lower - 1 / √P_upper) and L * (√P_upper - √P_lower)
+        assert_eq!(table.rows[0].base_sold, 0);
+        assert_eq!(table.rows[0].quote_raised, 0);
+        assert_eq!(table.rows[2].base_sold, 500_000);
+        assert_eq!(table.rows[2].quote_raised, 1_000_000);
+        assert_eq!(table.rows[3].base_sold, 500_000 + 333_333);
+        assert_eq!(table.rows[3].quote_raised, 1_000_000 + 2_000_000);
+        assert_eq!(table.rows[3].price, 9.0);
+        assert_eq!(table.rows[3].market_cap, 90.0);
+
+        assert!(get_curve_table(&config, 6, &[5u128 << 64]).is_err());
+    }
+
+    #[test]
+    fn test_curve_table_export() {
+        let table = get_curve_table(&get_config(), 6, &[]).unwrap();
+        let csv = table.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "sqrt_price,breakpoint,base_sold,quote_raised,price,market_cap"
+        );
+        assert_eq!(lines[1], "18446744073709551616,true,0,0,1,10");
+
+        let json: serde_json::Value = serde_json::from_str(&table.to_json().unwrap()).unwrap();
+        let row = &json["rows"][2];
+        assert_eq!(row["sqrt_price"], "55340232221128654848");
+        assert_eq!(row["base_sold"], 833_333);
+        assert_eq!(row["quote_raised"], 3_000_000);
+    }
+}
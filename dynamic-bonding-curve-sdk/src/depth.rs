@@ -0,0 +1,364 @@
+//! Pool depth queries for a trading UI.
+//!
+//! Amounts are solved from the curve directly instead of searching over `quote_exact_in`.
+//! Input amounts include trading fees but not the token 2022 transfer fee. Buys are quoted as
+//! through `swap_with_buy_tracker`, so a per wallet limit doesn't fail them.
+use anyhow::{bail, ensure, Context, Result};
+use dynamic_bonding_curve::{
+    activation_handler::ActivationType,
+    constants::BASIS_POINT_MAX,
+    params::swap::TradeDirection,
+    state::{fee::FeeMode, PoolConfig, SwapResult2, VirtualPool},
+};
+use ruint::aliases::U256;
+
+use crate::{
+    design::sqrt_u256,
+    quote::{quote_exact_in, quote_exact_in_with_sqrt_price_limit},
+};
+
+/// Max units added to the fee inverted quote amount to reach the migration quote threshold
+const MAX_FEE_ROUNDING_UNITS: u64 = 8;
+
+/// Price impacts shown next to a pool, 1%, 2%, 5% and 10%
+pub const DEFAULT_PRICE_IMPACT_BPS: [u16; 4] = [100, 200, 500, 1_000];
+
+#[derive(Debug)]
+pub struct GraduationQuote {
+    /// quote token to send, trading fee included
+    pub quote_amount: u64,
+    /// base token received
+    pub base_amount: u64,
+    pub swap_result: SwapResult2,
+}
+
+/// Buy that brings the quote reserve to `migration_quote_threshold` and completes the curve
+pub fn get_amount_to_graduate(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    current_timestamp: u64,
+    current_slot: u64,
+    has_referral: bool,
+) -> Result<GraduationQuote> {
+    ensure!(
+        !virtual_pool.is_curve_complete(config.migration_quote_threshold),
+        "virtual pool is completed"
+    );
+    let remaining_quote_amount = config
+        .migration_quote_threshold
+        .checked_sub(virtual_pool.quote_reserve)
+        .context("quote reserve is above the migration quote threshold")?;
+
+    let mut pool = *virtual_pool;
+    pool.update_pre_swap(config, current_timestamp)?;
+    let activation_type =
+        ActivationType::try_from(config.activation_type).context("invalid activation type")?;
+    let current_point = match activation_type {
+        ActivationType::Slot => current_slot,
+        ActivationType::Timestamp => current_timestamp,
+    };
+    let fee_mode = FeeMode::get_fee_mode(
+        config.collect_fee_mode,
+        TradeDirection::QuoteToBase,
+        has_referral,
+    )?;
+    let mut quote_amount = if fee_mode.fees_on_input {
+        config
+            .pool_fees
+            .get_fee_on_excluded_amount(
+                &pool.volatility_tracker,
+                remaining_quote_amount,
+                has_referral,
+                current_point,
+                pool.activation_point,
+                TradeDirection::QuoteToBase,
+            )?
+            .amount
+    } else {
+        remaining_quote_amount
+    };
+
+    // fee rounding can leave the inverted amount a few units short of the threshold
+    for _ in 0..=MAX_FEE_ROUNDING_UNITS {
+        let swap_result = quote_exact_in(
+            virtual_pool,
+            config,
+            false,
+            current_timestamp,
+            current_slot,
+            quote_amount,
+            has_referral,
+            true,
+        )?;
+        if swap_result.actual_input_amount >= remaining_quote_amount {
+            return Ok(GraduationQuote {
+                quote_amount,
+                base_amount: swap_result.output_amount,
+                swap_result,
+            });
+        }
+        quote_amount = quote_amount
+            .checked_add(1)
+            .context("quote amount overflow")?;
+    }
+    bail!("quote amount to graduate not found")
+}
+
+/// Swap that moves the pool price to `sqrt_price`, a buy above the current price and a sell
+/// below it. The input to send is `consumed_input_amount`.
+///
+/// The target must stay between the start and the migration sqrt price. With the exact fill
+/// migration mode a buy stops at the migration quote threshold, `next_sqrt_price` is then
+/// below the target.
+pub fn get_swap_to_sqrt_price(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    sqrt_price: u128,
+    current_timestamp: u64,
+    current_slot: u64,
+    has_referral: bool,
+) -> Result<SwapResult2> {
+    ensure!(
+        sqrt_price != virtual_pool.sqrt_price,
+        "pool is already at the target sqrt price"
+    );
+    let swap_base_for_quote = sqrt_price < virtual_pool.sqrt_price;
+    if swap_base_for_quote {
+        ensure!(
+            sqrt_price >= config.sqrt_start_price,
+            "target sqrt price is below the start sqrt price"
+        );
+    } else {
+        ensure!(
+            sqrt_price <= config.migration_sqrt_price,
+            "target sqrt price is above the migration sqrt price"
+        );
+    }
+
+    // the price limit stops the swap, the unused input is refunded
+    quote_exact_in_with_sqrt_price_limit(
+        virtual_pool,
+        config,
+        swap_base_for_quote,
+        current_timestamp,
+        current_slot,
+        u64::MAX,
+        has_referral,
+        true,
+        Some(sqrt_price),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthLevel {
+    pub price_impact_bps: u16,
+    /// pool sqrt price after the swap
+    pub sqrt_price: u128,
+    /// input to send, trading fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Swap size that moves the pool price by each of `price_impact_bps`, up for buys and down
+/// for sells. Levels the pool can't reach before migration or below the start price are
+/// left out.
+#[allow(clippy::too_many_arguments)]
+pub fn get_depth(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    swap_base_for_quote: bool,
+    current_timestamp: u64,
+    current_slot: u64,
+    has_referral: bool,
+    price_impact_bps: &[u16],
+) -> Result<Vec<DepthLevel>> {
+    let mut levels = Vec::with_capacity(price_impact_bps.len());
+    for &bps in price_impact_bps {
+        ensure!(bps > 0, "price impact is zero");
+        let price_factor = if swap_base_for_quote {
+            ensure!(
+                u64::from(bps) < BASIS_POINT_MAX,
+                "price impact of a sell must be below 100%"
+            );
+            BASIS_POINT_MAX - u64::from(bps)
+        } else {
+            BASIS_POINT_MAX + u64::from(bps)
+        };
+        // price = sqrt_price², so the target sqrt price is sqrt_price * √(1 ± impact)
+        let sqrt_price = U256::from(virtual_pool.sqrt_price);
+        let target_sqrt_price: u128 = sqrt_u256(
+            sqrt_price * sqrt_price * U256::from(price_factor) / U256::from(BASIS_POINT_MAX),
+        )
+        .try_into()
+        .context("sqrt price overflow")?;
+
+        let reachable = if swap_base_for_quote {
+            target_sqrt_price >= config.sqrt_start_price
+        } else {
+            target_sqrt_price <= config.migration_sqrt_price
+        };
+        if !reachable {
+            continue;
+        }
+        let swap_result = get_swap_to_sqrt_price(
+            virtual_pool,
+            config,
+            target_sqrt_price,
+            current_timestamp,
+            current_slot,
+            has_referral,
+        )?;
+        if swap_result.next_sqrt_price != target_sqrt_price {
+            continue;
+        }
+        levels.push(DepthLevel {
+            price_impact_bps: bps,
+            sqrt_price: target_sqrt_price,
+            amount_in: swap_result.consumed_input_amount,
+            amount_out: swap_result.output_amount,
+        });
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::{
+        params::liquidity_distribution::LiquidityDistributionParameters, state::CollectFeeMode,
+    };
+
+    use super::*;
+
+    const TIMESTAMP: u64 = 1_700_000_000;
+
+    fn get_config_and_pool() -> (PoolConfig, VirtualPool) {
+        let curve = [
+            LiquidityDistributionParameters {
+                sqrt_price: 2u128 << 64,
+                liquidity: 1_000_000u128 << 64,
+            },
+            LiquidityDistributionParameters {
+                sqrt_price: 4u128 << 64,
+                liquidity: 2_000_000u128 << 64,
+            },
+        ];
+        let mut config = PoolConfig {
+            sqrt_start_price: 1u128 << 64,
+            migration_sqrt_price: 3u128 << 64,
+            // quote raised from the start to the migration sqrt price
+            migration_quote_threshold: 3_000_000,
+            collect_fee_mode: CollectFeeMode::QuoteToken.into(),
+            ..Default::default()
+        };
+        // 1%
+        config.pool_fees.base_fee.cliff_fee_numerator = 10_000_000;
+        for (i, point) in curve.iter().enumerate() {
+            config.curve[i] = point.to_liquidity_distribution_config();
+        }
+        let pool = VirtualPool {
+            sqrt_price: config.sqrt_start_price,
+            base_reserve: 1_000_000,
+            ..Default::default()
+        };
+        (config, pool)
+    }
+
+    #[test]
+    fn test_amount_to_graduate() {
+        let (config, pool) = get_config_and_pool();
+        let quote = get_amount_to_graduate(&pool, &config, TIMESTAMP, 0, false).unwrap();
+        // 3_000_000 / 0.99 rounded up
+        assert_eq!(quote.quote_amount, 3_030_304);
+        assert_eq!(quote.swap_result.actual_input_amount, 3_000_000);
+        assert_eq!(quote.base_amount, 500_000 + 333_333);
+
+        let short = quote_exact_in(
+            &pool,
+            &config,
+            false,
+            TIMESTAMP,
+            0,
+            quote.quote_amount - 1,
+            false,
+            true,
+        )
+        .unwrap();
+        assert!(short.actual_input_amount < config.migration_quote_threshold);
+
+        let completed = VirtualPool {
+            quote_reserve: config.migration_quote_threshold,
+            ..pool
+        };
+        assert!(get_amount_to_graduate(&completed, &config, TIMESTAMP, 0, false).is_err());
+    }
+
+    #[test]
+    fn test_amount_to_graduate_under_rate_limiter() {
+        let (mut config, pool) = get_config_and_pool();
+        // 1% on the first 1_000_000 quote, 1% more on each next one
+        config.pool_fees.base_fee.fee_scheduler_mode = 2;
+        config.pool_fees.base_fee.number_of_period = 100;
+        config.pool_fees.base_fee.period_frequency = 100;
+        config.pool_fees.base_fee.reduction_factor = 1_000_000;
+
+        let quote = get_amount_to_graduate(&pool, &config, TIMESTAMP, 0, false).unwrap();
+        assert_eq!(quote.swap_result.actual_input_amount, 3_000_000);
+        let short = quote_exact_in(
+            &pool,
+            &config,
+            false,
+            TIMESTAMP,
+            0,
+            quote.quote_amount - 1,
+            false,
+            true,
+        )
+        .unwrap();
+        assert!(short.actual_input_amount < config.migration_quote_threshold);
+    }
+
+    #[test]
+    fn test_swap_to_sqrt_price() {
+        let (config, pool) = get_config_and_pool();
+        let buy = get_swap_to_sqrt_price(&pool, &config, 2u128 << 64, TIMESTAMP, 0, false).unwrap();
+        assert_eq!(buy.next_sqrt_price, 2u128 << 64);
+        assert_eq!(buy.actual_input_amount, 1_000_000);
+        // 1_000_000 / 0.99 rounded up
+        assert_eq!(buy.consumed_input_amount, 1_010_102);
+        assert_eq!(buy.output_amount, 500_000);
+
+        assert!(get_swap_to_sqrt_price(&pool, &config, 4u128 << 64, TIMESTAMP, 0, false).is_err());
+        assert!(get_swap_to_sqrt_price(&pool, &config, 1u128 << 63, TIMESTAMP, 0, false).is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        let (config, mut pool) = get_config_and_pool();
+        pool.sqrt_price = 2u128 << 64;
+        pool.quote_reserve = 1_000_000;
+
+        let buys = get_depth(
+            &pool,
+            &config,
+            false,
+            TIMESTAMP,
+            0,
+            false,
+            &DEFAULT_PRICE_IMPACT_BPS,
+        )
+        .unwrap();
+        assert_eq!(buys.len(), 4);
+        for pair in buys.windows(2) {
+            assert!(pair[0].sqrt_price < pair[1].sqrt_price);
+            assert!(pair[0].amount_in < pair[1].amount_in);
+            assert!(pair[0].amount_out < pair[1].amount_out);
+        }
+
+        // the price can't drop more than 75% before reaching the start price
+        let sells = get_depth(&pool, &config, true, TIMESTAMP, 0, false, &[1_000, 8_000]).unwrap();
+        assert_eq!(sells.len(), 1);
+        assert_eq!(sells[0].price_impact_bps, 1_000);
+        assert!(sells[0].sqrt_price < pool.sqrt_price);
+        assert!(sells[0].amount_out < pool.quote_reserve);
+    }
+}
@@ -0,0 +1,475 @@
+//! Design a curve from market cap targets.
+//!
+//! The first segment sells `percentage_supply_on_curve` of the supply between the initial and
+//! the migration market cap, the migration quote threshold is the quote collected on that
+//! segment. The supply left after the swap, migration and vesting amounts is put in a last
+//! segment up to `MAX_SQRT_PRICE`, so buyers can still swap over the migration threshold.
+use anyhow::{ensure, Context, Result};
+use dynamic_bonding_curve::{
+    constants::{MAX_SQRT_PRICE, MIN_SQRT_PRICE},
+    curve::{get_delta_amount_quote_unsigned, get_initial_liquidity_from_delta_base},
+    params::liquidity_distribution::{
+        get_base_token_for_swap, get_migration_base_token, get_migration_threshold_price,
+        LiquidityDistributionParameters,
+    },
+    state::{MigrationOption, PoolConfig},
+    u128x128_math::Rounding,
+    ConfigParameters, LockedVestingParams, TokenSupplyParams,
+};
+use ruint::aliases::U256;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CurveDesignParams {
+    /// total supply, without decimals
+    pub total_token_supply: u64,
+    /// percentage of the total supply sold on the curve before migration
+    pub percentage_supply_on_curve: u8,
+    /// market cap at the start price, in whole quote token (e.g. 30.5 SOL), `token_quote_decimal`
+    /// is applied to get the amount in the smallest unit
+    pub initial_market_cap: f64,
+    /// market cap at the migration price, in whole quote token
+    pub migration_market_cap: f64,
+    pub token_base_decimal: u8,
+    pub token_quote_decimal: u8,
+    pub migration_option: MigrationOption,
+    pub migration_fee_percentage: u8,
+    pub locked_vesting: LockedVestingParams,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveDesign {
+    pub sqrt_start_price: u128,
+    pub curve: Vec<LiquidityDistributionParameters>,
+    pub migration_quote_threshold: u64,
+    /// fixed supply, pre and post migration supply are the same
+    pub token_supply: TokenSupplyParams,
+    pub sqrt_migration_price: u128,
+    pub swap_base_amount: u64,
+    pub migration_base_amount: u64,
+}
+
+impl CurveDesign {
+    /// Set the curve related fields of config parameters, fees and lp percentages are kept
+    pub fn apply_to(&self, params: &CurveDesignParams, config_parameters: &mut ConfigParameters) {
+        config_parameters.sqrt_start_price = self.sqrt_start_price;
+        config_parameters.curve = self.curve.clone();
+        config_parameters.migration_quote_threshold = self.migration_quote_threshold;
+        config_parameters.token_supply = Some(self.token_supply);
+        config_parameters.token_decimal = params.token_base_decimal;
+        config_parameters.migration_option = params.migration_option.into();
+        config_parameters.migration_fee.fee_percentage = params.migration_fee_percentage;
+        config_parameters.locked_vesting = params.locked_vesting;
+    }
+}
+
+pub fn design_curve(params: &CurveDesignParams) -> Result<CurveDesign> {
+    let CurveDesignParams {
+        total_token_supply,
+        percentage_supply_on_curve,
+        initial_market_cap,
+        migration_market_cap,
+        token_base_decimal,
+        token_quote_decimal,
+        migration_option,
+        migration_fee_percentage,
+        locked_vesting,
+    } = *params;
+    ensure!(
+        (6..=9).contains(&token_base_decimal),
+        "token decimal must be between 6 and 9"
+    );
+    ensure!(
+        percentage_supply_on_curve > 0 && percentage_supply_on_curve < 100,
+        "percentage of supply on curve must be between 0 and 100"
+    );
+    ensure!(
+        initial_market_cap > 0.0 && initial_market_cap < migration_market_cap,
+        "migration market cap must be greater than initial market cap"
+    );
+
+    let total_supply = total_token_supply
+        .checked_mul(10u64.pow(token_base_decimal.into()))
+        .context("total supply overflow")?;
+    let sqrt_start_price =
+        get_sqrt_price_from_market_cap(initial_market_cap, total_supply, token_quote_decimal)?;
+    let sqrt_max_curve_price =
+        get_sqrt_price_from_market_cap(migration_market_cap, total_supply, token_quote_decimal)?;
+    ensure!(
+        sqrt_start_price >= MIN_SQRT_PRICE && sqrt_max_curve_price < MAX_SQRT_PRICE,
+        "market caps are out of price range"
+    );
+
+    // first segment sells the swap amount between the initial and migration market cap
+    let swap_amount =
+        u64::try_from(u128::from(total_supply) * u128::from(percentage_supply_on_curve) / 100)?;
+    let liquidity =
+        get_initial_liquidity_from_delta_base(swap_amount, sqrt_max_curve_price, sqrt_start_price)?;
+    let liquidity = u128::try_from(liquidity).context("liquidity overflow")?;
+    let migration_quote_threshold = get_delta_amount_quote_unsigned(
+        sqrt_start_price,
+        sqrt_max_curve_price,
+        liquidity,
+        // same rounding as `get_migration_threshold_price`, so the migration price is exactly
+        // the end of the first segment
+        Rounding::Up,
+    )?;
+    ensure!(
+        migration_quote_threshold > 0,
+        "migration quote threshold is zero"
+    );
+    let mut curve = vec![LiquidityDistributionParameters {
+        sqrt_price: sqrt_max_curve_price,
+        liquidity,
+    }];
+
+    let sqrt_migration_price =
+        get_migration_threshold_price(migration_quote_threshold, sqrt_start_price, &curve)?;
+    let swap_base_amount: u64 =
+        get_base_token_for_swap(sqrt_start_price, sqrt_migration_price, &curve)?
+            .try_into()
+            .context("swap base amount overflow")?;
+    let migration_base_amount = get_migration_base_token(
+        migration_quote_threshold,
+        migration_fee_percentage,
+        sqrt_migration_price,
+        migration_option,
+    )?;
+
+    // whole first segment is the maximum swap amount with buffer
+    let curve_base_amount =
+        PoolConfig::get_swap_amount_with_buffer(swap_base_amount, sqrt_start_price, &curve)?;
+    let minimum_supply = PoolConfig::get_total_token_supply(
+        curve_base_amount,
+        migration_base_amount,
+        &locked_vesting,
+    )?;
+    ensure!(
+        minimum_supply <= total_supply,
+        "total supply {} is less than required supply {}",
+        total_supply,
+        minimum_supply
+    );
+
+    // put the rest of the supply above the migration price
+    let leftover = total_supply - minimum_supply;
+    if leftover > 0 {
+        let liquidity =
+            get_initial_liquidity_from_delta_base(leftover, MAX_SQRT_PRICE, sqrt_max_curve_price)?;
+        let liquidity = u128::try_from(liquidity).context("liquidity overflow")?;
+        if liquidity > 0 {
+            curve.push(LiquidityDistributionParameters {
+                sqrt_price: MAX_SQRT_PRICE,
+                liquidity,
+            });
+        }
+    }
+
+    let token_supply = TokenSupplyParams {
+        pre_migration_token_supply: total_supply,
+        post_migration_token_supply: total_supply,
+    };
+    validate_token_supply(
+        sqrt_start_price,
+        &curve,
+        swap_base_amount,
+        migration_base_amount,
+        &locked_vesting,
+        &token_supply,
+    )?;
+
+    Ok(CurveDesign {
+        sqrt_start_price,
+        curve,
+        migration_quote_threshold,
+        token_supply,
+        sqrt_migration_price,
+        swap_base_amount,
+        migration_base_amount,
+    })
+}
+
+/// Same supply check as `create_config` for fixed supply tokens
+fn validate_token_supply(
+    sqrt_start_price: u128,
+    curve: &[LiquidityDistributionParameters],
+    swap_base_amount: u64,
+    migration_base_amount: u64,
+    locked_vesting: &LockedVestingParams,
+    token_supply: &TokenSupplyParams,
+) -> Result<()> {
+    let swap_base_amount_buffer =
+        PoolConfig::get_swap_amount_with_buffer(swap_base_amount, sqrt_start_price, curve)?;
+    let minimum_base_supply_with_buffer = PoolConfig::get_total_token_supply(
+        swap_base_amount_buffer,
+        migration_base_amount,
+        locked_vesting,
+    )?;
+    let minimum_base_supply_without_buffer = PoolConfig::get_total_token_supply(
+        swap_base_amount,
+        migration_base_amount,
+        locked_vesting,
+    )?;
+    ensure!(
+        minimum_base_supply_without_buffer <= token_supply.post_migration_token_supply
+            && token_supply.post_migration_token_supply <= token_supply.pre_migration_token_supply
+            && minimum_base_supply_with_buffer <= token_supply.pre_migration_token_supply,
+        "invalid token supply"
+    );
+    Ok(())
+}
+
+/// price = market_cap / total_supply, sqrt_price = sqrt(price) << 64
+fn get_sqrt_price_from_market_cap(
+    market_cap: f64,
+    total_supply: u64,
+    token_quote_decimal: u8,
+) -> Result<u128> {
+    // market cap in the smallest quote unit, below 2^128 so the shift below can't overflow
+    let market_cap = (market_cap * 10f64.powi(token_quote_decimal.into())).round();
+    ensure!(
+        market_cap >= 1.0 && market_cap < u128::MAX as f64,
+        "market cap is out of range"
+    );
+    let price_x128 = (U256::from(market_cap as u128) << 128) / U256::from(total_supply);
+    sqrt_u256(price_x128)
+        .try_into()
+        .context("sqrt price overflow")
+}
+
+pub(crate) fn sqrt_u256(value: U256) -> U256 {
+    if value.is_zero() {
+        return U256::ZERO;
+    }
+    // start above the root, newton iterations decrease until the floor of the root
+    let mut x = U256::from(1) << value.bit_len().div_ceil(2);
+    loop {
+        let y = (x + value / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::*;
+    use anchor_spl::{
+        token::spl_token::{self, solana_program::program_pack::Pack},
+        token_interface::Mint,
+    };
+    use dynamic_bonding_curve::{
+        constants::MAX_CURVE_POINT,
+        params::{
+            fee_parameters::{BaseFeeParameters, PoolFeeParameters},
+            swap::TradeDirection,
+        },
+        state::{fee::FeeMode, CollectFeeMode, VirtualPool},
+        MigrationFee,
+    };
+
+    use super::*;
+
+    fn get_params(
+        migration_option: MigrationOption,
+        locked_vesting: LockedVestingParams,
+    ) -> CurveDesignParams {
+        CurveDesignParams {
+            total_token_supply: 1_000_000_000,
+            percentage_supply_on_curve: 70,
+            initial_market_cap: 30.0,
+            migration_market_cap: 300.0,
+            token_base_decimal: 6,
+            token_quote_decimal: 9,
+            migration_option,
+            migration_fee_percentage: 0,
+            locked_vesting,
+        }
+    }
+
+    #[test]
+    fn test_sqrt_u256() {
+        for value in [1u128, 2, 3, 4, 99, 100, 101, u64::MAX as u128, u128::MAX] {
+            let root = sqrt_u256(U256::from(value));
+            assert!(root * root <= U256::from(value));
+            assert!((root + U256::from(1)) * (root + U256::from(1)) > U256::from(value));
+        }
+    }
+
+    #[test]
+    fn test_design_curve() {
+        let locked_vesting = LockedVestingParams {
+            amount_per_period: 1_000_000,
+            frequency: 1,
+            number_of_period: 120,
+            cliff_unlock_amount: 1_000_000,
+            ..Default::default()
+        };
+        for migration_option in [MigrationOption::MeteoraDamm, MigrationOption::DammV2] {
+            for locked_vesting in [locked_vesting, LockedVestingParams::default()] {
+                let params = get_params(migration_option, locked_vesting);
+                let design = design_curve(&params).unwrap();
+                assert_eq!(design.curve.len(), 2);
+                assert!(design.curve.len() <= MAX_CURVE_POINT);
+                assert!(design.curve[0].sqrt_price > design.sqrt_start_price);
+                assert_eq!(design.curve[1].sqrt_price, MAX_SQRT_PRICE);
+                assert_eq!(design.sqrt_migration_price, design.curve[0].sqrt_price);
+
+                // 70% of supply is sold on the curve
+                let sold_on_curve = 700_000_000_000_000u64;
+                assert!(design.swap_base_amount.abs_diff(sold_on_curve) <= 1);
+                // quote collected is between the initial and migration market cap of the sold supply
+                assert!(design.migration_quote_threshold > 30_000_000_000);
+                assert!(design.migration_quote_threshold < 300_000_000_000);
+
+                // buying the migration threshold completes the curve and leaves enough
+                // base token for migration and vesting
+                let mut config = PoolConfig {
+                    migration_quote_threshold: design.migration_quote_threshold,
+                    sqrt_start_price: design.sqrt_start_price,
+                    collect_fee_mode: CollectFeeMode::QuoteToken.into(),
+                    ..Default::default()
+                };
+                for (i, point) in design.curve.iter().enumerate() {
+                    config.curve[i] = point.to_liquidity_distribution_config();
+                }
+                let mut pool = VirtualPool {
+                    sqrt_price: design.sqrt_start_price,
+                    base_reserve: design.token_supply.pre_migration_token_supply,
+                    ..Default::default()
+                };
+                let fee_mode = FeeMode::get_fee_mode(
+                    config.collect_fee_mode,
+                    TradeDirection::QuoteToBase,
+                    false,
+                )
+                .unwrap();
+                let swap_result = pool
+                    .get_swap_result(
+                        &config,
+                        design.migration_quote_threshold,
+                        &fee_mode,
+                        TradeDirection::QuoteToBase,
+                        0,
+                    )
+                    .unwrap();
+                pool.apply_swap_result(
+                    &config,
+                    &swap_result,
+                    &fee_mode,
+                    TradeDirection::QuoteToBase,
+                    0,
+                )
+                .unwrap();
+                assert!(pool.is_curve_complete(design.migration_quote_threshold));
+                assert!(
+                    pool.base_reserve
+                        >= design.migration_base_amount
+                            + locked_vesting.get_total_amount().unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_design_curve_passes_config_validation() {
+        let params = get_params(MigrationOption::DammV2, LockedVestingParams::default());
+        let mut config_parameters = ConfigParameters {
+            pool_fees: PoolFeeParameters {
+                base_fee: BaseFeeParameters {
+                    cliff_fee_numerator: 2_500_000,
+                    ..Default::default()
+                },
+                dynamic_fee: None,
+            },
+            collect_fee_mode: 0,
+            migration_option: 0,
+            activation_type: 0,
+            token_type: 0,
+            token_decimal: 0,
+            partner_lp_percentage: 0,
+            partner_locked_lp_percentage: 100,
+            creator_lp_percentage: 0,
+            creator_locked_lp_percentage: 0,
+            migration_quote_threshold: 0,
+            sqrt_start_price: 0,
+            locked_vesting: LockedVestingParams::default(),
+            migration_fee_option: 0,
+            token_supply: None,
+            creator_trading_fee_percentage: 0,
+            token_update_authority: 0,
+            migration_fee: MigrationFee::default(),
+            migration_fill_mode: 0,
+            max_activation_delay: 0,
+            buy_limit_duration: 0,
+            max_buy_quote_per_swap: 0,
+            max_buy_quote_per_wallet: 0,
+            padding_0: [0; 3],
+            padding_1: [0; 3],
+            curve: vec![],
+        };
+        design_curve(&params)
+            .unwrap()
+            .apply_to(&params, &mut config_parameters);
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            decimals: params.token_quote_decimal,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &spl_token::ID,
+            false,
+            0,
+        );
+        let quote_mint = InterfaceAccount::<Mint>::try_from(&account_info).unwrap();
+        config_parameters.validate(&quote_mint).unwrap();
+    }
+
+    #[test]
+    fn test_design_curve_fractional_market_cap() {
+        let mut params = get_params(MigrationOption::DammV2, LockedVestingParams::default());
+        params.initial_market_cap = 30.5;
+        let design = design_curve(&params).unwrap();
+        let whole_cap_design = design_curve(&get_params(
+            MigrationOption::DammV2,
+            LockedVestingParams::default(),
+        ))
+        .unwrap();
+        assert!(design.sqrt_start_price > whole_cap_design.sqrt_start_price);
+        assert!(design.migration_quote_threshold > whole_cap_design.migration_quote_threshold);
+
+        // below the smallest quote unit
+        params.initial_market_cap = 1e-10;
+        assert!(design_curve(&params).is_err());
+    }
+
+    #[test]
+    fn test_design_curve_invalid_params() {
+        let mut params = get_params(MigrationOption::DammV2, LockedVestingParams::default());
+        params.migration_market_cap = params.initial_market_cap;
+        assert!(design_curve(&params).is_err());
+
+        // vesting more than the supply left after the curve
+        let mut params = get_params(
+            MigrationOption::DammV2,
+            LockedVestingParams {
+                cliff_unlock_amount: 300_000_000_000_000,
+                frequency: 1,
+                ..Default::default()
+            },
+        );
+        assert!(design_curve(&params).is_err());
+        params.percentage_supply_on_curve = 50;
+        assert!(design_curve(&params).is_ok());
+    }
+}
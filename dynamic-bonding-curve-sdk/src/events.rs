@@ -0,0 +1,241 @@
+//! Decode program events.
+//!
+//! Events are emitted with `emit_cpi!`, as data of a self cpi instruction prefixed by
+//! `EVENT_IX_TAG_LE`. Events emitted with `emit!` are found in "Program data:" log lines instead,
+//! both are supported.
+use anchor_lang::{event::EVENT_IX_TAG_LE, prelude::*, Discriminator};
+use anyhow::{bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use dynamic_bonding_curve::{
+    EvtClaimCreatorTradingFee, EvtClaimProtocolFee, EvtClaimTradingFee, EvtCloseClaimFeeOperator,
+    EvtCreateClaimFeeOperator, EvtCreateConfig, EvtCreateDammV2MigrationMetadata,
+    EvtCreateMeteoraMigrationMetadata, EvtCreatorWithdrawSurplus, EvtCurveComplete,
+    EvtInitializePool, EvtPartnerMetadata, EvtPartnerWithdrawMigrationFee,
+    EvtPartnerWithdrawSurplus, EvtProtocolWithdrawSurplus, EvtSwap, EvtSwapExactOut,
+    EvtSwapWithPriceLimit, EvtUpdatePoolCreator, EvtVirtualPoolMetadata, EvtWithdrawLeftover,
+    EvtWithdrawMigrationFee,
+};
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+macro_rules! dbc_events {
+    ($($variant:ident($event:ident)),* $(,)?) => {
+        /// All events of the dynamic bonding curve program
+        pub enum DbcEvent {
+            $($variant($event),)*
+        }
+
+        impl DbcEvent {
+            /// Decode event data, starting with the event discriminator
+            pub fn decode(data: &[u8]) -> Result<Self> {
+                $(
+                    if data.starts_with($event::DISCRIMINATOR) {
+                        let mut event_data = &data[$event::DISCRIMINATOR.len()..];
+                        let event = $event::deserialize(&mut event_data)
+                            .context(concat!("invalid ", stringify!($event), " data"))?;
+                        return Ok(Self::$variant(event));
+                    }
+                )*
+                bail!("unknown event discriminator")
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => stringify!($event),)*
+                }
+            }
+        }
+    };
+}
+
+dbc_events!(
+    PartnerMetadata(EvtPartnerMetadata),
+    VirtualPoolMetadata(EvtVirtualPoolMetadata),
+    CreateConfig(EvtCreateConfig),
+    CreateClaimFeeOperator(EvtCreateClaimFeeOperator),
+    CloseClaimFeeOperator(EvtCloseClaimFeeOperator),
+    InitializePool(EvtInitializePool),
+    Swap(EvtSwap),
+    SwapExactOut(EvtSwapExactOut),
+    SwapWithPriceLimit(EvtSwapWithPriceLimit),
+    CurveComplete(EvtCurveComplete),
+    ClaimProtocolFee(EvtClaimProtocolFee),
+    ClaimTradingFee(EvtClaimTradingFee),
+    ClaimCreatorTradingFee(EvtClaimCreatorTradingFee),
+    CreateMeteoraMigrationMetadata(EvtCreateMeteoraMigrationMetadata),
+    CreateDammV2MigrationMetadata(EvtCreateDammV2MigrationMetadata),
+    ProtocolWithdrawSurplus(EvtProtocolWithdrawSurplus),
+    PartnerWithdrawSurplus(EvtPartnerWithdrawSurplus),
+    CreatorWithdrawSurplus(EvtCreatorWithdrawSurplus),
+    WithdrawLeftover(EvtWithdrawLeftover),
+    UpdatePoolCreator(EvtUpdatePoolCreator),
+    WithdrawMigrationFee(EvtWithdrawMigrationFee),
+    PartnerWithdrawMigrationFee(EvtPartnerWithdrawMigrationFee),
+);
+
+impl DbcEvent {
+    /// Decode data of an `emit_cpi!` inner instruction
+    pub fn decode_cpi_instruction_data(ix_data: &[u8]) -> Result<Self> {
+        ensure!(
+            ix_data.starts_with(EVENT_IX_TAG_LE),
+            "not an event cpi instruction"
+        );
+        Self::decode(&ix_data[EVENT_IX_TAG_LE.len()..])
+    }
+
+    /// Decode a "Program data:" log line, returns `None` for any other log line
+    pub fn decode_log(log: &str) -> Result<Option<Self>> {
+        let Some(encoded) = log.strip_prefix(PROGRAM_DATA_PREFIX) else {
+            return Ok(None);
+        };
+        let data = STANDARD
+            .decode(encoded.trim())
+            .context("invalid base64 program data")?;
+        Self::decode(&data).map(Some)
+    }
+}
+
+/// Decode events of inner instructions invoking the dynamic bonding curve program.
+/// Instructions that are not event cpi instructions are skipped
+pub fn decode_cpi_events<'a>(
+    inner_instructions: impl IntoIterator<Item = (&'a Pubkey, &'a [u8])>,
+) -> Result<Vec<DbcEvent>> {
+    inner_instructions
+        .into_iter()
+        .filter(|(program_id, ix_data)| {
+            **program_id == dynamic_bonding_curve::ID && ix_data.starts_with(EVENT_IX_TAG_LE)
+        })
+        .map(|(_, ix_data)| DbcEvent::decode_cpi_instruction_data(ix_data))
+        .collect()
+}
+
+/// Decode "Program data:" log lines of a transaction that were logged while the
+/// dynamic bonding curve program was executing, data logged by other programs is skipped
+pub fn decode_log_events<S: AsRef<str>>(logs: &[S]) -> Result<Vec<DbcEvent>> {
+    let program_id = dynamic_bonding_curve::ID.to_string();
+    let mut invoke_stack: Vec<&str> = vec![];
+    let mut events = vec![];
+    for log in logs {
+        let log = log.as_ref();
+        let mut parts = log.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("Program"), Some(id), Some("invoke")) => invoke_stack.push(id),
+            (Some("Program"), Some(_), Some("success" | "failed:")) => {
+                invoke_stack.pop();
+            }
+            _ => {
+                if invoke_stack.last() == Some(&program_id.as_str()) {
+                    if let Some(event) = DbcEvent::decode_log(log)? {
+                        events.push(event);
+                    }
+                }
+            }
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Event;
+
+    use super::*;
+
+    fn get_curve_complete_event() -> EvtCurveComplete {
+        EvtCurveComplete {
+            pool: Pubkey::new_unique(),
+            config: Pubkey::new_unique(),
+            base_reserve: 1_000,
+            quote_reserve: 2_000,
+        }
+    }
+
+    #[test]
+    fn test_decode_cpi_instruction_data() {
+        let event = get_curve_complete_event();
+        let mut ix_data = EVENT_IX_TAG_LE.to_vec();
+        ix_data.extend_from_slice(&event.data());
+
+        let decoded = DbcEvent::decode_cpi_instruction_data(&ix_data).unwrap();
+        assert_eq!(decoded.name(), "EvtCurveComplete");
+        let DbcEvent::CurveComplete(decoded) = decoded else {
+            panic!("wrong event");
+        };
+        assert_eq!(decoded.pool, event.pool);
+        assert_eq!(decoded.quote_reserve, event.quote_reserve);
+
+        // missing event tag
+        assert!(DbcEvent::decode_cpi_instruction_data(&event.data()).is_err());
+
+        let other_program = Pubkey::new_unique();
+        let swap_data = vec![0u8; 16];
+        let events = decode_cpi_events([
+            (&other_program, ix_data.as_slice()),
+            (&dynamic_bonding_curve::ID, swap_data.as_slice()),
+            (&dynamic_bonding_curve::ID, ix_data.as_slice()),
+        ])
+        .unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn test_decode_log_events() {
+        let event = EvtCreateClaimFeeOperator {
+            operator: Pubkey::new_unique(),
+        };
+        let data_log = format!("Program data: {}", STANDARD.encode(event.data()));
+        let program_id = dynamic_bonding_curve::ID;
+        let other_program = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {program_id} invoke [1]"),
+            "Program log: Instruction: CreateClaimFeeOperator".to_string(),
+            format!("Program {other_program} invoke [2]"),
+            // logged by another program, must be skipped
+            "Program data: AAAA".to_string(),
+            format!("Program {other_program} success"),
+            data_log.clone(),
+            format!("Program {program_id} success"),
+            data_log,
+        ];
+
+        let events = decode_log_events(&logs).unwrap();
+        assert_eq!(events.len(), 1);
+        let DbcEvent::CreateClaimFeeOperator(decoded) = &events[0] else {
+            panic!("wrong event");
+        };
+        assert_eq!(decoded.operator, event.operator);
+
+        assert!(DbcEvent::decode_log("Program data: AAAAAAAAAAA=").is_err());
+        assert!(DbcEvent::decode_log("Program log: hello")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_decode_swap_event_of_first_version() {
+        // EvtSwap of the first program version: pool, config, trade direction, has referral,
+        // params, swap result without consumed and refunded input, amount in and timestamp
+        let pool = Pubkey::new_unique();
+        let mut data = EvtSwap::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(pool.as_ref());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&[1, 0]);
+        for amount in [100u64, 90, 99, 95] {
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        data.extend_from_slice(&11666745337427033u128.to_le_bytes());
+        for amount in [1u64, 0, 0, 100, 1] {
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+
+        let DbcEvent::Swap(decoded) = DbcEvent::decode(&data).unwrap() else {
+            panic!("wrong event");
+        };
+        assert_eq!(decoded.pool, pool);
+        assert_eq!(decoded.swap_result.output_amount, 95);
+        assert_eq!(decoded.swap_result.referral_fee, 0);
+        assert_eq!(decoded.amount_in, 100);
+        assert_eq!(decoded.current_timestamp, 1);
+        assert_eq!(decoded.data(), data);
+    }
+}
@@ -0,0 +1,303 @@
+//! Project trading fees before a config is deployed.
+//!
+//! Points are in the activation type unit of the config, slots or seconds.
+use anyhow::{Context, Result};
+use dynamic_bonding_curve::{
+    params::swap::TradeDirection,
+    state::{fee::VolatilityTracker, BaseFeeConfig, PoolFeesConfig},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaseFeeProjection {
+    /// points passed since activation
+    pub elapsed: u64,
+    pub fee_numerator: u64,
+}
+
+/// Base fee numerator at each of `elapsed_points` after activation
+pub fn project_base_fee(
+    base_fee: &BaseFeeConfig,
+    elapsed_points: &[u64],
+) -> Result<Vec<BaseFeeProjection>> {
+    elapsed_points
+        .iter()
+        .map(|&elapsed| {
+            Ok(BaseFeeProjection {
+                elapsed,
+                fee_numerator: base_fee.get_base_fee_numerator(elapsed, 0)?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimiterFeeProjection {
+    /// quote amount of the buy
+    pub amount: u64,
+    pub fee_numerator: u64,
+}
+
+/// Base fee numerator of a buy of each of `amounts` while the rate limiter applies, None if the
+/// base fee isn't a rate limiter
+pub fn project_rate_limiter_fee(
+    base_fee: &BaseFeeConfig,
+    amounts: &[u64],
+) -> Result<Option<Vec<RateLimiterFeeProjection>>> {
+    let Some(rate_limiter) = base_fee.get_fee_rate_limiter()? else {
+        return Ok(None);
+    };
+    let projections = amounts
+        .iter()
+        .map(|&amount| {
+            Ok(RateLimiterFeeProjection {
+                amount,
+                fee_numerator: rate_limiter.get_fee_numerator_on_amount(amount)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Some(projections))
+}
+
+/// A trade moving the pool price to `sqrt_price`
+#[derive(Debug, Clone, Copy)]
+pub struct PriceStep {
+    pub timestamp: u64,
+    /// slot or timestamp, depending on the activation type
+    pub current_point: u64,
+    pub sqrt_price: u128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeStep {
+    pub timestamp: u64,
+    pub sqrt_price: u128,
+    /// fee charged on the trade of this step
+    pub base_fee_numerator: u64,
+    pub variable_fee_numerator: u64,
+    /// base and variable fee, capped at `MAX_FEE_NUMERATOR`
+    pub total_fee_numerator: u64,
+    /// volatility accumulator after the trade
+    pub volatility_accumulator: u128,
+}
+
+/// Feed `path` through the volatility tracker like swaps do, starting from a fresh pool at
+/// `sqrt_start_price`. Fees of a step are the ones charged on its trade, before the price moves.
+/// Trades are treated as sells, the rate limiter fee of buys is given by `project_rate_limiter_fee`
+pub fn simulate_fees(
+    pool_fees: &PoolFeesConfig,
+    activation_point: u64,
+    sqrt_start_price: u128,
+    path: &[PriceStep],
+) -> Result<Vec<FeeStep>> {
+    let dynamic_fee = &pool_fees.dynamic_fee;
+    let mut volatility_tracker = VolatilityTracker::default();
+    let mut sqrt_price = sqrt_start_price;
+    let mut steps = Vec::with_capacity(path.len());
+    for step in path {
+        if dynamic_fee.is_dynamic_fee_enable() {
+            volatility_tracker.update_references(dynamic_fee, sqrt_price, step.timestamp)?;
+        }
+
+        let base_fee_numerator = pool_fees
+            .base_fee
+            .get_base_fee_numerator(step.current_point, activation_point)?;
+        let variable_fee_numerator = dynamic_fee
+            .get_variable_fee_numerator(&volatility_tracker)?
+            .try_into()
+            .context("variable fee overflow")?;
+        let total_fee_numerator = pool_fees.get_total_trading_fee(
+            &volatility_tracker,
+            step.current_point,
+            activation_point,
+            0,
+            TradeDirection::BaseToQuote,
+        )?;
+
+        let old_sqrt_price = sqrt_price;
+        sqrt_price = step.sqrt_price;
+        if dynamic_fee.is_dynamic_fee_enable() {
+            volatility_tracker.update_volatility_accumulator(dynamic_fee, sqrt_price)?;
+            let delta_bin_id = VolatilityTracker::get_delta_bin_id(
+                dynamic_fee.bin_step_u128,
+                old_sqrt_price,
+                sqrt_price,
+            )?;
+            if delta_bin_id > 0 {
+                volatility_tracker.last_update_timestamp = step.timestamp;
+            }
+        }
+
+        steps.push(FeeStep {
+            timestamp: step.timestamp,
+            sqrt_price,
+            base_fee_numerator,
+            variable_fee_numerator,
+            total_fee_numerator,
+            volatility_accumulator: volatility_tracker.volatility_accumulator,
+        });
+    }
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::{
+        constants::{BIN_STEP_BPS_DEFAULT, BIN_STEP_BPS_U128_DEFAULT},
+        params::fee_parameters::{BaseFeeParameters, DynamicFeeParameters, PoolFeeParameters},
+        state::FeeSchedulerMode,
+    };
+
+    use super::*;
+
+    fn get_base_fee(fee_scheduler_mode: FeeSchedulerMode) -> BaseFeeParameters {
+        BaseFeeParameters {
+            cliff_fee_numerator: 500_000_000, // 50%
+            number_of_period: 60,
+            period_frequency: 60, // every minute
+            reduction_factor: match fee_scheduler_mode {
+                FeeSchedulerMode::Linear => 8_000_000,
+                FeeSchedulerMode::Exponential => 500, // 5% per period
+                FeeSchedulerMode::RateLimiter => {
+                    unreachable!("rate limiter has its own parameters")
+                }
+            },
+            fee_scheduler_mode: fee_scheduler_mode.into(),
+        }
+    }
+
+    #[test]
+    fn test_project_base_fee() {
+        let elapsed_points = [0, 59, 60, 5 * 60, 30 * 60, 60 * 60, 120 * 60];
+
+        let base_fee = get_base_fee(FeeSchedulerMode::Linear).to_base_fee_config();
+        let fees: Vec<u64> = project_base_fee(&base_fee, &elapsed_points)
+            .unwrap()
+            .iter()
+            .map(|projection| projection.fee_numerator)
+            .collect();
+        assert_eq!(
+            fees,
+            vec![
+                500_000_000,
+                500_000_000,
+                492_000_000,
+                460_000_000,
+                260_000_000,
+                20_000_000,
+                20_000_000
+            ]
+        );
+
+        let base_fee = get_base_fee(FeeSchedulerMode::Exponential).to_base_fee_config();
+        let projections = project_base_fee(&base_fee, &elapsed_points).unwrap();
+        for i in 1..projections.len() {
+            assert!(projections[i].fee_numerator <= projections[i - 1].fee_numerator);
+        }
+        // 0.5 * 0.95^5
+        assert!(projections[3].fee_numerator.abs_diff(386_890_468) < 1_000);
+        assert_eq!(projections[5].fee_numerator, projections[6].fee_numerator);
+    }
+
+    #[test]
+    fn test_project_rate_limiter_fee() {
+        let base_fee = BaseFeeParameters {
+            cliff_fee_numerator: 10_000_000, // 1%
+            number_of_period: 100,           // 1% more per reference amount
+            period_frequency: 600,
+            reduction_factor: 1_000_000_000, // 1 SOL
+            fee_scheduler_mode: FeeSchedulerMode::RateLimiter.into(),
+        }
+        .to_base_fee_config();
+        let fees: Vec<u64> = project_rate_limiter_fee(
+            &base_fee,
+            &[
+                1,
+                1_000_000_000,
+                2_000_000_000,
+                3_000_000_000,
+                1_000_000_000_000,
+            ],
+        )
+        .unwrap()
+        .unwrap()
+        .iter()
+        .map(|projection| projection.fee_numerator)
+        .collect();
+        // 1%, 1%, (1% + 2%) / 2, (1% + 2% + 3%) / 3, then 1% to 50% on the first 50 SOL and 50%
+        // on the rest
+        assert_eq!(
+            fees,
+            vec![10_000_000, 10_000_000, 15_000_000, 20_000_000, 487_750_000]
+        );
+
+        let base_fee = get_base_fee(FeeSchedulerMode::Linear).to_base_fee_config();
+        assert!(project_rate_limiter_fee(&base_fee, &[1]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_simulate_fees() {
+        let pool_fees = PoolFeeParameters {
+            base_fee: BaseFeeParameters {
+                cliff_fee_numerator: 10_000_000, // 1%
+                ..Default::default()
+            },
+            dynamic_fee: Some(DynamicFeeParameters {
+                bin_step: BIN_STEP_BPS_DEFAULT,
+                bin_step_u128: BIN_STEP_BPS_U128_DEFAULT,
+                filter_period: 10,
+                decay_period: 120,
+                reduction_factor: 5_000,
+                max_volatility_accumulator: 14_460_000,
+                variable_fee_control: 30_000,
+            }),
+        }
+        .to_pool_fees_config();
+
+        let activation_point = 1_700_000_000;
+        let sqrt_start_price = 1u128 << 64;
+        // price up 10% every 5 seconds, then a quiet period
+        let mut path = Vec::new();
+        let mut sqrt_price = sqrt_start_price;
+        for i in 0..5 {
+            sqrt_price = sqrt_price * 1049 / 1000;
+            path.push(PriceStep {
+                timestamp: activation_point + i * 5,
+                current_point: activation_point + i * 5,
+                sqrt_price,
+            });
+        }
+        path.push(PriceStep {
+            timestamp: activation_point + 1_000,
+            current_point: activation_point + 1_000,
+            sqrt_price,
+        });
+
+        let steps = simulate_fees(&pool_fees, activation_point, sqrt_start_price, &path).unwrap();
+        // the first trade sees no volatility yet
+        assert_eq!(steps[0].variable_fee_numerator, 0);
+        assert_eq!(steps[0].total_fee_numerator, 10_000_000);
+        assert!(steps[0].volatility_accumulator > 0);
+        for step in &steps[1..5] {
+            assert!(step.variable_fee_numerator > 0);
+            assert_eq!(
+                step.total_fee_numerator,
+                step.base_fee_numerator + step.variable_fee_numerator
+            );
+        }
+        // the accumulator of the last trade is still charged, then it resets after the decay
+        // period
+        assert!(steps[5].variable_fee_numerator > 0);
+        assert_eq!(steps[5].volatility_accumulator, 0);
+
+        // without dynamic fee only the base fee is charged
+        let mut pool_fees = pool_fees;
+        pool_fees.dynamic_fee.initialized = 0;
+        let steps = simulate_fees(&pool_fees, activation_point, sqrt_start_price, &path).unwrap();
+        assert!(
+            steps
+                .iter()
+                .all(|step| step.total_fee_numerator == 10_000_000
+                    && step.volatility_accumulator == 0)
+        );
+    }
+}
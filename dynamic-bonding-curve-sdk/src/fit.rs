@@ -0,0 +1,291 @@
+//! Approximate a price function with a piecewise constant liquidity curve.
+//!
+//! With constant liquidity, `Δbase = L * (1 / √P_lower - 1 / √P_upper)`, so `1 / √P` is linear in
+//! the sold supply within a segment. Each segment matches the target price at both ends, segments
+//! are split in half where the price deviation is the largest until the tolerance or
+//! `MAX_CURVE_POINT` is reached.
+use anyhow::{ensure, Context, Result};
+use dynamic_bonding_curve::{
+    constants::{MAX_CURVE_POINT, MAX_SQRT_PRICE, MIN_SQRT_PRICE},
+    curve::{get_delta_amount_quote_unsigned, get_initial_liquidity_from_delta_base},
+    params::liquidity_distribution::LiquidityDistributionParameters,
+    u128x128_math::Rounding,
+};
+
+use crate::price::{get_price_from_sqrt_price, get_sqrt_price_from_price};
+
+/// Price samples used to measure the deviation of a segment
+const SAMPLES_PER_SEGMENT: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CurveFitParams {
+    /// supply sold on the curve, without decimals, the price function is defined on
+    /// `[0, supply_on_curve]`
+    pub supply_on_curve: f64,
+    pub token_base_decimal: u8,
+    pub token_quote_decimal: u8,
+    /// maximum number of segments, at most `MAX_CURVE_POINT`
+    pub max_segments: usize,
+    /// stop splitting once every segment deviates less than this relative price error
+    pub tolerance: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentFit {
+    /// sold supply at the start and end of the segment, without decimals
+    pub supply_start: f64,
+    pub supply_end: f64,
+    /// maximum relative deviation from the target price within the segment
+    pub max_deviation: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveFit {
+    pub sqrt_start_price: u128,
+    pub curve: Vec<LiquidityDistributionParameters>,
+    pub segments: Vec<SegmentFit>,
+    /// quote collected when the whole supply on curve is sold
+    pub migration_quote_threshold: u64,
+}
+
+impl CurveFit {
+    pub fn max_deviation(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|segment| segment.max_deviation)
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Price increasing linearly from `start_price` to `end_price`
+pub fn linear_price(start_price: f64, end_price: f64, supply: f64) -> impl Fn(f64) -> f64 {
+    move |x| start_price + (end_price - start_price) * x / supply
+}
+
+/// Price growing by the same ratio for every token sold, from `start_price` to `end_price`
+pub fn exponential_price(start_price: f64, end_price: f64, supply: f64) -> impl Fn(f64) -> f64 {
+    let rate = (end_price / start_price).ln() / supply;
+    move |x| start_price * (rate * x).exp()
+}
+
+/// Logistic price from `min_price` to `max_price`, centered at `midpoint` supply
+pub fn sigmoid_price(
+    min_price: f64,
+    max_price: f64,
+    midpoint: f64,
+    steepness: f64,
+) -> impl Fn(f64) -> f64 {
+    move |x| min_price + (max_price - min_price) / (1.0 + (-steepness * (x - midpoint)).exp())
+}
+
+/// Fit a curve to `price`, the price of one token in quote token, as a function of the sold
+/// supply. Both are in units without decimals
+pub fn fit_curve(price: impl Fn(f64) -> f64, params: &CurveFitParams) -> Result<CurveFit> {
+    let CurveFitParams {
+        supply_on_curve,
+        token_base_decimal,
+        token_quote_decimal,
+        max_segments,
+        tolerance,
+    } = *params;
+    ensure!(supply_on_curve > 0.0, "supply on curve must be positive");
+    ensure!(
+        max_segments > 0 && max_segments <= MAX_CURVE_POINT,
+        "max segments must be between 1 and {}",
+        MAX_CURVE_POINT
+    );
+
+    let mut breakpoints = vec![0.0, supply_on_curve];
+    loop {
+        let fit = build_curve(
+            &price,
+            &breakpoints,
+            token_base_decimal,
+            token_quote_decimal,
+        )?;
+        let (worst, worst_deviation) = fit
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| (i, segment.max_deviation))
+            .fold(
+                (0, 0.0),
+                |acc, item| if item.1 > acc.1 { item } else { acc },
+            );
+        if worst_deviation <= tolerance || fit.curve.len() == max_segments {
+            return Ok(fit);
+        }
+        let midpoint = (breakpoints[worst] + breakpoints[worst + 1]) / 2.0;
+        breakpoints.insert(worst + 1, midpoint);
+    }
+}
+
+fn build_curve(
+    price: &impl Fn(f64) -> f64,
+    breakpoints: &[f64],
+    token_base_decimal: u8,
+    token_quote_decimal: u8,
+) -> Result<CurveFit> {
+    let base_factor = 10f64.powi(token_base_decimal.into());
+    let sqrt_prices = breakpoints
+        .iter()
+        .map(|&x| {
+            let sqrt_price =
+                get_sqrt_price_from_price(price(x), token_base_decimal, token_quote_decimal)?;
+            ensure!(
+                (MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price),
+                "price at supply {} is out of range",
+                x
+            );
+            Ok(sqrt_price)
+        })
+        .collect::<Result<Vec<u128>>>()?;
+
+    let mut curve = Vec::with_capacity(breakpoints.len() - 1);
+    let mut segments = Vec::with_capacity(breakpoints.len() - 1);
+    let mut migration_quote_threshold = 0u64;
+    for i in 0..breakpoints.len() - 1 {
+        let (lower_sqrt_price, upper_sqrt_price) = (sqrt_prices[i], sqrt_prices[i + 1]);
+        ensure!(
+            upper_sqrt_price > lower_sqrt_price,
+            "price must be increasing, at supply {}",
+            breakpoints[i + 1]
+        );
+        let base_start = (breakpoints[i] * base_factor) as u64;
+        let base_end = (breakpoints[i + 1] * base_factor) as u64;
+        let liquidity = get_initial_liquidity_from_delta_base(
+            base_end - base_start,
+            upper_sqrt_price,
+            lower_sqrt_price,
+        )?;
+        let liquidity = u128::try_from(liquidity).context("liquidity overflow")?;
+        ensure!(
+            liquidity > 0,
+            "segment at supply {} is empty",
+            breakpoints[i]
+        );
+
+        // 1 / √P decreases linearly with the sold base amount
+        let max_deviation = (0..=SAMPLES_PER_SEGMENT)
+            .map(|step| {
+                let x = breakpoints[i]
+                    + (breakpoints[i + 1] - breakpoints[i]) * step as f64
+                        / SAMPLES_PER_SEGMENT as f64;
+                let sold = x * base_factor - base_start as f64;
+                let curve_sqrt_price =
+                    1.0 / (1.0 / lower_sqrt_price as f64 - sold / liquidity as f64);
+                let curve_price = get_price_from_sqrt_price(
+                    curve_sqrt_price as u128,
+                    token_base_decimal,
+                    token_quote_decimal,
+                );
+                let target_price = price(x);
+                (curve_price - target_price).abs() / target_price
+            })
+            .fold(0.0, f64::max);
+
+        migration_quote_threshold = migration_quote_threshold
+            .checked_add(get_delta_amount_quote_unsigned(
+                lower_sqrt_price,
+                upper_sqrt_price,
+                liquidity,
+                Rounding::Up,
+            )?)
+            .context("migration quote threshold overflow")?;
+        curve.push(LiquidityDistributionParameters {
+            sqrt_price: upper_sqrt_price,
+            liquidity,
+        });
+        segments.push(SegmentFit {
+            supply_start: breakpoints[i],
+            supply_end: breakpoints[i + 1],
+            max_deviation,
+        });
+    }
+
+    Ok(CurveFit {
+        sqrt_start_price: sqrt_prices[0],
+        curve,
+        segments,
+        migration_quote_threshold,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::params::liquidity_distribution::{
+        get_base_token_for_swap, get_migration_threshold_price,
+    };
+
+    use super::*;
+
+    fn get_params(max_segments: usize) -> CurveFitParams {
+        CurveFitParams {
+            supply_on_curve: 800_000_000.0,
+            token_base_decimal: 6,
+            token_quote_decimal: 9,
+            max_segments,
+            tolerance: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_fit_curve() {
+        let supply = 800_000_000.0;
+        let price_functions: Vec<Box<dyn Fn(f64) -> f64>> = vec![
+            Box::new(linear_price(0.000_000_03, 0.000_000_3, supply)),
+            Box::new(exponential_price(0.000_000_03, 0.000_000_3, supply)),
+            Box::new(sigmoid_price(
+                0.000_000_03,
+                0.000_000_3,
+                supply / 2.0,
+                10.0 / supply,
+            )),
+        ];
+        for price in price_functions {
+            let coarse = fit_curve(&price, &get_params(2)).unwrap();
+            let fit = fit_curve(&price, &get_params(MAX_CURVE_POINT)).unwrap();
+            assert_eq!(fit.curve.len(), MAX_CURVE_POINT);
+            assert!(fit.max_deviation() < coarse.max_deviation());
+            assert!(fit.max_deviation() < 0.01);
+            for i in 1..fit.curve.len() {
+                assert!(fit.curve[i].sqrt_price > fit.curve[i - 1].sqrt_price);
+            }
+
+            // the whole supply on curve is sold at the migration quote threshold
+            let sqrt_migration_price = get_migration_threshold_price(
+                fit.migration_quote_threshold,
+                fit.sqrt_start_price,
+                &fit.curve,
+            )
+            .unwrap();
+            assert_eq!(sqrt_migration_price, fit.curve.last().unwrap().sqrt_price);
+            let swap_base_amount: u64 =
+                get_base_token_for_swap(fit.sqrt_start_price, sqrt_migration_price, &fit.curve)
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+            let sold = 800_000_000_000_000u64;
+            assert!(swap_base_amount.abs_diff(sold) <= MAX_CURVE_POINT as u64);
+        }
+    }
+
+    #[test]
+    fn test_fit_curve_with_tolerance() {
+        let price = exponential_price(0.000_000_03, 0.000_000_3, 800_000_000.0);
+        let mut params = get_params(MAX_CURVE_POINT);
+        params.tolerance = 0.05;
+        let fit = fit_curve(&price, &params).unwrap();
+        assert!(fit.curve.len() < MAX_CURVE_POINT);
+        assert!(fit.max_deviation() <= 0.05);
+    }
+
+    #[test]
+    fn test_fit_curve_invalid_price() {
+        // decreasing price
+        let price = linear_price(0.000_000_3, 0.000_000_03, 800_000_000.0);
+        assert!(fit_curve(price, &get_params(4)).is_err());
+        assert!(fit_curve(|_| 0.0, &get_params(4)).is_err());
+        assert!(fit_curve(|x| x, &get_params(MAX_CURVE_POINT + 1)).is_err());
+    }
+}
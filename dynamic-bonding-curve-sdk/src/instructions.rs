@@ -0,0 +1,1118 @@
+//! Instruction builders for every dynamic bonding curve entrypoint.
+//!
+//! Account metas come from the anchor generated `dynamic_bonding_curve::accounts` structs,
+//! so their order always follows the program contexts. Pdas are derived with [`crate::pda`].
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program, sysvar},
+    InstructionData,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token::spl_token,
+    token_2022::spl_token_2022,
+};
+use anyhow::Result;
+use dynamic_bonding_curve::{
+    accounts, instruction,
+    state::{PoolConfig, TokenType, VirtualPool},
+    token::TokenProgramFlags,
+    ConfigParameters, CreatePartnerMetadataParameters, CreateVirtualPoolMetadataParameters,
+    InitializePoolParameters, SwapExactOutParameters, SwapParameters, SwapWithPriceLimitParameters,
+};
+
+use crate::pda::{
+    derive_allowlist_entry, derive_base_locker, derive_buy_tracker, derive_claim_fee_operator,
+    derive_damm_lock_escrow, derive_damm_lp_mint, derive_damm_pool, derive_damm_protocol_fee,
+    derive_damm_v2_event_authority, derive_damm_v2_migration_metadata, derive_damm_v2_pool,
+    derive_damm_v2_pool_authority, derive_damm_v2_position, derive_damm_v2_position_nft_account,
+    derive_damm_v2_token_vault, derive_damm_vault_lp, derive_dynamic_vault, derive_event_authority,
+    derive_locker_escrow, derive_locker_event_authority, derive_meteora_damm_migration_metadata,
+    derive_mint_metadata, derive_partner_metadata, derive_pool, derive_pool_authority,
+    derive_token_vault, derive_virtual_pool_metadata, VAULT_PROGRAM_ID,
+};
+
+/// Accounts shared by the instructions operating on a virtual pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub config: Pubkey,
+    pub pool: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub token_base_program: Pubkey,
+    pub token_quote_program: Pubkey,
+}
+
+impl PoolKeys {
+    /// Derive pool and vault addresses of a pool that is not created yet
+    pub fn new(
+        config: Pubkey,
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+        token_base_program: Pubkey,
+        token_quote_program: Pubkey,
+    ) -> Self {
+        let pool = derive_pool(&config, &base_mint, &quote_mint);
+        Self {
+            config,
+            pool,
+            base_mint,
+            quote_mint,
+            base_vault: derive_token_vault(&base_mint, &pool),
+            quote_vault: derive_token_vault(&quote_mint, &pool),
+            token_base_program,
+            token_quote_program,
+        }
+    }
+
+    pub fn from_state(
+        pool: Pubkey,
+        virtual_pool: &VirtualPool,
+        config: &PoolConfig,
+    ) -> Result<Self> {
+        let (token_base_program, token_quote_program) = get_token_programs(config)?;
+        Ok(Self {
+            config: virtual_pool.config,
+            pool,
+            base_mint: virtual_pool.base_mint,
+            quote_mint: config.quote_mint,
+            base_vault: virtual_pool.base_vault,
+            quote_vault: virtual_pool.quote_vault,
+            token_base_program,
+            token_quote_program,
+        })
+    }
+}
+
+/// Token programs of base and quote mint of pools created from `config`
+pub fn get_token_programs(config: &PoolConfig) -> Result<(Pubkey, Pubkey)> {
+    let token_base_program = match config.get_token_type()? {
+        TokenType::SplToken => spl_token::ID,
+        TokenType::Token2022 => spl_token_2022::ID,
+    };
+    let token_quote_program = match config.get_quote_token_flag()? {
+        TokenProgramFlags::TokenProgram => spl_token::ID,
+        TokenProgramFlags::TokenProgram2022 => spl_token_2022::ID,
+    };
+    Ok((token_base_program, token_quote_program))
+}
+
+/// User accounts of a swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapAccounts {
+    pub input_token_account: Pubkey,
+    pub output_token_account: Pubkey,
+    pub payer: Pubkey,
+    pub referral_token_account: Option<Pubkey>,
+}
+
+fn build_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: dynamic_bonding_curve::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Swap instruction with the instructions sysvar as remaining account, the program reads it to
+/// reject transactions with several swaps on the pool while the rate limiter applies
+fn build_swap_instruction(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    let mut instruction = build_instruction(accounts, data);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    instruction
+}
+
+/// ADMIN ///
+pub fn create_claim_fee_operator(admin: Pubkey, operator: Pubkey) -> Instruction {
+    build_instruction(
+        accounts::CreateClaimFeeOperatorCtx {
+            claim_fee_operator: derive_claim_fee_operator(&operator),
+            operator,
+            admin,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::CreateClaimFeeOperator {},
+    )
+}
+
+pub fn close_claim_fee_operator(
+    admin: Pubkey,
+    operator: Pubkey,
+    rent_receiver: Pubkey,
+) -> Instruction {
+    build_instruction(
+        accounts::CloseClaimFeeOperatorCtx {
+            claim_fee_operator: derive_claim_fee_operator(&operator),
+            rent_receiver,
+            admin,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::CloseClaimFeeOperator {},
+    )
+}
+
+pub fn claim_protocol_fee(
+    pool_keys: &PoolKeys,
+    operator: Pubkey,
+    token_base_account: Pubkey,
+    token_quote_account: Pubkey,
+) -> Instruction {
+    build_instruction(
+        accounts::ClaimProtocolFeesCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            pool: pool_keys.pool,
+            base_vault: pool_keys.base_vault,
+            quote_vault: pool_keys.quote_vault,
+            base_mint: pool_keys.base_mint,
+            quote_mint: pool_keys.quote_mint,
+            token_base_account,
+            token_quote_account,
+            claim_fee_operator: derive_claim_fee_operator(&operator),
+            operator,
+            token_base_program: pool_keys.token_base_program,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::ClaimProtocolFee {},
+    )
+}
+
+pub fn protocol_withdraw_surplus(pool_keys: &PoolKeys, token_quote_account: Pubkey) -> Instruction {
+    build_instruction(
+        accounts::ProtocolWithdrawSurplusCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            virtual_pool: pool_keys.pool,
+            token_quote_account,
+            quote_vault: pool_keys.quote_vault,
+            quote_mint: pool_keys.quote_mint,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::ProtocolWithdrawSurplus {},
+    )
+}
+
+/// PARTNER ///
+pub fn create_partner_metadata(
+    payer: Pubkey,
+    fee_claimer: Pubkey,
+    metadata: CreatePartnerMetadataParameters,
+) -> Instruction {
+    build_instruction(
+        accounts::CreatePartnerMetadataCtx {
+            partner_metadata: derive_partner_metadata(&fee_claimer),
+            payer,
+            fee_claimer,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::CreatePartnerMetadata { metadata },
+    )
+}
+
+/// `config` is a new keypair and must sign the transaction
+pub fn create_config(
+    config: Pubkey,
+    fee_claimer: Pubkey,
+    leftover_receiver: Pubkey,
+    quote_mint: Pubkey,
+    payer: Pubkey,
+    config_parameters: ConfigParameters,
+) -> Instruction {
+    build_instruction(
+        accounts::CreateConfigCtx {
+            config,
+            fee_claimer,
+            leftover_receiver,
+            quote_mint,
+            payer,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::CreateConfig { config_parameters },
+    )
+}
+
+pub fn claim_trading_fee(
+    pool_keys: &PoolKeys,
+    fee_claimer: Pubkey,
+    token_a_account: Pubkey,
+    token_b_account: Pubkey,
+    max_amount_a: u64,
+    max_amount_b: u64,
+) -> Instruction {
+    build_instruction(
+        accounts::ClaimTradingFeesCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            pool: pool_keys.pool,
+            token_a_account,
+            token_b_account,
+            base_vault: pool_keys.base_vault,
+            quote_vault: pool_keys.quote_vault,
+            base_mint: pool_keys.base_mint,
+            quote_mint: pool_keys.quote_mint,
+            fee_claimer,
+            token_base_program: pool_keys.token_base_program,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::ClaimTradingFee {
+            max_amount_a,
+            max_amount_b,
+        },
+    )
+}
+
+pub fn partner_withdraw_surplus(
+    pool_keys: &PoolKeys,
+    fee_claimer: Pubkey,
+    token_quote_account: Pubkey,
+) -> Instruction {
+    build_instruction(
+        accounts::PartnerWithdrawSurplusCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            virtual_pool: pool_keys.pool,
+            token_quote_account,
+            quote_vault: pool_keys.quote_vault,
+            quote_mint: pool_keys.quote_mint,
+            fee_claimer,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::PartnerWithdrawSurplus {},
+    )
+}
+
+/// POOL CREATOR ///
+/// Accounts of `InitializeVirtualPoolWithSplTokenCtx`, also nested in the first buy variant
+pub fn get_initialize_virtual_pool_with_spl_token_accounts(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    payer: Pubkey,
+) -> accounts::InitializeVirtualPoolWithSplTokenCtx {
+    accounts::InitializeVirtualPoolWithSplTokenCtx {
+        config: pool_keys.config,
+        pool_authority: derive_pool_authority(),
+        creator,
+        base_mint: pool_keys.base_mint,
+        quote_mint: pool_keys.quote_mint,
+        pool: pool_keys.pool,
+        base_vault: pool_keys.base_vault,
+        quote_vault: pool_keys.quote_vault,
+        mint_metadata: derive_mint_metadata(&pool_keys.base_mint),
+        metadata_program: mpl_token_metadata::ID,
+        payer,
+        token_quote_program: pool_keys.token_quote_program,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+        event_authority: derive_event_authority(),
+        program: dynamic_bonding_curve::ID,
+    }
+}
+
+/// `pool_keys` should come from [`PoolKeys::new`] with spl token as base token program,
+/// `pool_keys.base_mint` is a new keypair and must sign the transaction
+pub fn initialize_virtual_pool_with_spl_token(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    payer: Pubkey,
+    params: InitializePoolParameters,
+) -> Instruction {
+    build_instruction(
+        get_initialize_virtual_pool_with_spl_token_accounts(pool_keys, creator, payer),
+        instruction::InitializeVirtualPoolWithSplToken { params },
+    )
+}
+
+/// Same as [`initialize_virtual_pool_with_spl_token`], then buys with `first_buy` from
+/// `creator_quote_token_account`. The creator base token account (associated token account)
+/// is created in the instruction.
+pub fn initialize_virtual_pool_with_spl_token_and_first_buy(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    payer: Pubkey,
+    creator_quote_token_account: Pubkey,
+    params: InitializePoolParameters,
+    first_buy: Option<SwapParameters>,
+) -> Instruction {
+    build_instruction(
+        accounts::InitializeVirtualPoolWithSplTokenAndFirstBuyCtx {
+            initialize_pool: get_initialize_virtual_pool_with_spl_token_accounts(
+                pool_keys, creator, payer,
+            ),
+            creator_quote_token_account,
+            creator_base_token_account: get_associated_token_address_with_program_id(
+                &creator,
+                &pool_keys.base_mint,
+                &spl_token::ID,
+            ),
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::InitializeVirtualPoolWithSplTokenAndFirstBuy { params, first_buy },
+    )
+}
+
+/// Accounts of `InitializeVirtualPoolWithToken2022Ctx`, also nested in the first buy variant
+pub fn get_initialize_virtual_pool_with_token2022_accounts(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    payer: Pubkey,
+) -> accounts::InitializeVirtualPoolWithToken2022Ctx {
+    accounts::InitializeVirtualPoolWithToken2022Ctx {
+        config: pool_keys.config,
+        pool_authority: derive_pool_authority(),
+        creator,
+        base_mint: pool_keys.base_mint,
+        quote_mint: pool_keys.quote_mint,
+        pool: pool_keys.pool,
+        base_vault: pool_keys.base_vault,
+        quote_vault: pool_keys.quote_vault,
+        payer,
+        token_quote_program: pool_keys.token_quote_program,
+        token_program: spl_token_2022::ID,
+        system_program: system_program::ID,
+        event_authority: derive_event_authority(),
+        program: dynamic_bonding_curve::ID,
+    }
+}
+
+/// `pool_keys` should come from [`PoolKeys::new`] with token 2022 as base token program,
+/// `pool_keys.base_mint` is a new keypair and must sign the transaction
+pub fn initialize_virtual_pool_with_token2022(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    payer: Pubkey,
+    params: InitializePoolParameters,
+) -> Instruction {
+    build_instruction(
+        get_initialize_virtual_pool_with_token2022_accounts(pool_keys, creator, payer),
+        instruction::InitializeVirtualPoolWithToken2022 { params },
+    )
+}
+
+/// Same as [`initialize_virtual_pool_with_token2022`], then buys with `first_buy` from
+/// `creator_quote_token_account`. The creator base token account (associated token account)
+/// is created in the instruction.
+pub fn initialize_virtual_pool_with_token2022_and_first_buy(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    payer: Pubkey,
+    creator_quote_token_account: Pubkey,
+    params: InitializePoolParameters,
+    first_buy: Option<SwapParameters>,
+) -> Instruction {
+    build_instruction(
+        accounts::InitializeVirtualPoolWithToken2022AndFirstBuyCtx {
+            initialize_pool: get_initialize_virtual_pool_with_token2022_accounts(
+                pool_keys, creator, payer,
+            ),
+            creator_quote_token_account,
+            creator_base_token_account: get_associated_token_address_with_program_id(
+                &creator,
+                &pool_keys.base_mint,
+                &spl_token_2022::ID,
+            ),
+            token_program: spl_token_2022::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::InitializeVirtualPoolWithToken2022AndFirstBuy { params, first_buy },
+    )
+}
+
+pub fn create_virtual_pool_metadata(
+    virtual_pool: Pubkey,
+    creator: Pubkey,
+    payer: Pubkey,
+    metadata: CreateVirtualPoolMetadataParameters,
+) -> Instruction {
+    build_instruction(
+        accounts::CreateVirtualPoolMetadataCtx {
+            virtual_pool,
+            virtual_pool_metadata: derive_virtual_pool_metadata(&virtual_pool),
+            creator,
+            payer,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::CreateVirtualPoolMetadata { metadata },
+    )
+}
+
+pub fn claim_creator_trading_fee(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    token_a_account: Pubkey,
+    token_b_account: Pubkey,
+    max_base_amount: u64,
+    max_quote_amount: u64,
+) -> Instruction {
+    build_instruction(
+        accounts::ClaimCreatorTradingFeesCtx {
+            pool_authority: derive_pool_authority(),
+            pool: pool_keys.pool,
+            token_a_account,
+            token_b_account,
+            base_vault: pool_keys.base_vault,
+            quote_vault: pool_keys.quote_vault,
+            base_mint: pool_keys.base_mint,
+            quote_mint: pool_keys.quote_mint,
+            creator,
+            token_base_program: pool_keys.token_base_program,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::ClaimCreatorTradingFee {
+            max_base_amount,
+            max_quote_amount,
+        },
+    )
+}
+
+pub fn creator_withdraw_surplus(
+    pool_keys: &PoolKeys,
+    creator: Pubkey,
+    token_quote_account: Pubkey,
+) -> Instruction {
+    build_instruction(
+        accounts::CreatorWithdrawSurplusCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            virtual_pool: pool_keys.pool,
+            token_quote_account,
+            quote_vault: pool_keys.quote_vault,
+            quote_mint: pool_keys.quote_mint,
+            creator,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::CreatorWithdrawSurplus {},
+    )
+}
+
+/// The damm v1 migration metadata is always passed as remaining account,
+/// the program only reads it once the pool migrated to damm v1
+pub fn transfer_pool_creator(
+    virtual_pool: Pubkey,
+    config: Pubkey,
+    creator: Pubkey,
+    new_creator: Pubkey,
+) -> Instruction {
+    let mut ix = build_instruction(
+        accounts::TransferPoolCreatorCtx {
+            virtual_pool,
+            config,
+            creator,
+            new_creator,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::TransferPoolCreator {},
+    );
+    ix.accounts.push(AccountMeta::new_readonly(
+        derive_meteora_damm_migration_metadata(&virtual_pool),
+        false,
+    ));
+    ix
+}
+
+/// PARTNER AND CREATOR ///
+pub fn withdraw_migration_fee(
+    pool_keys: &PoolKeys,
+    sender: Pubkey,
+    token_quote_account: Pubkey,
+    flag: u8,
+) -> Instruction {
+    build_instruction(
+        accounts::WithdrawMigrationFeeCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            virtual_pool: pool_keys.pool,
+            token_quote_account,
+            quote_vault: pool_keys.quote_vault,
+            quote_mint: pool_keys.quote_mint,
+            sender,
+            token_quote_program: pool_keys.token_quote_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::WithdrawMigrationFee { flag },
+    )
+}
+
+/// TRADING ///
+/// Accounts of `SwapCtx`, shared by all swap instructions
+pub fn get_swap_accounts(pool_keys: &PoolKeys, swap_accounts: &SwapAccounts) -> accounts::SwapCtx {
+    accounts::SwapCtx {
+        pool_authority: derive_pool_authority(),
+        config: pool_keys.config,
+        pool: pool_keys.pool,
+        input_token_account: swap_accounts.input_token_account,
+        output_token_account: swap_accounts.output_token_account,
+        base_vault: pool_keys.base_vault,
+        quote_vault: pool_keys.quote_vault,
+        base_mint: pool_keys.base_mint,
+        quote_mint: pool_keys.quote_mint,
+        payer: swap_accounts.payer,
+        token_base_program: pool_keys.token_base_program,
+        token_quote_program: pool_keys.token_quote_program,
+        referral_token_account: swap_accounts.referral_token_account,
+        event_authority: derive_event_authority(),
+        program: dynamic_bonding_curve::ID,
+    }
+}
+
+pub fn swap(
+    pool_keys: &PoolKeys,
+    swap_accounts: &SwapAccounts,
+    params: SwapParameters,
+) -> Instruction {
+    build_swap_instruction(
+        get_swap_accounts(pool_keys, swap_accounts),
+        instruction::Swap { params },
+    )
+}
+
+pub fn swap_exact_out(
+    pool_keys: &PoolKeys,
+    swap_accounts: &SwapAccounts,
+    params: SwapExactOutParameters,
+) -> Instruction {
+    build_swap_instruction(
+        get_swap_accounts(pool_keys, swap_accounts),
+        instruction::SwapExactOut { params },
+    )
+}
+
+pub fn swap_with_price_limit(
+    pool_keys: &PoolKeys,
+    swap_accounts: &SwapAccounts,
+    params: SwapWithPriceLimitParameters,
+) -> Instruction {
+    build_swap_instruction(
+        get_swap_accounts(pool_keys, swap_accounts),
+        instruction::SwapWithPriceLimit { params },
+    )
+}
+
+/// Buy in the allowlist phase, `swap_accounts.payer` must be in the allowlist and `proof` comes
+/// from [`crate::allowlist::AllowlistTree::get_proof`]. `rent_payer` pays the allowlist entry
+/// rent on the first buy.
+pub fn swap_allowlisted(
+    pool_keys: &PoolKeys,
+    swap_accounts: &SwapAccounts,
+    rent_payer: Pubkey,
+    params: SwapParameters,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    build_swap_instruction(
+        accounts::SwapAllowlistedCtx {
+            swap: get_swap_accounts(pool_keys, swap_accounts),
+            allowlist_entry: derive_allowlist_entry(&pool_keys.pool, &swap_accounts.payer),
+            rent_payer,
+            system_program: system_program::ID,
+        },
+        instruction::SwapAllowlisted { params, proof },
+    )
+}
+
+/// Buy while the config buy limits apply, the quote spent by `swap_accounts.payer` is tracked
+/// against the per wallet limit. `rent_payer` pays the buy tracker rent on the first buy.
+pub fn swap_with_buy_tracker(
+    pool_keys: &PoolKeys,
+    swap_accounts: &SwapAccounts,
+    rent_payer: Pubkey,
+    params: SwapParameters,
+) -> Instruction {
+    build_swap_instruction(
+        accounts::SwapWithBuyTrackerCtx {
+            swap: get_swap_accounts(pool_keys, swap_accounts),
+            buy_tracker: derive_buy_tracker(&pool_keys.pool, &swap_accounts.payer),
+            rent_payer,
+            system_program: system_program::ID,
+        },
+        instruction::SwapWithBuyTracker { params },
+    )
+}
+
+/// PERMISSIONLESS ///
+/// The escrow token account (associated token account of the locker escrow) must exist
+pub fn create_locker(pool_keys: &PoolKeys, creator: Pubkey, payer: Pubkey) -> Instruction {
+    let base = derive_base_locker(&pool_keys.pool);
+    let escrow = derive_locker_escrow(&base);
+    build_instruction(
+        accounts::CreateLockerCtx {
+            virtual_pool: pool_keys.pool,
+            config: pool_keys.config,
+            pool_authority: derive_pool_authority(),
+            base_vault: pool_keys.base_vault,
+            base_mint: pool_keys.base_mint,
+            base,
+            creator,
+            escrow,
+            escrow_token: get_associated_token_address_with_program_id(
+                &escrow,
+                &pool_keys.base_mint,
+                &pool_keys.token_base_program,
+            ),
+            payer,
+            token_program: pool_keys.token_base_program,
+            locker_program: locker::ID,
+            locker_event_authority: derive_locker_event_authority(),
+            system_program: system_program::ID,
+        },
+        instruction::CreateLocker {},
+    )
+}
+
+pub fn withdraw_leftover(pool_keys: &PoolKeys, leftover_receiver: Pubkey) -> Instruction {
+    build_instruction(
+        accounts::WithdrawLeftoverCtx {
+            pool_authority: derive_pool_authority(),
+            config: pool_keys.config,
+            virtual_pool: pool_keys.pool,
+            token_base_account: get_associated_token_address_with_program_id(
+                &leftover_receiver,
+                &pool_keys.base_mint,
+                &pool_keys.token_base_program,
+            ),
+            base_vault: pool_keys.base_vault,
+            base_mint: pool_keys.base_mint,
+            leftover_receiver,
+            token_base_program: pool_keys.token_base_program,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::WithdrawLeftover {},
+    )
+}
+
+/// MIGRATE DAMM V1 ///
+pub fn migration_meteora_damm_create_metadata(
+    virtual_pool: Pubkey,
+    config: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    build_instruction(
+        accounts::MigrationMeteoraDammCreateMetadataCtx {
+            virtual_pool,
+            config,
+            migration_metadata: derive_meteora_damm_migration_metadata(&virtual_pool),
+            payer,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::MigrationMeteoraDammCreateMetadata {},
+    )
+}
+
+/// `vault_base` is the base key of the dynamic vaults of both mints, the vaults must exist
+pub fn migrate_meteora_damm(
+    pool_keys: &PoolKeys,
+    damm_config: Pubkey,
+    vault_base: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    let damm_pool = derive_damm_pool(&damm_config, &pool_keys.base_mint, &pool_keys.quote_mint);
+    let lp_mint = derive_damm_lp_mint(&damm_pool);
+    let a_vault = derive_dynamic_vault(&pool_keys.base_mint, &vault_base);
+    let b_vault = derive_dynamic_vault(&pool_keys.quote_mint, &vault_base);
+    let pool_authority = derive_pool_authority();
+    build_instruction(
+        accounts::MigrateMeteoraDammCtx {
+            virtual_pool: pool_keys.pool,
+            migration_metadata: derive_meteora_damm_migration_metadata(&pool_keys.pool),
+            config: pool_keys.config,
+            pool_authority,
+            pool: damm_pool,
+            damm_config,
+            lp_mint,
+            token_a_mint: pool_keys.base_mint,
+            token_b_mint: pool_keys.quote_mint,
+            a_vault: a_vault.vault,
+            b_vault: b_vault.vault,
+            a_token_vault: a_vault.token_vault,
+            b_token_vault: b_vault.token_vault,
+            a_vault_lp_mint: a_vault.lp_mint,
+            b_vault_lp_mint: b_vault.lp_mint,
+            a_vault_lp: derive_damm_vault_lp(&a_vault.vault, &damm_pool),
+            b_vault_lp: derive_damm_vault_lp(&b_vault.vault, &damm_pool),
+            base_vault: pool_keys.base_vault,
+            quote_vault: pool_keys.quote_vault,
+            virtual_pool_lp: get_associated_token_address_with_program_id(
+                &pool_authority,
+                &lp_mint,
+                &spl_token::ID,
+            ),
+            protocol_token_a_fee: derive_damm_protocol_fee(&pool_keys.base_mint, &damm_pool),
+            protocol_token_b_fee: derive_damm_protocol_fee(&pool_keys.quote_mint, &damm_pool),
+            payer,
+            rent: sysvar::rent::ID,
+            mint_metadata: derive_mint_metadata(&lp_mint),
+            metadata_program: mpl_token_metadata::ID,
+            amm_program: dynamic_amm::ID,
+            vault_program: VAULT_PROGRAM_ID,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateMeteoraDamm {},
+    )
+}
+
+/// `owner` is the pool creator or the partner fee claimer, its lock escrow
+/// and the escrow lp token account must exist
+pub fn migrate_meteora_damm_lock_lp_token(
+    pool_keys: &PoolKeys,
+    damm_config: Pubkey,
+    vault_base: Pubkey,
+    owner: Pubkey,
+) -> Instruction {
+    let damm_pool = derive_damm_pool(&damm_config, &pool_keys.base_mint, &pool_keys.quote_mint);
+    let lp_mint = derive_damm_lp_mint(&damm_pool);
+    let lock_escrow = derive_damm_lock_escrow(&damm_pool, &owner);
+    let a_vault = derive_dynamic_vault(&pool_keys.base_mint, &vault_base);
+    let b_vault = derive_dynamic_vault(&pool_keys.quote_mint, &vault_base);
+    let pool_authority = derive_pool_authority();
+    build_instruction(
+        accounts::MigrateMeteoraDammLockLpTokenCtx {
+            virtual_pool: pool_keys.pool,
+            migration_metadata: derive_meteora_damm_migration_metadata(&pool_keys.pool),
+            pool_authority,
+            pool: damm_pool,
+            lp_mint,
+            lock_escrow,
+            owner,
+            source_tokens: get_associated_token_address_with_program_id(
+                &pool_authority,
+                &lp_mint,
+                &spl_token::ID,
+            ),
+            escrow_vault: get_associated_token_address_with_program_id(
+                &lock_escrow,
+                &lp_mint,
+                &spl_token::ID,
+            ),
+            amm_program: dynamic_amm::ID,
+            a_vault: a_vault.vault,
+            b_vault: b_vault.vault,
+            a_vault_lp: derive_damm_vault_lp(&a_vault.vault, &damm_pool),
+            b_vault_lp: derive_damm_vault_lp(&b_vault.vault, &damm_pool),
+            a_vault_lp_mint: a_vault.lp_mint,
+            b_vault_lp_mint: b_vault.lp_mint,
+            token_program: spl_token::ID,
+        },
+        instruction::MigrateMeteoraDammLockLpToken {},
+    )
+}
+
+/// `owner` is the pool creator or the partner fee claimer, its lp token account must exist
+pub fn migrate_meteora_damm_claim_lp_token(
+    pool_keys: &PoolKeys,
+    damm_config: Pubkey,
+    owner: Pubkey,
+    sender: Pubkey,
+) -> Instruction {
+    let damm_pool = derive_damm_pool(&damm_config, &pool_keys.base_mint, &pool_keys.quote_mint);
+    let lp_mint = derive_damm_lp_mint(&damm_pool);
+    let pool_authority = derive_pool_authority();
+    build_instruction(
+        accounts::MigrateMeteoraDammClaimLpTokenCtx {
+            virtual_pool: pool_keys.pool,
+            migration_metadata: derive_meteora_damm_migration_metadata(&pool_keys.pool),
+            pool_authority,
+            lp_mint,
+            source_token: get_associated_token_address_with_program_id(
+                &pool_authority,
+                &lp_mint,
+                &spl_token::ID,
+            ),
+            destination_token: get_associated_token_address_with_program_id(
+                &owner,
+                &lp_mint,
+                &spl_token::ID,
+            ),
+            owner,
+            sender,
+            token_program: spl_token::ID,
+        },
+        instruction::MigrateMeteoraDammClaimLpToken {},
+    )
+}
+
+/// MIGRATE DAMM V2 ///
+pub fn migration_damm_v2_create_metadata(
+    virtual_pool: Pubkey,
+    config: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    build_instruction(
+        accounts::MigrationDammV2CreateMetadataCtx {
+            virtual_pool,
+            config,
+            migration_metadata: derive_damm_v2_migration_metadata(&virtual_pool),
+            payer,
+            system_program: system_program::ID,
+            event_authority: derive_event_authority(),
+            program: dynamic_bonding_curve::ID,
+        },
+        instruction::MigrationDammV2CreateMetadata {},
+    )
+}
+
+/// Position nft mints are new keypairs and must sign the transaction,
+/// the second position is only needed when both partner and creator receive lp
+pub fn migration_damm_v2(
+    pool_keys: &PoolKeys,
+    damm_config: Pubkey,
+    first_position_nft_mint: Pubkey,
+    second_position_nft_mint: Option<Pubkey>,
+    payer: Pubkey,
+) -> Instruction {
+    let damm_pool = derive_damm_v2_pool(&damm_config, &pool_keys.base_mint, &pool_keys.quote_mint);
+    let mut ix = build_instruction(
+        accounts::MigrateDammV2Ctx {
+            virtual_pool: pool_keys.pool,
+            migration_metadata: derive_damm_v2_migration_metadata(&pool_keys.pool),
+            config: pool_keys.config,
+            pool_authority: derive_pool_authority(),
+            pool: damm_pool,
+            first_position_nft_mint,
+            first_position_nft_account: derive_damm_v2_position_nft_account(
+                &first_position_nft_mint,
+            ),
+            first_position: derive_damm_v2_position(&first_position_nft_mint),
+            second_position_nft_mint,
+            second_position_nft_account: second_position_nft_mint
+                .as_ref()
+                .map(derive_damm_v2_position_nft_account),
+            second_position: second_position_nft_mint
+                .as_ref()
+                .map(derive_damm_v2_position),
+            damm_pool_authority: derive_damm_v2_pool_authority(),
+            amm_program: damm_v2::ID,
+            base_mint: pool_keys.base_mint,
+            quote_mint: pool_keys.quote_mint,
+            token_a_vault: derive_damm_v2_token_vault(&pool_keys.base_mint, &damm_pool),
+            token_b_vault: derive_damm_v2_token_vault(&pool_keys.quote_mint, &damm_pool),
+            base_vault: pool_keys.base_vault,
+            quote_vault: pool_keys.quote_vault,
+            payer,
+            token_base_program: pool_keys.token_base_program,
+            token_quote_program: pool_keys.token_quote_program,
+            token_2022_program: spl_token_2022::ID,
+            damm_event_authority: derive_damm_v2_event_authority(),
+            system_program: system_program::ID,
+        },
+        instruction::MigrationDammV2 {},
+    );
+    // damm config is read from remaining accounts
+    ix.accounts
+        .push(AccountMeta::new_readonly(damm_config, false));
+    ix
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Discriminator;
+
+    use super::*;
+
+    fn get_pool_keys() -> PoolKeys {
+        PoolKeys::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            spl_token::native_mint::ID,
+            spl_token::ID,
+            spl_token::ID,
+        )
+    }
+
+    #[test]
+    fn test_pool_keys_derivation() {
+        let pool_keys = get_pool_keys();
+        assert_eq!(
+            pool_keys.pool,
+            derive_pool(
+                &pool_keys.config,
+                &pool_keys.quote_mint,
+                &pool_keys.base_mint
+            )
+        );
+        assert_eq!(
+            pool_keys.base_vault,
+            derive_token_vault(&pool_keys.base_mint, &pool_keys.pool)
+        );
+        assert_eq!(
+            pool_keys.quote_vault,
+            derive_token_vault(&pool_keys.quote_mint, &pool_keys.pool)
+        );
+    }
+
+    #[test]
+    fn test_swap_instruction() {
+        let pool_keys = get_pool_keys();
+        let accounts = SwapAccounts {
+            input_token_account: Pubkey::new_unique(),
+            output_token_account: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            referral_token_account: None,
+        };
+        let ix = swap(
+            &pool_keys,
+            &accounts,
+            SwapParameters {
+                amount_in: 1_000,
+                minimum_amount_out: 900,
+            },
+        );
+        assert_eq!(ix.program_id, dynamic_bonding_curve::ID);
+        assert_eq!(ix.accounts.len(), 16);
+        assert_eq!(ix.accounts[0].pubkey, derive_pool_authority());
+        assert_eq!(ix.accounts[2].pubkey, pool_keys.pool);
+        assert!(ix.accounts[2].is_writable);
+        assert_eq!(ix.accounts[9].pubkey, accounts.payer);
+        assert!(ix.accounts[9].is_signer);
+        // missing referral is replaced by program id
+        assert_eq!(ix.accounts[12].pubkey, dynamic_bonding_curve::ID);
+        assert_eq!(ix.accounts[13].pubkey, derive_event_authority());
+        // instructions sysvar for the rate limiter
+        assert_eq!(ix.accounts[15].pubkey, sysvar::instructions::ID);
+        assert!(!ix.accounts[15].is_writable);
+
+        let mut data = instruction::Swap::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&900u64.to_le_bytes());
+        assert_eq!(ix.data, data);
+    }
+
+    #[test]
+    fn test_swap_with_buy_tracker_instruction() {
+        let pool_keys = get_pool_keys();
+        let accounts = SwapAccounts {
+            input_token_account: Pubkey::new_unique(),
+            output_token_account: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            referral_token_account: None,
+        };
+        let rent_payer = Pubkey::new_unique();
+        let ix = swap_with_buy_tracker(
+            &pool_keys,
+            &accounts,
+            rent_payer,
+            SwapParameters {
+                amount_in: 1_000,
+                minimum_amount_out: 900,
+            },
+        );
+        // swap accounts, then the buy tracker accounts and the instructions sysvar
+        assert_eq!(ix.accounts.len(), 19);
+        assert_eq!(ix.accounts[9].pubkey, accounts.payer);
+        assert_eq!(
+            ix.accounts[15].pubkey,
+            derive_buy_tracker(&pool_keys.pool, &accounts.payer)
+        );
+        assert!(ix.accounts[15].is_writable);
+        assert_eq!(ix.accounts[16].pubkey, rent_payer);
+        assert!(ix.accounts[16].is_signer);
+        assert_eq!(ix.accounts[18].pubkey, sysvar::instructions::ID);
+        assert!(ix
+            .data
+            .starts_with(instruction::SwapWithBuyTracker::DISCRIMINATOR));
+    }
+
+    #[test]
+    fn test_initialize_virtual_pool_with_first_buy_instruction() {
+        let pool_keys = get_pool_keys();
+        let creator = Pubkey::new_unique();
+        let creator_quote_token_account = Pubkey::new_unique();
+        let ix = initialize_virtual_pool_with_spl_token_and_first_buy(
+            &pool_keys,
+            creator,
+            creator,
+            creator_quote_token_account,
+            InitializePoolParameters {
+                name: "name".to_string(),
+                symbol: "symbol".to_string(),
+                uri: "uri".to_string(),
+                activation_point: None,
+                allowlist: None,
+            },
+            Some(SwapParameters {
+                amount_in: 1_000,
+                minimum_amount_out: 0,
+            }),
+        );
+        // the nested initialize accounts come first, with their own event cpi accounts
+        assert_eq!(ix.accounts.len(), 16 + 7);
+        assert_eq!(ix.accounts[0].pubkey, pool_keys.config);
+        assert_eq!(ix.accounts[3].pubkey, pool_keys.base_mint);
+        assert!(ix.accounts[3].is_signer);
+        assert_eq!(ix.accounts[16].pubkey, creator_quote_token_account);
+        assert!(ix.accounts[16].is_writable);
+        assert_eq!(
+            ix.accounts[17].pubkey,
+            get_associated_token_address_with_program_id(
+                &creator,
+                &pool_keys.base_mint,
+                &spl_token::ID
+            )
+        );
+        assert_eq!(ix.accounts[18].pubkey, spl_token::ID);
+        assert_eq!(ix.accounts[19].pubkey, associated_token::ID);
+        assert_eq!(ix.accounts[21].pubkey, derive_event_authority());
+    }
+
+    #[test]
+    fn test_migration_damm_v2_instruction() {
+        let pool_keys = get_pool_keys();
+        let damm_config = Pubkey::new_unique();
+        let first_position_nft_mint = Pubkey::new_unique();
+        let ix = migration_damm_v2(
+            &pool_keys,
+            damm_config,
+            first_position_nft_mint,
+            None,
+            Pubkey::new_unique(),
+        );
+        assert_eq!(
+            ix.accounts[7].pubkey,
+            derive_damm_v2_position(&first_position_nft_mint)
+        );
+        // missing second position accounts are replaced by program id
+        for meta in &ix.accounts[8..11] {
+            assert_eq!(meta.pubkey, dynamic_bonding_curve::ID);
+        }
+        assert_eq!(ix.accounts.last().unwrap().pubkey, damm_config);
+    }
+}
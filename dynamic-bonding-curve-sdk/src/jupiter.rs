@@ -0,0 +1,515 @@
+//! `jupiter_amm_interface::Amm` implementation to route through virtual pools from the
+//! Jupiter aggregator.
+use std::sync::atomic::Ordering;
+
+use anchor_lang::{prelude::*, solana_program::sysvar};
+use anyhow::{bail, Context, Result};
+use dynamic_bonding_curve::{
+    activation_handler::ActivationType,
+    params::swap::TradeDirection,
+    state::{fee::FeeMode, PoolConfig, VirtualPool},
+};
+use jupiter_amm_interface::{
+    try_get_account_data_and_owner, AccountMap, Amm, AmmContext, ClockRef, KeyedAccount, Quote,
+    QuoteParams, Swap, SwapAndAccountMetas, SwapMode, SwapParams,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    accounts::decode_zero_copy_account,
+    instructions::{get_swap_accounts, PoolKeys, SwapAccounts},
+    quote::quote_exact_in,
+};
+
+pub const LABEL: &str = "Meteora DBC";
+
+/// Account metas of `SwapCtx`, including event cpi accounts, and the instructions sysvar
+const SWAP_ACCOUNTS_LEN: usize = 16;
+
+#[derive(Clone)]
+pub struct VirtualPoolAmm {
+    key: Pubkey,
+    virtual_pool: VirtualPool,
+    config: Option<PoolConfig>,
+    clock_ref: ClockRef,
+}
+
+impl VirtualPoolAmm {
+    fn get_config(&self) -> Result<&PoolConfig> {
+        self.config.as_ref().context("config is not loaded")
+    }
+
+    fn is_swap_base_for_quote(&self, input_mint: Pubkey, output_mint: Pubkey) -> Result<bool> {
+        let config = self.get_config()?;
+        let base_mint = self.virtual_pool.base_mint;
+        if input_mint == base_mint && output_mint == config.quote_mint {
+            Ok(true)
+        } else if input_mint == config.quote_mint && output_mint == base_mint {
+            Ok(false)
+        } else {
+            Err(anyhow::anyhow!("invalid mints"))
+        }
+    }
+
+    fn get_current_point(&self, config: &PoolConfig) -> Result<u64> {
+        let activation_type =
+            ActivationType::try_from(config.activation_type).context("invalid activation type")?;
+        Ok(match activation_type {
+            ActivationType::Slot => self.clock_ref.slot.load(Ordering::Relaxed),
+            ActivationType::Timestamp => {
+                self.clock_ref.unix_timestamp.load(Ordering::Relaxed) as u64
+            }
+        })
+    }
+
+    /// Routed buys go through `swap`, they fail before activation, in the allowlist phase and
+    /// under a per wallet buy limit as they have no buy tracker
+    fn is_buy_blocked(&self, config: &PoolConfig) -> Result<bool> {
+        let current_point = self.get_current_point(config)?;
+        let activation_point = self.virtual_pool.activation_point;
+        Ok(current_point < activation_point
+            || self.virtual_pool.is_allowlist_phase(current_point)
+            || (config.max_buy_quote_per_wallet != 0
+                && config.is_buy_limit_applied(
+                    current_point,
+                    activation_point,
+                    TradeDirection::QuoteToBase,
+                )?))
+    }
+
+    fn get_fee_mode(&self, swap_base_for_quote: bool) -> Result<FeeMode> {
+        let trade_direction = if swap_base_for_quote {
+            TradeDirection::BaseToQuote
+        } else {
+            TradeDirection::QuoteToBase
+        };
+        Ok(FeeMode::get_fee_mode(
+            self.get_config()?.collect_fee_mode,
+            trade_direction,
+            false,
+        )?)
+    }
+}
+
+impl Amm for VirtualPoolAmm {
+    fn from_keyed_account(keyed_account: &KeyedAccount, amm_context: &AmmContext) -> Result<Self> {
+        Ok(Self {
+            key: keyed_account.key,
+            virtual_pool: decode_zero_copy_account(
+                &keyed_account.account.owner,
+                &keyed_account.account.data,
+            )?,
+            config: None,
+            clock_ref: amm_context.clock_ref.clone(),
+        })
+    }
+
+    fn label(&self) -> String {
+        LABEL.to_string()
+    }
+
+    fn program_id(&self) -> Pubkey {
+        dynamic_bonding_curve::ID
+    }
+
+    fn key(&self) -> Pubkey {
+        self.key
+    }
+
+    /// Quote mint is only known after config is loaded by `update`
+    fn get_reserve_mints(&self) -> Vec<Pubkey> {
+        match &self.config {
+            Some(config) => vec![self.virtual_pool.base_mint, config.quote_mint],
+            None => vec![self.virtual_pool.base_mint],
+        }
+    }
+
+    fn requires_update_for_reserve_mints(&self) -> bool {
+        true
+    }
+
+    fn get_accounts_to_update(&self) -> Vec<Pubkey> {
+        vec![self.key, self.virtual_pool.config]
+    }
+
+    fn update(&mut self, account_map: &AccountMap) -> Result<()> {
+        let (pool_data, pool_owner) = try_get_account_data_and_owner(account_map, &self.key)?;
+        self.virtual_pool = decode_zero_copy_account(pool_owner, pool_data)?;
+
+        let (config_data, config_owner) =
+            try_get_account_data_and_owner(account_map, &self.virtual_pool.config)?;
+        self.config = Some(decode_zero_copy_account(config_owner, config_data)?);
+        Ok(())
+    }
+
+    fn quote(&self, quote_params: &QuoteParams) -> Result<Quote> {
+        let config = self.get_config()?;
+        let swap_base_for_quote =
+            self.is_swap_base_for_quote(quote_params.input_mint, quote_params.output_mint)?;
+        let current_timestamp = self.clock_ref.unix_timestamp.load(Ordering::Relaxed) as u64;
+        let current_slot = self.clock_ref.slot.load(Ordering::Relaxed);
+
+        // routed swaps go through `swap`, which only takes an exact input
+        if quote_params.swap_mode == SwapMode::ExactOut {
+            bail!("exact out is not supported");
+        }
+        if !swap_base_for_quote && self.is_buy_blocked(config)? {
+            bail!("virtual pool doesn't accept routed buys yet");
+        }
+
+        // referral fee is taken from protocol fee, it doesn't change the quote
+        let swap_result = quote_exact_in(
+            &self.virtual_pool,
+            config,
+            swap_base_for_quote,
+            current_timestamp,
+            current_slot,
+            quote_params.amount,
+            false,
+            false,
+        )?;
+
+        let fee_mode = self.get_fee_mode(swap_base_for_quote)?;
+        let fee_amount = swap_result
+            .trading_fee
+            .checked_add(swap_result.protocol_fee)
+            .and_then(|fee| fee.checked_add(swap_result.referral_fee))
+            .context("fee overflow")?;
+        // fee is charged on the input, or on the output before it is paid out
+        let fee_base_amount = if fee_mode.fees_on_input {
+            swap_result.consumed_input_amount
+        } else {
+            swap_result
+                .output_amount
+                .checked_add(fee_amount)
+                .context("fee overflow")?
+        };
+        let fee_pct = if fee_base_amount == 0 {
+            Decimal::ZERO
+        } else {
+            Decimal::from(fee_amount) / Decimal::from(fee_base_amount)
+        };
+        let fee_mint = if fee_mode.fees_on_base_token {
+            self.virtual_pool.base_mint
+        } else {
+            config.quote_mint
+        };
+
+        Ok(Quote {
+            in_amount: swap_result.consumed_input_amount,
+            out_amount: swap_result.output_amount,
+            fee_amount,
+            fee_mint,
+            fee_pct,
+        })
+    }
+
+    /// Account metas of `SwapCtx`, in order, including event cpi accounts, then the instructions
+    /// sysvar as remaining account for the rate limiter
+    fn get_swap_and_account_metas(&self, swap_params: &SwapParams) -> Result<SwapAndAccountMetas> {
+        let config = self.get_config()?;
+        let swap_base_for_quote =
+            self.is_swap_base_for_quote(swap_params.source_mint, swap_params.destination_mint)?;
+
+        // referral fee is paid in the fee token
+        let fee_mint = if self.get_fee_mode(swap_base_for_quote)?.fees_on_base_token {
+            self.virtual_pool.base_mint
+        } else {
+            config.quote_mint
+        };
+        let referral_token_account = swap_params
+            .quote_mint_to_referrer
+            .and_then(|referrers| referrers.get(&fee_mint))
+            .copied();
+
+        let pool_keys = PoolKeys::from_state(self.key, &self.virtual_pool, config)?;
+        let swap_accounts = get_swap_accounts(
+            &pool_keys,
+            &SwapAccounts {
+                input_token_account: swap_params.source_token_account,
+                output_token_account: swap_params.destination_token_account,
+                payer: swap_params.token_transfer_authority,
+                referral_token_account,
+            },
+        );
+        let mut account_metas = swap_accounts.to_account_metas(None);
+        account_metas.push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+        Ok(SwapAndAccountMetas {
+            swap: Swap::MeteoraDynamicBondingCurveSwapWithRemainingAccounts,
+            account_metas,
+        })
+    }
+
+    /// `swap` only takes an exact input
+    fn supports_exact_out(&self) -> bool {
+        false
+    }
+
+    fn clone_amm(&self) -> Box<dyn Amm + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn get_accounts_len(&self) -> usize {
+        SWAP_ACCOUNTS_LEN
+    }
+
+    /// Pool stops trading once the curve is completed, until it is migrated. It is also
+    /// inactive while routed buys are blocked, the route can't tell buys and sells apart
+    fn is_active(&self) -> bool {
+        match &self.config {
+            Some(config) => {
+                !self
+                    .virtual_pool
+                    .is_curve_complete(config.migration_quote_threshold)
+                    && matches!(self.is_buy_blocked(config), Ok(false))
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::Discriminator;
+    use dynamic_bonding_curve::{
+        constants::MAX_SQRT_PRICE, params::liquidity_distribution::LiquidityDistributionParameters,
+        state::CollectFeeMode,
+    };
+    use jupiter_amm_interface::{FeeMode as JupiterFeeMode, QuoteMintToReferrer};
+    use solana_account::Account;
+
+    use super::*;
+
+    fn to_account<T: Discriminator + bytemuck::Pod>(account: &T) -> Account {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(account));
+        Account {
+            lamports: 1_000_000,
+            data,
+            owner: dynamic_bonding_curve::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn get_amm() -> (VirtualPoolAmm, AccountMap) {
+        let pool_key = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+
+        let mut config = PoolConfig {
+            quote_mint: Pubkey::new_unique(),
+            migration_quote_threshold: 80_000_000_000,
+            sqrt_start_price: 2916686334356757,
+            collect_fee_mode: CollectFeeMode::QuoteToken.into(),
+            ..Default::default()
+        };
+        config.pool_fees.base_fee.cliff_fee_numerator = 10_000_000;
+        config.curve[0] = LiquidityDistributionParameters {
+            sqrt_price: 11666745337427032,
+            liquidity: 3111132089980541388292920297291756,
+        }
+        .to_liquidity_distribution_config();
+        config.curve[1] = LiquidityDistributionParameters {
+            sqrt_price: MAX_SQRT_PRICE,
+            liquidity: 1,
+        }
+        .to_liquidity_distribution_config();
+
+        let virtual_pool = VirtualPool {
+            config: config_key,
+            base_mint: Pubkey::new_unique(),
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            base_reserve: u64::MAX / 2,
+            sqrt_price: config.sqrt_start_price,
+            ..Default::default()
+        };
+
+        let keyed_account = KeyedAccount {
+            key: pool_key,
+            account: to_account(&virtual_pool),
+            params: None,
+        };
+        let mut account_map = AccountMap::default();
+        account_map.insert(pool_key, keyed_account.account.clone());
+        account_map.insert(config_key, to_account(&config));
+
+        let amm =
+            VirtualPoolAmm::from_keyed_account(&keyed_account, &AmmContext::default()).unwrap();
+        (amm, account_map)
+    }
+
+    fn get_swap_params<'a, 'b>(
+        source_mint: Pubkey,
+        destination_mint: Pubkey,
+        quote_mint_to_referrer: Option<&'a QuoteMintToReferrer>,
+        jupiter_program_id: &'b Pubkey,
+    ) -> SwapParams<'a, 'b> {
+        SwapParams {
+            swap_mode: SwapMode::ExactIn,
+            in_amount: 1_000,
+            out_amount: 0,
+            source_mint,
+            destination_mint,
+            source_token_account: Pubkey::new_unique(),
+            destination_token_account: Pubkey::new_unique(),
+            token_transfer_authority: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            quote_mint_to_referrer,
+            jupiter_program_id,
+            missing_dynamic_accounts_as_default: false,
+        }
+    }
+
+    #[test]
+    fn test_quote_and_account_metas() {
+        let (mut amm, account_map) = get_amm();
+        assert_eq!(amm.get_accounts_to_update().len(), 2);
+        assert!(!amm.is_active());
+
+        amm.update(&account_map).unwrap();
+        assert!(amm.is_active());
+        let [base_mint, quote_mint] = amm.get_reserve_mints()[..] else {
+            panic!("missing reserve mints");
+        };
+
+        let quote = amm
+            .quote(&QuoteParams {
+                amount: 1_000_000_000,
+                input_mint: quote_mint,
+                output_mint: base_mint,
+                swap_mode: SwapMode::ExactIn,
+                fee_mode: JupiterFeeMode::Normal,
+            })
+            .unwrap();
+        assert_eq!(quote.in_amount, 1_000_000_000);
+        assert!(quote.out_amount > 0);
+        assert_eq!(quote.fee_mint, quote_mint);
+        // 1% fee on the quote input
+        assert_eq!(quote.fee_pct, Decimal::new(1, 2));
+
+        assert!(!amm.supports_exact_out());
+        assert!(amm
+            .quote(&QuoteParams {
+                amount: quote.out_amount,
+                input_mint: quote_mint,
+                output_mint: base_mint,
+                swap_mode: SwapMode::ExactOut,
+                fee_mode: JupiterFeeMode::Normal,
+            })
+            .is_err());
+
+        let jupiter_program_id = Pubkey::new_unique();
+        let swap_and_account_metas = amm
+            .get_swap_and_account_metas(&get_swap_params(
+                quote_mint,
+                base_mint,
+                None,
+                &jupiter_program_id,
+            ))
+            .unwrap();
+        assert!(matches!(
+            swap_and_account_metas.swap,
+            Swap::MeteoraDynamicBondingCurveSwapWithRemainingAccounts
+        ));
+        let account_metas = swap_and_account_metas.account_metas;
+        assert_eq!(account_metas.len(), amm.get_accounts_len());
+        assert_eq!(account_metas[12].pubkey, dynamic_bonding_curve::ID);
+        assert!(account_metas[9].is_signer);
+        assert_eq!(account_metas[15].pubkey, sysvar::instructions::ID);
+
+        // referrer of the fee mint is used as referral token account
+        let referral_token_account = Pubkey::new_unique();
+        let mut referrers = QuoteMintToReferrer::default();
+        referrers.insert(quote_mint, referral_token_account);
+        let account_metas = amm
+            .get_swap_and_account_metas(&get_swap_params(
+                quote_mint,
+                base_mint,
+                Some(&referrers),
+                &jupiter_program_id,
+            ))
+            .unwrap()
+            .account_metas;
+        assert_eq!(account_metas[12].pubkey, referral_token_account);
+
+        assert!(amm
+            .get_swap_and_account_metas(&get_swap_params(
+                quote_mint,
+                Pubkey::new_unique(),
+                None,
+                &jupiter_program_id,
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn test_routed_buys_blocked() {
+        let (mut amm, account_map) = get_amm();
+        amm.update(&account_map).unwrap();
+        let [base_mint, quote_mint] = amm.get_reserve_mints()[..] else {
+            panic!("missing reserve mints");
+        };
+        let quote = |amm: &VirtualPoolAmm, input_mint, output_mint| {
+            amm.quote(&QuoteParams {
+                amount: 1_000_000_000,
+                input_mint,
+                output_mint,
+                swap_mode: SwapMode::ExactIn,
+                fee_mode: JupiterFeeMode::Normal,
+            })
+        };
+        // slot activation type
+        let end_slot = 100;
+
+        amm.virtual_pool.allowlist_merkle_root = [1; 32];
+        amm.virtual_pool.allowlist_end_point = end_slot;
+        assert!(!amm.is_active());
+        assert!(quote(&amm, quote_mint, base_mint).is_err());
+        amm.clock_ref.slot.store(end_slot, Ordering::Relaxed);
+        assert!(amm.is_active());
+        assert!(quote(&amm, quote_mint, base_mint).is_ok());
+
+        amm.clock_ref.slot.store(0, Ordering::Relaxed);
+        amm.virtual_pool.allowlist_merkle_root = [0; 32];
+        let config = amm.config.as_mut().unwrap();
+        config.buy_limit_duration = end_slot;
+        config.max_buy_quote_per_wallet = 1_000_000_000;
+        assert!(!amm.is_active());
+        assert!(quote(&amm, quote_mint, base_mint).is_err());
+        // sells aren't limited
+        amm.virtual_pool.quote_reserve = 1_000_000_000;
+        assert!(quote(&amm, base_mint, quote_mint).is_ok());
+        amm.clock_ref.slot.store(end_slot, Ordering::Relaxed);
+        assert!(amm.is_active());
+        assert!(quote(&amm, quote_mint, base_mint).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_account_data() {
+        let (amm, account_map) = get_amm();
+        // the config stored in the pool isn't a virtual pool
+        let config_key = amm.get_accounts_to_update()[1];
+        let keyed_account = KeyedAccount {
+            key: config_key,
+            account: account_map[&config_key].clone(),
+            params: None,
+        };
+        assert!(
+            VirtualPoolAmm::from_keyed_account(&keyed_account, &AmmContext::default()).is_err()
+        );
+
+        // a virtual pool owned by another program
+        let pool_key = amm.key();
+        let mut keyed_account = KeyedAccount {
+            key: pool_key,
+            account: account_map[&pool_key].clone(),
+            params: None,
+        };
+        keyed_account.account.owner = Pubkey::new_unique();
+        assert!(
+            VirtualPoolAmm::from_keyed_account(&keyed_account, &AmmContext::default()).is_err()
+        );
+    }
+}
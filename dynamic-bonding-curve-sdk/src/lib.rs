@@ -1 +1,17 @@
+pub mod accounts;
+pub mod allowlist;
+pub mod analytics;
+pub mod depth;
+pub mod design;
+pub mod events;
+pub mod fees;
+pub mod fit;
+pub mod instructions;
+pub mod jupiter;
+pub mod migration;
+pub mod pda;
+pub mod price;
 pub mod quote;
+pub mod replay;
+pub mod transfer_fee;
+pub mod validation;
@@ -0,0 +1,441 @@
+//! Simulate the migration of a completed pool without sending transactions.
+//!
+//! Follows the migration flow of the program: the locked vesting is moved to the creator
+//! escrow, the migration quote amount and base token are deposited in the DAMM pool, the rest
+//! of the base token is burnt up to the burnable amount and the remaining leftover can be
+//! withdrawn by the leftover receiver.
+use anyhow::{ensure, Context, Result};
+use dynamic_bonding_curve::{
+    constants::{MAX_SQRT_PRICE, MIN_SQRT_PRICE},
+    curve::{
+        get_initial_liquidity_from_delta_base, get_initial_liquidity_from_delta_quote,
+        get_initialize_amounts,
+    },
+    state::{
+        LiquidityDistribution, LiquidityDistributionItem, LiquidityDistributionU64,
+        MigrationAmount, MigrationFeeDistribution, MigrationOption, PoolConfig, VirtualPool,
+    },
+};
+use ruint::aliases::U512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LpOwner {
+    Partner,
+    Creator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DammV2Position {
+    pub owner: LpOwner,
+    pub unlocked_liquidity: u128,
+    pub locked_liquidity: u128,
+    /// token deposited for the position
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigratedLiquidity {
+    /// lp minted by a DAMM v1 constant product pool, estimated as `√(base * quote)` with fresh
+    /// vaults
+    MeteoraDamm {
+        lp_amount: u64,
+        partner_locked_lp: u64,
+        partner_lp: u64,
+        creator_locked_lp: u64,
+        creator_lp: u64,
+    },
+    /// the position with more liquidity creates the pool, the other one takes what is left
+    DammV2 {
+        liquidity: u128,
+        first_position: DammV2Position,
+        second_position: Option<DammV2Position>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MigrationSimulation {
+    pub migration_option: MigrationOption,
+    /// quote token deposited in the DAMM pool
+    pub quote_amount: u64,
+    pub migration_fee: u64,
+    pub partner_migration_fee: u64,
+    pub creator_migration_fee: u64,
+    /// base token deposited in the DAMM pool
+    pub base_amount: u64,
+    pub liquidity: MigratedLiquidity,
+    pub total_surplus: u64,
+    pub partner_surplus: u64,
+    pub creator_surplus: u64,
+    pub protocol_surplus: u64,
+    /// base token sent to the creator vesting escrow
+    pub locked_vesting_amount: u64,
+    pub vesting_start_time: u64,
+    pub vesting_cliff_time: u64,
+    pub burnt_base_amount: u64,
+    /// base token left for the leftover receiver
+    pub leftover_base_amount: u64,
+    /// quote token left in the quote vault after deposits, excluding fees and surplus
+    pub leftover_quote_amount: u64,
+}
+
+/// Simulate the migration of `virtual_pool`, the pool must have completed its curve
+pub fn simulate_migration(
+    config: &PoolConfig,
+    virtual_pool: &VirtualPool,
+) -> Result<MigrationSimulation> {
+    ensure!(
+        virtual_pool.is_curve_complete(config.migration_quote_threshold),
+        "virtual pool is not completed"
+    );
+    let migration_option = config.get_migration_option()?;
+
+    let MigrationAmount {
+        quote_amount,
+        fee: migration_fee,
+    } = config.get_migration_quote_amount_for_config()?;
+    let MigrationFeeDistribution {
+        partner_migration_fee,
+        creator_migration_fee,
+    } = config.get_migration_fee_distribution()?;
+
+    let total_surplus = virtual_pool.get_total_surplus(config.migration_quote_threshold)?;
+    let partner_surplus = virtual_pool.get_partner_surplus(config, total_surplus)?;
+    let creator_surplus = virtual_pool.get_creator_surplus(config, total_surplus)?;
+    let protocol_surplus = virtual_pool.get_protocol_surplus(config.migration_quote_threshold)?;
+
+    let locked_vesting_params = config.locked_vesting_config.to_locked_vesting_params();
+    let locked_vesting_amount = if locked_vesting_params.has_vesting() {
+        locked_vesting_params.get_total_amount()?
+    } else {
+        0
+    };
+    let vesting_start_time = virtual_pool.finish_curve_timestamp;
+    let vesting_cliff_time = vesting_start_time
+        .checked_add(locked_vesting_params.cliff_duration_from_migration_time)
+        .context("vesting cliff time overflow")?;
+
+    // base vault without trading fees
+    let base_reserve = virtual_pool
+        .base_reserve
+        .checked_sub(locked_vesting_amount)
+        .context("base reserve can't cover the locked vesting")?;
+
+    let (liquidity, base_amount, deposited_quote_amount) = match migration_option {
+        MigrationOption::MeteoraDamm => {
+            let base_amount = config.migration_base_threshold;
+            let lp_amount =
+                u64::try_from((u128::from(base_amount) * u128::from(quote_amount)).isqrt())
+                    .context("lp amount overflow")?;
+            let LiquidityDistributionU64 {
+                partner_locked_lp,
+                partner_lp,
+                creator_locked_lp,
+                creator_lp,
+            } = config.get_lp_distribution(lp_amount)?;
+            (
+                MigratedLiquidity::MeteoraDamm {
+                    lp_amount,
+                    partner_locked_lp,
+                    partner_lp,
+                    creator_locked_lp,
+                    creator_lp,
+                },
+                base_amount,
+                quote_amount,
+            )
+        }
+        MigrationOption::DammV2 => simulate_damm_v2_liquidity(config, base_reserve, quote_amount)?,
+    };
+
+    let left_base_amount = base_reserve
+        .checked_sub(base_amount)
+        .context("base reserve can't cover the migration base amount")?;
+    let burnt_base_amount = config.get_burnable_amount_post_migration(left_base_amount)?;
+
+    Ok(MigrationSimulation {
+        migration_option,
+        quote_amount: deposited_quote_amount,
+        migration_fee,
+        partner_migration_fee,
+        creator_migration_fee,
+        base_amount,
+        liquidity,
+        total_surplus,
+        partner_surplus,
+        creator_surplus,
+        protocol_surplus,
+        locked_vesting_amount,
+        vesting_start_time,
+        vesting_cliff_time,
+        burnt_base_amount,
+        leftover_base_amount: left_base_amount - burnt_base_amount,
+        leftover_quote_amount: quote_amount - deposited_quote_amount,
+    })
+}
+
+fn simulate_damm_v2_liquidity(
+    config: &PoolConfig,
+    base_reserve: u64,
+    quote_amount: u64,
+) -> Result<(MigratedLiquidity, u64, u64)> {
+    let sqrt_price = config.migration_sqrt_price;
+    let liquidity = get_liquidity_for_adding_liquidity(base_reserve, quote_amount, sqrt_price)?;
+    let LiquidityDistribution { partner, creator } =
+        config.get_liquidity_distribution(liquidity)?;
+
+    let ((first, first_owner), (second, second_owner)) =
+        if partner.get_total_liquidity()? > creator.get_total_liquidity()? {
+            ((partner, LpOwner::Partner), (creator, LpOwner::Creator))
+        } else {
+            ((creator, LpOwner::Creator), (partner, LpOwner::Partner))
+        };
+
+    let first_liquidity = first.get_total_liquidity()?;
+    let (first_base_amount, first_quote_amount) =
+        get_initialize_amounts(MIN_SQRT_PRICE, MAX_SQRT_PRICE, sqrt_price, first_liquidity)?;
+    let first_position = DammV2Position {
+        owner: first_owner,
+        unlocked_liquidity: first.unlocked_liquidity,
+        locked_liquidity: first.locked_liquidity,
+        base_amount: first_base_amount,
+        quote_amount: first_quote_amount,
+    };
+
+    let remaining_base_amount = base_reserve
+        .checked_sub(first_base_amount)
+        .context("base reserve can't cover the first position")?;
+    let remaining_quote_amount = quote_amount
+        .checked_sub(first_quote_amount)
+        .context("quote amount can't cover the first position")?;
+    let second_liquidity = get_liquidity_for_adding_liquidity(
+        remaining_base_amount,
+        remaining_quote_amount,
+        sqrt_price,
+    )?;
+    let second_position = if second_liquidity > 0 {
+        let LiquidityDistributionItem {
+            unlocked_liquidity, ..
+        } = second;
+        let unlocked_liquidity = second_liquidity.min(unlocked_liquidity);
+        let (base_amount, quote_amount) =
+            get_initialize_amounts(MIN_SQRT_PRICE, MAX_SQRT_PRICE, sqrt_price, second_liquidity)?;
+        Some(DammV2Position {
+            owner: second_owner,
+            unlocked_liquidity,
+            locked_liquidity: second_liquidity - unlocked_liquidity,
+            base_amount,
+            quote_amount,
+        })
+    } else {
+        None
+    };
+
+    let (second_base_amount, second_quote_amount) = second_position
+        .map(|position| (position.base_amount, position.quote_amount))
+        .unwrap_or_default();
+    Ok((
+        MigratedLiquidity::DammV2 {
+            liquidity,
+            first_position,
+            second_position,
+        },
+        first_base_amount + second_base_amount,
+        first_quote_amount + second_quote_amount,
+    ))
+}
+
+/// Same as the program, the liquidity of the token that runs out first
+fn get_liquidity_for_adding_liquidity(
+    base_amount: u64,
+    quote_amount: u64,
+    sqrt_price: u128,
+) -> Result<u128> {
+    let liquidity_from_base =
+        get_initial_liquidity_from_delta_base(base_amount, MAX_SQRT_PRICE, sqrt_price)?;
+    let liquidity_from_quote =
+        get_initial_liquidity_from_delta_quote(quote_amount, MIN_SQRT_PRICE, sqrt_price)?;
+    if liquidity_from_base > U512::from(liquidity_from_quote) {
+        Ok(liquidity_from_quote)
+    } else {
+        Ok(liquidity_from_base
+            .try_into()
+            .context("liquidity overflow")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::LockedVestingParams;
+
+    use super::*;
+    use crate::design::{design_curve, CurveDesignParams};
+
+    fn get_config_and_pool(migration_option: MigrationOption) -> (PoolConfig, VirtualPool) {
+        let locked_vesting = LockedVestingParams {
+            amount_per_period: 1_000_000,
+            frequency: 1,
+            number_of_period: 120,
+            cliff_unlock_amount: 1_000_000,
+            cliff_duration_from_migration_time: 3_600,
+        };
+        let params = CurveDesignParams {
+            total_token_supply: 1_000_000_000,
+            percentage_supply_on_curve: 70,
+            initial_market_cap: 30.0,
+            migration_market_cap: 300.0,
+            token_base_decimal: 6,
+            token_quote_decimal: 9,
+            migration_option,
+            migration_fee_percentage: 5,
+            locked_vesting,
+        };
+        let design = design_curve(&params).unwrap();
+        let mut config = PoolConfig {
+            token_decimal: 6,
+            sqrt_start_price: design.sqrt_start_price,
+            migration_quote_threshold: design.migration_quote_threshold,
+            migration_sqrt_price: design.sqrt_migration_price,
+            swap_base_amount: design.swap_base_amount,
+            migration_base_threshold: design.migration_base_amount,
+            migration_option: migration_option.into(),
+            migration_fee_percentage: 5,
+            creator_migration_fee_percentage: 20,
+            creator_trading_fee_percentage: 50,
+            partner_locked_lp_percentage: 30,
+            partner_lp_percentage: 30,
+            creator_locked_lp_percentage: 20,
+            creator_lp_percentage: 20,
+            fixed_token_supply_flag: 1,
+            pre_migration_token_supply: design.token_supply.pre_migration_token_supply,
+            post_migration_token_supply: design.token_supply.post_migration_token_supply,
+            ..Default::default()
+        };
+        config.locked_vesting_config.amount_per_period = locked_vesting.amount_per_period;
+        config.locked_vesting_config.frequency = locked_vesting.frequency;
+        config.locked_vesting_config.number_of_period = locked_vesting.number_of_period;
+        config.locked_vesting_config.cliff_unlock_amount = locked_vesting.cliff_unlock_amount;
+        config
+            .locked_vesting_config
+            .cliff_duration_from_migration_time = locked_vesting.cliff_duration_from_migration_time;
+        for (i, point) in design.curve.iter().enumerate() {
+            config.curve[i] = point.to_liquidity_distribution_config();
+        }
+
+        let pool = VirtualPool {
+            sqrt_price: design.sqrt_migration_price,
+            base_reserve: config.pre_migration_token_supply - design.swap_base_amount,
+            quote_reserve: design.migration_quote_threshold + 1_000_000_000,
+            finish_curve_timestamp: 1_000,
+            ..Default::default()
+        };
+        (config, pool)
+    }
+
+    fn assert_common(config: &PoolConfig, pool: &VirtualPool, simulation: &MigrationSimulation) {
+        assert_eq!(
+            simulation.quote_amount + simulation.leftover_quote_amount + simulation.migration_fee,
+            config.migration_quote_threshold
+        );
+        assert_eq!(
+            simulation.partner_migration_fee + simulation.creator_migration_fee,
+            simulation.migration_fee
+        );
+        assert_eq!(
+            simulation.creator_migration_fee,
+            simulation.migration_fee * 20 / 100
+        );
+
+        assert_eq!(simulation.total_surplus, 1_000_000_000);
+        assert_eq!(
+            simulation.partner_surplus + simulation.creator_surplus + simulation.protocol_surplus,
+            simulation.total_surplus
+        );
+
+        assert_eq!(simulation.locked_vesting_amount, 121_000_000);
+        assert_eq!(simulation.vesting_start_time, 1_000);
+        assert_eq!(simulation.vesting_cliff_time, 4_600);
+
+        // every base token of the reserve is accounted for
+        assert_eq!(
+            simulation.locked_vesting_amount
+                + simulation.base_amount
+                + simulation.burnt_base_amount
+                + simulation.leftover_base_amount,
+            pool.base_reserve
+        );
+        assert!(
+            simulation.burnt_base_amount
+                <= config.pre_migration_token_supply - config.post_migration_token_supply
+        );
+    }
+
+    #[test]
+    fn test_simulate_migration_meteora_damm() {
+        let (config, pool) = get_config_and_pool(MigrationOption::MeteoraDamm);
+        let simulation = simulate_migration(&config, &pool).unwrap();
+        assert_common(&config, &pool, &simulation);
+        assert_eq!(simulation.base_amount, config.migration_base_threshold);
+        assert_eq!(simulation.leftover_quote_amount, 0);
+
+        let MigratedLiquidity::MeteoraDamm {
+            lp_amount,
+            partner_locked_lp,
+            partner_lp,
+            creator_locked_lp,
+            creator_lp,
+        } = simulation.liquidity
+        else {
+            panic!("unexpected liquidity {:?}", simulation.liquidity);
+        };
+        assert!(lp_amount > 0);
+        assert_eq!(
+            partner_locked_lp + partner_lp + creator_locked_lp + creator_lp,
+            lp_amount
+        );
+        assert_eq!(partner_locked_lp, lp_amount * 30 / 100);
+
+        let mut pool = pool;
+        pool.quote_reserve = config.migration_quote_threshold - 1;
+        assert!(simulate_migration(&config, &pool).is_err());
+    }
+
+    #[test]
+    fn test_simulate_migration_damm_v2() {
+        let (config, pool) = get_config_and_pool(MigrationOption::DammV2);
+        let simulation = simulate_migration(&config, &pool).unwrap();
+        assert_common(&config, &pool, &simulation);
+
+        let MigratedLiquidity::DammV2 {
+            liquidity,
+            first_position,
+            second_position,
+        } = simulation.liquidity
+        else {
+            panic!("unexpected liquidity {:?}", simulation.liquidity);
+        };
+        let second_position = second_position.unwrap();
+        // partner has 60% of the liquidity
+        assert_eq!(first_position.owner, LpOwner::Partner);
+        assert_eq!(second_position.owner, LpOwner::Creator);
+        assert_eq!(first_position.unlocked_liquidity, liquidity * 30 / 100);
+        assert_eq!(first_position.locked_liquidity, liquidity * 30 / 100);
+        assert!(
+            first_position.unlocked_liquidity
+                + first_position.locked_liquidity
+                + second_position.unlocked_liquidity
+                + second_position.locked_liquidity
+                <= liquidity
+        );
+        assert_eq!(
+            first_position.base_amount + second_position.base_amount,
+            simulation.base_amount
+        );
+        assert_eq!(
+            first_position.quote_amount + second_position.quote_amount,
+            simulation.quote_amount
+        );
+        // the quote token is almost fully deposited
+        assert!(simulation.leftover_quote_amount < 10);
+    }
+}
@@ -0,0 +1,283 @@
+//! Program derived addresses used by dynamic bonding curve instructions.
+//!
+//! Seeds mirror the `seeds = [...]` constraints of the program accounts and the
+//! pda derivation of the external programs the migration instructions call into.
+use anchor_lang::prelude::*;
+use dynamic_bonding_curve::{
+    const_pda,
+    constants::seeds::{
+        ALLOWLIST_ENTRY_PREFIX, BASE_LOCKER_PREFIX, BUY_TRACKER_PREFIX, CLAIM_FEE_OPERATOR_PREFIX,
+        DAMM_V2_METADATA_PREFIX, METEORA_METADATA_PREFIX, PARTNER_METADATA_PREFIX,
+        POOL_AUTHORITY_PREFIX, POOL_PREFIX, POSITION_NFT_ACCOUNT_PREFIX, POSITION_PREFIX,
+        TOKEN_VAULT_PREFIX, VIRTUAL_POOL_METADATA_PREFIX,
+    },
+    max_key, min_key,
+};
+
+pub const EVENT_AUTHORITY_SEEDS: &[u8] = b"__event_authority";
+
+/// Meteora dynamic vault program, used by damm v1 pools
+pub const VAULT_PROGRAM_ID: Pubkey = pubkey!("24Uqj9JCLxUeoC3hGfh5W3s9FM9uCHDS2SG3LYwBpyTi");
+
+pub fn derive_pool_authority() -> Pubkey {
+    const_pda::pool_authority::ID
+}
+
+pub fn derive_event_authority() -> Pubkey {
+    derive_event_authority_for(&dynamic_bonding_curve::ID)
+}
+
+pub fn derive_pool(config: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            POOL_PREFIX,
+            config.as_ref(),
+            &max_key(base_mint, quote_mint),
+            &min_key(base_mint, quote_mint),
+        ],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_token_vault(mint: &Pubkey, pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TOKEN_VAULT_PREFIX, mint.as_ref(), pool.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_claim_fee_operator(operator: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[CLAIM_FEE_OPERATOR_PREFIX, operator.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_partner_metadata(fee_claimer: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[PARTNER_METADATA_PREFIX, fee_claimer.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_virtual_pool_metadata(virtual_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[VIRTUAL_POOL_METADATA_PREFIX, virtual_pool.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_meteora_damm_migration_metadata(virtual_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[METEORA_METADATA_PREFIX, virtual_pool.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_damm_v2_migration_metadata(virtual_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[DAMM_V2_METADATA_PREFIX, virtual_pool.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+pub fn derive_base_locker(virtual_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[BASE_LOCKER_PREFIX, virtual_pool.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+/// Quote spent by `owner` in the allowlist phase of a pool
+pub fn derive_allowlist_entry(virtual_pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            ALLOWLIST_ENTRY_PREFIX,
+            virtual_pool.as_ref(),
+            owner.as_ref(),
+        ],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+/// Quote spent by `owner` while the buy limits of a pool apply
+pub fn derive_buy_tracker(virtual_pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[BUY_TRACKER_PREFIX, virtual_pool.as_ref(), owner.as_ref()],
+        &dynamic_bonding_curve::ID,
+    )
+    .0
+}
+
+/// Metaplex metadata account of a mint
+pub fn derive_mint_metadata(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+fn derive_event_authority_for(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEEDS], program_id).0
+}
+
+/// locker
+pub fn derive_locker_escrow(base: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", base.as_ref()], &locker::ID).0
+}
+
+pub fn derive_locker_event_authority() -> Pubkey {
+    derive_event_authority_for(&locker::ID)
+}
+
+/// damm v1
+pub fn derive_damm_pool(
+    damm_config: &Pubkey,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            &max_key(token_a_mint, token_b_mint),
+            &min_key(token_a_mint, token_b_mint),
+            damm_config.as_ref(),
+        ],
+        &dynamic_amm::ID,
+    )
+    .0
+}
+
+pub fn derive_damm_lp_mint(damm_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lp_mint", damm_pool.as_ref()], &dynamic_amm::ID).0
+}
+
+pub fn derive_damm_protocol_fee(mint: &Pubkey, damm_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"fee", mint.as_ref(), damm_pool.as_ref()],
+        &dynamic_amm::ID,
+    )
+    .0
+}
+
+pub fn derive_damm_vault_lp(vault: &Pubkey, damm_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[vault.as_ref(), damm_pool.as_ref()], &dynamic_amm::ID).0
+}
+
+pub fn derive_damm_lock_escrow(damm_pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"lock_escrow", damm_pool.as_ref(), owner.as_ref()],
+        &dynamic_amm::ID,
+    )
+    .0
+}
+
+/// Dynamic vault accounts of a mint, `vault_base` is the base key the vault program was deployed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DynamicVault {
+    pub vault: Pubkey,
+    pub token_vault: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+pub fn derive_dynamic_vault(mint: &Pubkey, vault_base: &Pubkey) -> DynamicVault {
+    let vault = Pubkey::find_program_address(
+        &[b"vault", mint.as_ref(), vault_base.as_ref()],
+        &VAULT_PROGRAM_ID,
+    )
+    .0;
+    let token_vault =
+        Pubkey::find_program_address(&[b"token_vault", vault.as_ref()], &VAULT_PROGRAM_ID).0;
+    let lp_mint = Pubkey::find_program_address(&[b"lp_mint", vault.as_ref()], &VAULT_PROGRAM_ID).0;
+    DynamicVault {
+        vault,
+        token_vault,
+        lp_mint,
+    }
+}
+
+/// damm v2
+pub fn derive_damm_v2_pool(
+    damm_config: &Pubkey,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            POOL_PREFIX,
+            damm_config.as_ref(),
+            &max_key(token_a_mint, token_b_mint),
+            &min_key(token_a_mint, token_b_mint),
+        ],
+        &damm_v2::ID,
+    )
+    .0
+}
+
+pub fn derive_damm_v2_pool_authority() -> Pubkey {
+    Pubkey::find_program_address(&[POOL_AUTHORITY_PREFIX], &damm_v2::ID).0
+}
+
+pub fn derive_damm_v2_token_vault(mint: &Pubkey, damm_pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TOKEN_VAULT_PREFIX, mint.as_ref(), damm_pool.as_ref()],
+        &damm_v2::ID,
+    )
+    .0
+}
+
+pub fn derive_damm_v2_position(position_nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[POSITION_PREFIX, position_nft_mint.as_ref()], &damm_v2::ID).0
+}
+
+pub fn derive_damm_v2_position_nft_account(position_nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[POSITION_NFT_ACCOUNT_PREFIX, position_nft_mint.as_ref()],
+        &damm_v2::ID,
+    )
+    .0
+}
+
+pub fn derive_damm_v2_event_authority() -> Pubkey {
+    derive_event_authority_for(&damm_v2::ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_authority_matches_seeds() {
+        let (pool_authority, _) =
+            Pubkey::find_program_address(&[POOL_AUTHORITY_PREFIX], &dynamic_bonding_curve::ID);
+        assert_eq!(derive_pool_authority(), pool_authority);
+    }
+
+    #[test]
+    fn test_pool_is_independent_of_mint_order() {
+        let config = Pubkey::new_unique();
+        let base_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        assert_eq!(
+            derive_pool(&config, &base_mint, &quote_mint),
+            derive_pool(&config, &quote_mint, &base_mint)
+        );
+        assert_eq!(
+            derive_damm_pool(&config, &base_mint, &quote_mint),
+            derive_damm_pool(&config, &quote_mint, &base_mint)
+        );
+        assert_eq!(
+            derive_damm_v2_pool(&config, &base_mint, &quote_mint),
+            derive_damm_v2_pool(&config, &quote_mint, &base_mint)
+        );
+    }
+}
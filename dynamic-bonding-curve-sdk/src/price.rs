@@ -0,0 +1,218 @@
+//! Convert Q64.64 sqrt prices to human prices and market caps.
+//!
+//! `sqrt_price` is the square root of the price of one base token unit in quote token units,
+//! shifted by 64 bits. The human price is the price of one whole base token in whole quote
+//! tokens: `price = (sqrt_price / 2^64)^2 * 10^(token_base_decimal - token_quote_decimal)`.
+use anyhow::{ensure, Result};
+use dynamic_bonding_curve::state::{PoolConfig, VirtualPool};
+
+const Q64: f64 = 18446744073709551616.0;
+
+fn get_decimal_factor(token_base_decimal: u8, token_quote_decimal: u8) -> f64 {
+    10f64.powi(i32::from(token_base_decimal) - i32::from(token_quote_decimal))
+}
+
+/// Price of one whole base token in quote token
+pub fn get_price_from_sqrt_price(
+    sqrt_price: u128,
+    token_base_decimal: u8,
+    token_quote_decimal: u8,
+) -> f64 {
+    let sqrt_price = sqrt_price as f64 / Q64;
+    sqrt_price * sqrt_price * get_decimal_factor(token_base_decimal, token_quote_decimal)
+}
+
+/// Sqrt price of a price of one whole base token in quote token, rounded down
+pub fn get_sqrt_price_from_price(
+    price: f64,
+    token_base_decimal: u8,
+    token_quote_decimal: u8,
+) -> Result<u128> {
+    ensure!(
+        price.is_finite() && price > 0.0,
+        "price must be positive and finite"
+    );
+    let sqrt_price =
+        (price / get_decimal_factor(token_base_decimal, token_quote_decimal)).sqrt() * Q64;
+    ensure!(sqrt_price < u128::MAX as f64, "sqrt price overflow");
+    Ok(sqrt_price as u128)
+}
+
+/// Market cap in quote token, `supply` is in base token units
+pub fn get_market_cap_from_sqrt_price(
+    sqrt_price: u128,
+    supply: u64,
+    token_base_decimal: u8,
+    token_quote_decimal: u8,
+) -> f64 {
+    let price = get_price_from_sqrt_price(sqrt_price, token_base_decimal, token_quote_decimal);
+    price * supply as f64 / 10f64.powi(token_base_decimal.into())
+}
+
+/// Sqrt price at which `supply` base token units are worth `market_cap` quote token
+pub fn get_sqrt_price_from_market_cap(
+    market_cap: f64,
+    supply: u64,
+    token_base_decimal: u8,
+    token_quote_decimal: u8,
+) -> Result<u128> {
+    ensure!(supply > 0, "supply is zero");
+    let price = market_cap / (supply as f64 / 10f64.powi(token_base_decimal.into()));
+    get_sqrt_price_from_price(price, token_base_decimal, token_quote_decimal)
+}
+
+/// Prices of a config, the base token decimal is the config `token_decimal`
+pub trait PoolConfigPrice {
+    fn get_start_price(&self, token_quote_decimal: u8) -> f64;
+
+    fn get_migration_price(&self, token_quote_decimal: u8) -> f64;
+
+    /// Base token minted when a pool is created
+    fn get_pre_migration_supply(&self) -> Result<u64>;
+
+    /// Base token supply after migration, the unsold buffer is burnt.
+    /// Swaps over the migration threshold may leave a bit more
+    fn get_post_migration_supply(&self) -> Result<u64>;
+
+    fn get_start_market_cap(&self, token_quote_decimal: u8) -> Result<f64>;
+
+    fn get_migration_market_cap(&self, token_quote_decimal: u8) -> Result<f64>;
+}
+
+impl PoolConfigPrice for PoolConfig {
+    fn get_start_price(&self, token_quote_decimal: u8) -> f64 {
+        get_price_from_sqrt_price(
+            self.sqrt_start_price,
+            self.token_decimal,
+            token_quote_decimal,
+        )
+    }
+
+    fn get_migration_price(&self, token_quote_decimal: u8) -> f64 {
+        get_price_from_sqrt_price(
+            self.migration_sqrt_price,
+            self.token_decimal,
+            token_quote_decimal,
+        )
+    }
+
+    fn get_pre_migration_supply(&self) -> Result<u64> {
+        Ok(self.get_initial_base_supply()?)
+    }
+
+    fn get_post_migration_supply(&self) -> Result<u64> {
+        if self.is_fixed_token_supply() {
+            Ok(self.post_migration_token_supply)
+        } else {
+            Ok(PoolConfig::get_total_token_supply(
+                self.swap_base_amount,
+                self.migration_base_threshold,
+                &self.locked_vesting_config.to_locked_vesting_params(),
+            )?)
+        }
+    }
+
+    fn get_start_market_cap(&self, token_quote_decimal: u8) -> Result<f64> {
+        Ok(get_market_cap_from_sqrt_price(
+            self.sqrt_start_price,
+            self.get_pre_migration_supply()?,
+            self.token_decimal,
+            token_quote_decimal,
+        ))
+    }
+
+    fn get_migration_market_cap(&self, token_quote_decimal: u8) -> Result<f64> {
+        Ok(get_market_cap_from_sqrt_price(
+            self.migration_sqrt_price,
+            self.get_post_migration_supply()?,
+            self.token_decimal,
+            token_quote_decimal,
+        ))
+    }
+}
+
+/// Prices of a pool, decimals are taken from its config
+pub trait VirtualPoolPrice {
+    fn get_current_price(&self, config: &PoolConfig, token_quote_decimal: u8) -> f64;
+
+    /// Market cap of the pre migration supply at the current price
+    fn get_current_market_cap(&self, config: &PoolConfig, token_quote_decimal: u8) -> Result<f64>;
+}
+
+impl VirtualPoolPrice for VirtualPool {
+    fn get_current_price(&self, config: &PoolConfig, token_quote_decimal: u8) -> f64 {
+        get_price_from_sqrt_price(self.sqrt_price, config.token_decimal, token_quote_decimal)
+    }
+
+    fn get_current_market_cap(&self, config: &PoolConfig, token_quote_decimal: u8) -> Result<f64> {
+        Ok(get_market_cap_from_sqrt_price(
+            self.sqrt_price,
+            config.get_pre_migration_supply()?,
+            config.token_decimal,
+            token_quote_decimal,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() <= b.abs() * 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_price_conversion() {
+        // 1 quote atom per base atom, 6 base decimals and 9 quote decimals
+        let sqrt_price = 1u128 << 64;
+        assert_close(get_price_from_sqrt_price(sqrt_price, 6, 9), 0.001);
+        assert_close(get_price_from_sqrt_price(sqrt_price, 9, 6), 1000.0);
+        assert_close(get_price_from_sqrt_price(sqrt_price, 6, 6), 1.0);
+
+        for (base_decimal, quote_decimal) in [(6, 9), (9, 6), (9, 9)] {
+            for price in [0.000_000_03, 0.5, 1.0, 123.456] {
+                let sqrt_price =
+                    get_sqrt_price_from_price(price, base_decimal, quote_decimal).unwrap();
+                assert_close(
+                    get_price_from_sqrt_price(sqrt_price, base_decimal, quote_decimal),
+                    price,
+                );
+            }
+        }
+        assert!(get_sqrt_price_from_price(0.0, 6, 9).is_err());
+        assert!(get_sqrt_price_from_price(f64::NAN, 6, 9).is_err());
+    }
+
+    #[test]
+    fn test_market_cap() {
+        let supply = 1_000_000_000_000_000; // 1B tokens with 6 decimals
+        let sqrt_price = get_sqrt_price_from_market_cap(30.0, supply, 6, 9).unwrap();
+        assert_close(get_price_from_sqrt_price(sqrt_price, 6, 9), 0.000_000_03);
+        assert_close(
+            get_market_cap_from_sqrt_price(sqrt_price, supply, 6, 9),
+            30.0,
+        );
+
+        let config = PoolConfig {
+            token_decimal: 6,
+            sqrt_start_price: sqrt_price,
+            migration_sqrt_price: sqrt_price * 4,
+            fixed_token_supply_flag: 1,
+            pre_migration_token_supply: supply,
+            post_migration_token_supply: supply / 2,
+            ..Default::default()
+        };
+        assert_close(config.get_start_price(9), 0.000_000_03);
+        assert_close(config.get_migration_price(9), 0.000_000_48);
+        assert_close(config.get_start_market_cap(9).unwrap(), 30.0);
+        assert_close(config.get_migration_market_cap(9).unwrap(), 240.0);
+
+        let pool = VirtualPool {
+            sqrt_price: sqrt_price * 2,
+            ..Default::default()
+        };
+        assert_close(pool.get_current_price(&config, 9), 0.000_000_12);
+        assert_close(pool.get_current_market_cap(&config, 9).unwrap(), 120.0);
+    }
+}
@@ -2,9 +2,16 @@ use anyhow::{ensure, Context, Result};
 use dynamic_bonding_curve::{
     activation_handler::ActivationType,
     params::swap::TradeDirection,
-    state::{fee::FeeMode, PoolConfig, SwapResult, VirtualPool},
+    state::{fee::FeeMode, PoolConfig, SwapResult2, VirtualPool},
 };
 
+use crate::transfer_fee::{
+    calculate_transfer_fee_excluded_amount, calculate_transfer_fee_included_amount,
+};
+
+/// `is_wallet_tracked` is true for buys through `swap_with_buy_tracker`, other buys fail while
+/// the config has a per wallet limit
+#[allow(clippy::too_many_arguments)]
 pub fn quote_exact_in(
     virtual_pool: &VirtualPool,
     config: &PoolConfig,
@@ -13,7 +20,35 @@ pub fn quote_exact_in(
     current_slot: u64,
     transfer_fee_excluded_amount_in: u64, // must be calculated from outside
     has_referral: bool,
-) -> Result<SwapResult> {
+    is_wallet_tracked: bool,
+) -> Result<SwapResult2> {
+    quote_exact_in_with_sqrt_price_limit(
+        virtual_pool,
+        config,
+        swap_base_for_quote,
+        current_timestamp,
+        current_slot,
+        transfer_fee_excluded_amount_in,
+        has_referral,
+        is_wallet_tracked,
+        None,
+    )
+}
+
+/// When `sqrt_price_limit` is reached, the swap stops and only consumes part of the input.
+/// The consumed amount is `swap_result.consumed_input_amount`, the rest is not transferred.
+#[allow(clippy::too_many_arguments)]
+pub fn quote_exact_in_with_sqrt_price_limit(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    swap_base_for_quote: bool,
+    current_timestamp: u64,
+    current_slot: u64,
+    transfer_fee_excluded_amount_in: u64, // must be calculated from outside
+    has_referral: bool,
+    is_wallet_tracked: bool,
+    sqrt_price_limit: Option<u128>,
+) -> Result<SwapResult2> {
     let mut virtual_pool = *virtual_pool;
 
     ensure!(
@@ -30,20 +65,395 @@ pub fn quote_exact_in(
         ActivationType::Slot => current_slot,
         ActivationType::Timestamp => current_timestamp,
     };
+    ensure!(
+        current_point >= virtual_pool.activation_point,
+        "virtual pool is not activated"
+    );
 
     let trade_direction = if swap_base_for_quote {
         TradeDirection::BaseToQuote
     } else {
         TradeDirection::QuoteToBase
     };
+    // allowlisted buys go through `swap_allowlisted`, other buys fail until the phase ends
+    ensure!(
+        trade_direction == TradeDirection::BaseToQuote
+            || !virtual_pool.is_allowlist_phase(current_point),
+        "virtual pool is in allowlist phase"
+    );
     let fee_mode = &FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral)?;
-    let swap_result = virtual_pool.get_swap_result(
-        &config,
+    let swap_result = virtual_pool.get_swap_result_with_sqrt_price_limit(
+        config,
         transfer_fee_excluded_amount_in,
         fee_mode,
         trade_direction,
         current_point,
+        sqrt_price_limit,
+    )?;
+    // the quote spent by the wallet so far is in its buy tracker, only its presence is checked
+    config.validate_buy_limit(
+        swap_result.consumed_input_amount,
+        current_point,
+        virtual_pool.activation_point,
+        trade_direction,
+        is_wallet_tracked,
     )?;
 
     Ok(swap_result)
 }
+
+/// Required input is `swap_result.consumed_input_amount`, before token 2022 transfer fee
+#[allow(clippy::too_many_arguments)]
+pub fn quote_exact_out(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    swap_base_for_quote: bool,
+    current_timestamp: u64,
+    current_slot: u64,
+    amount_out: u64,
+    has_referral: bool,
+    is_wallet_tracked: bool,
+) -> Result<SwapResult2> {
+    let mut virtual_pool = *virtual_pool;
+
+    ensure!(
+        !virtual_pool.is_curve_complete(config.migration_quote_threshold),
+        "virtual pool is completed"
+    );
+
+    ensure!(amount_out > 0, "amount is zero");
+
+    virtual_pool.update_pre_swap(config, current_timestamp)?;
+    let activation_type =
+        ActivationType::try_from(config.activation_type).context("invalid activation type")?;
+    let current_point = match activation_type {
+        ActivationType::Slot => current_slot,
+        ActivationType::Timestamp => current_timestamp,
+    };
+    ensure!(
+        current_point >= virtual_pool.activation_point,
+        "virtual pool is not activated"
+    );
+
+    let trade_direction = if swap_base_for_quote {
+        TradeDirection::BaseToQuote
+    } else {
+        TradeDirection::QuoteToBase
+    };
+    // allowlisted buys go through `swap_allowlisted`, other buys fail until the phase ends
+    ensure!(
+        trade_direction == TradeDirection::BaseToQuote
+            || !virtual_pool.is_allowlist_phase(current_point),
+        "virtual pool is in allowlist phase"
+    );
+    let fee_mode = &FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral)?;
+    let swap_result = virtual_pool.get_swap_result_from_exact_output(
+        config,
+        amount_out,
+        fee_mode,
+        trade_direction,
+        current_point,
+    )?;
+    // the quote spent by the wallet so far is in its buy tracker, only its presence is checked
+    config.validate_buy_limit(
+        swap_result.consumed_input_amount,
+        current_point,
+        virtual_pool.activation_point,
+        trade_direction,
+        is_wallet_tracked,
+    )?;
+
+    virtual_pool.apply_swap_result(
+        config,
+        &swap_result,
+        fee_mode,
+        trade_direction,
+        current_timestamp,
+    )?;
+    ensure!(
+        !virtual_pool.is_curve_complete(config.migration_quote_threshold),
+        "swap would complete the curve"
+    );
+
+    Ok(swap_result)
+}
+
+/// End to end swap quote, including token 2022 transfer fee on both sides
+#[derive(Debug)]
+pub struct SwapQuoteWithTransferFee {
+    /// amount user sends
+    pub amount_in: u64,
+    /// amount lands in the vault, after input transfer fee
+    pub vault_amount_in: u64,
+    /// amount user finally receives, after output transfer fee
+    pub amount_out: u64,
+    pub input_transfer_fee: u64,
+    pub output_transfer_fee: u64,
+    pub swap_result: SwapResult2,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn quote_exact_in_with_transfer_fee(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    base_mint_data: &[u8],
+    quote_mint_data: &[u8],
+    swap_base_for_quote: bool,
+    current_timestamp: u64,
+    current_slot: u64,
+    current_epoch: u64,
+    amount_in: u64,
+    has_referral: bool,
+    is_wallet_tracked: bool,
+) -> Result<SwapQuoteWithTransferFee> {
+    let (input_mint_data, output_mint_data) = if swap_base_for_quote {
+        (base_mint_data, quote_mint_data)
+    } else {
+        (quote_mint_data, base_mint_data)
+    };
+
+    let vault_amount_in =
+        calculate_transfer_fee_excluded_amount(input_mint_data, amount_in, current_epoch)?;
+
+    let swap_result = quote_exact_in(
+        virtual_pool,
+        config,
+        swap_base_for_quote,
+        current_timestamp,
+        current_slot,
+        vault_amount_in.amount,
+        has_referral,
+        is_wallet_tracked,
+    )?;
+
+    let amount_out = calculate_transfer_fee_excluded_amount(
+        output_mint_data,
+        swap_result.output_amount,
+        current_epoch,
+    )?;
+
+    Ok(SwapQuoteWithTransferFee {
+        amount_in,
+        vault_amount_in: vault_amount_in.amount,
+        amount_out: amount_out.amount,
+        input_transfer_fee: vault_amount_in.transfer_fee,
+        output_transfer_fee: amount_out.transfer_fee,
+        swap_result,
+    })
+}
+
+/// `amount_out` is what user finally receives, after output transfer fee
+#[allow(clippy::too_many_arguments)]
+pub fn quote_exact_out_with_transfer_fee(
+    virtual_pool: &VirtualPool,
+    config: &PoolConfig,
+    base_mint_data: &[u8],
+    quote_mint_data: &[u8],
+    swap_base_for_quote: bool,
+    current_timestamp: u64,
+    current_slot: u64,
+    current_epoch: u64,
+    amount_out: u64,
+    has_referral: bool,
+    is_wallet_tracked: bool,
+) -> Result<SwapQuoteWithTransferFee> {
+    let (input_mint_data, output_mint_data) = if swap_base_for_quote {
+        (base_mint_data, quote_mint_data)
+    } else {
+        (quote_mint_data, base_mint_data)
+    };
+
+    let vault_amount_out =
+        calculate_transfer_fee_included_amount(output_mint_data, amount_out, current_epoch)?;
+
+    let swap_result = quote_exact_out(
+        virtual_pool,
+        config,
+        swap_base_for_quote,
+        current_timestamp,
+        current_slot,
+        vault_amount_out.amount,
+        has_referral,
+        is_wallet_tracked,
+    )?;
+
+    let amount_in = calculate_transfer_fee_included_amount(
+        input_mint_data,
+        swap_result.consumed_input_amount,
+        current_epoch,
+    )?;
+
+    Ok(SwapQuoteWithTransferFee {
+        amount_in: amount_in.amount,
+        vault_amount_in: swap_result.consumed_input_amount,
+        amount_out,
+        input_transfer_fee: amount_in.transfer_fee,
+        output_transfer_fee: vault_amount_out.transfer_fee,
+        swap_result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::{
+        params::liquidity_distribution::LiquidityDistributionParameters, state::CollectFeeMode,
+    };
+
+    use super::*;
+
+    const TIMESTAMP: u64 = 1_700_000_000;
+
+    fn get_config_and_pool() -> (PoolConfig, VirtualPool) {
+        let curve = [
+            LiquidityDistributionParameters {
+                sqrt_price: 2u128 << 64,
+                liquidity: 1_000_000u128 << 64,
+            },
+            LiquidityDistributionParameters {
+                sqrt_price: 4u128 << 64,
+                liquidity: 2_000_000u128 << 64,
+            },
+        ];
+        let mut config = PoolConfig {
+            sqrt_start_price: 1u128 << 64,
+            migration_sqrt_price: 3u128 << 64,
+            // quote raised from the start to the migration sqrt price
+            migration_quote_threshold: 3_000_000,
+            collect_fee_mode: CollectFeeMode::QuoteToken.into(),
+            ..Default::default()
+        };
+        // 1%
+        config.pool_fees.base_fee.cliff_fee_numerator = 10_000_000;
+        for (i, point) in curve.iter().enumerate() {
+            config.curve[i] = point.to_liquidity_distribution_config();
+        }
+        let pool = VirtualPool {
+            sqrt_price: config.sqrt_start_price,
+            base_reserve: 1_000_000,
+            ..Default::default()
+        };
+        (config, pool)
+    }
+
+    #[test]
+    fn test_quote_exact_out_round_trip() {
+        let (config, pool) = get_config_and_pool();
+        for amount_out in [1, 1_000, 100_000, 700_000] {
+            let amount_in =
+                quote_exact_out(&pool, &config, false, TIMESTAMP, 0, amount_out, false, true)
+                    .unwrap()
+                    .consumed_input_amount;
+
+            // the required input buys at least the requested output
+            let swap_result =
+                quote_exact_in(&pool, &config, false, TIMESTAMP, 0, amount_in, false, true)
+                    .unwrap();
+            assert!(swap_result.output_amount >= amount_out);
+
+            // and one less unit falls short
+            if amount_in > 1 {
+                let swap_result = quote_exact_in(
+                    &pool,
+                    &config,
+                    false,
+                    TIMESTAMP,
+                    0,
+                    amount_in - 1,
+                    false,
+                    true,
+                )
+                .unwrap();
+                assert!(swap_result.output_amount < amount_out);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quote_exact_out_zero_amount() {
+        let (config, pool) = get_config_and_pool();
+        let err = quote_exact_out(&pool, &config, false, TIMESTAMP, 0, 0, false, true).unwrap_err();
+        assert_eq!(err.to_string(), "amount is zero");
+    }
+
+    #[test]
+    fn test_quote_exact_out_completing_curve() {
+        let (config, pool) = get_config_and_pool();
+        // past the migration sqrt price, about 833k base
+        let err =
+            quote_exact_out(&pool, &config, false, TIMESTAMP, 0, 850_000, false, true).unwrap_err();
+        assert_eq!(err.to_string(), "swap would complete the curve");
+
+        // the required input takes the quote reserve past the swallow allowance
+        assert!(
+            quote_exact_out(&pool, &config, false, TIMESTAMP, 0, 900_000, false, true).is_err()
+        );
+    }
+
+    #[test]
+    fn test_quote_under_per_wallet_limit() {
+        let (mut config, pool) = get_config_and_pool();
+        // slot activation type
+        config.buy_limit_duration = 100;
+        config.max_buy_quote_per_wallet = 1_000_000;
+
+        // only buys through `swap_with_buy_tracker` are allowed in the window
+        assert!(quote_exact_in(&pool, &config, false, TIMESTAMP, 0, 1_000, false, false).is_err());
+        assert!(quote_exact_out(&pool, &config, false, TIMESTAMP, 0, 1_000, false, false).is_err());
+        assert!(quote_exact_in(&pool, &config, false, TIMESTAMP, 0, 1_000, false, true).is_ok());
+
+        // sells aren't limited
+        let mut pool = pool;
+        pool.quote_reserve = 1_000_000;
+        pool.sqrt_price = 2u128 << 64;
+        assert!(quote_exact_in(&pool, &config, true, TIMESTAMP, 0, 1_000, false, false).is_ok());
+
+        // and the window ends
+        assert!(quote_exact_in(&pool, &config, false, TIMESTAMP, 100, 1_000, false, false).is_ok());
+    }
+
+    #[test]
+    fn test_quote_in_allowlist_phase() {
+        let (config, mut pool) = get_config_and_pool();
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, TradeDirection::QuoteToBase, false)
+                .unwrap();
+        let swap_result =
+            quote_exact_in(&pool, &config, false, TIMESTAMP, 0, 1_000_000, false, true).unwrap();
+        pool.apply_swap_result(
+            &config,
+            &swap_result,
+            &fee_mode,
+            TradeDirection::QuoteToBase,
+            TIMESTAMP,
+        )
+        .unwrap();
+        // slot activation type
+        let allowlist_end_slot = 100;
+        pool.allowlist_merkle_root = [1; 32];
+        pool.allowlist_end_point = allowlist_end_slot;
+
+        let err =
+            quote_exact_in(&pool, &config, false, TIMESTAMP, 0, 1_000, false, true).unwrap_err();
+        assert_eq!(err.to_string(), "virtual pool is in allowlist phase");
+        let err =
+            quote_exact_out(&pool, &config, false, TIMESTAMP, 0, 1_000, false, true).unwrap_err();
+        assert_eq!(err.to_string(), "virtual pool is in allowlist phase");
+
+        // anyone can sell
+        assert!(quote_exact_in(&pool, &config, true, TIMESTAMP, 0, 1_000, false, true).is_ok());
+        assert!(quote_exact_out(&pool, &config, true, TIMESTAMP, 0, 1_000, false, true).is_ok());
+
+        // buys are allowed once the phase ends
+        assert!(quote_exact_in(
+            &pool,
+            &config,
+            false,
+            TIMESTAMP,
+            allowlist_end_slot,
+            1_000,
+            false,
+            true
+        )
+        .is_ok());
+    }
+}
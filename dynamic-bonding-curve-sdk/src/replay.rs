@@ -0,0 +1,561 @@
+//! Rebuild the state of a virtual pool by replaying its events.
+//!
+//! Every state change done by swaps, fee claims and withdrawals is applied with the same
+//! program functions the instructions use, so a full event history from `EvtInitializePool`
+//! reproduces the pool account. Locker creation and migration don't emit events, the pool
+//! is only known to be migrated once leftover is withdrawn, `mark_migrated` can be used when
+//! the migration transaction is known from elsewhere. `EvtInitializePool` doesn't carry the
+//! allowlist, it is read from the pool initialization instruction and set with `with_allowlist`.
+use anchor_lang::prelude::*;
+use anyhow::{bail, ensure, Context, Result};
+use dynamic_bonding_curve::{
+    params::{allowlist::AllowlistParameters, swap::TradeDirection},
+    process_curve_complete,
+    state::{
+        fee::FeeMode, MigrationProgress, PoolConfig, SwapResult2, VirtualPool, CREATOR_MASK,
+        PARTNER_MASK,
+    },
+    EvtCurveComplete, EvtInitializePool, EvtSwap,
+};
+
+use crate::{events::DbcEvent, pda::derive_token_vault};
+
+/// `EvtSwap` keeps the swap result layout of the first version, the input it consumed is
+/// `amount_in`
+fn get_swap_result_2(event: &EvtSwap) -> SwapResult2 {
+    let swap_result = &event.swap_result;
+    SwapResult2 {
+        actual_input_amount: swap_result.actual_input_amount,
+        output_amount: swap_result.output_amount,
+        next_sqrt_price: swap_result.next_sqrt_price,
+        trading_fee: swap_result.trading_fee,
+        protocol_fee: swap_result.protocol_fee,
+        referral_fee: swap_result.referral_fee,
+        consumed_input_amount: event.amount_in,
+        refunded_input_amount: event.params.amount_in.saturating_sub(event.amount_in),
+    }
+}
+
+/// A field of the replayed pool that differs from the account snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub field: &'static str,
+    pub replayed: String,
+    pub snapshot: String,
+}
+
+pub struct PoolReplay {
+    pub pool: Pubkey,
+    pub config: PoolConfig,
+    allowlist: Option<AllowlistParameters>,
+    virtual_pool: Option<VirtualPool>,
+}
+
+impl PoolReplay {
+    pub fn new(pool: Pubkey, config: PoolConfig) -> Self {
+        Self {
+            pool,
+            config,
+            allowlist: None,
+            virtual_pool: None,
+        }
+    }
+
+    /// Allowlist the pool was initialized with, applied with `EvtInitializePool`
+    pub fn with_allowlist(mut self, allowlist: AllowlistParameters) -> Self {
+        self.allowlist = Some(allowlist);
+        self
+    }
+
+    /// Replayed pool state, `None` until `EvtInitializePool` is applied
+    pub fn virtual_pool(&self) -> Option<&VirtualPool> {
+        self.virtual_pool.as_ref()
+    }
+
+    /// Apply all events in order, events of other pools are skipped
+    pub fn apply_all<'a>(&mut self, events: impl IntoIterator<Item = &'a DbcEvent>) -> Result<()> {
+        for event in events {
+            self.apply(event)
+                .with_context(|| format!("failed to replay {}", event.name()))?;
+        }
+        Ok(())
+    }
+
+    /// Apply a single event, returns whether the event changed the pool
+    pub fn apply(&mut self, event: &DbcEvent) -> Result<bool> {
+        match event {
+            DbcEvent::InitializePool(event) if event.pool == self.pool => {
+                self.initialize(event)?;
+            }
+            DbcEvent::Swap(event) if event.pool == self.pool => {
+                self.swap(
+                    event.trade_direction,
+                    event.has_referral,
+                    &get_swap_result_2(event),
+                    event.current_timestamp,
+                )?;
+            }
+            DbcEvent::SwapExactOut(event) if event.pool == self.pool => {
+                self.swap(
+                    event.trade_direction,
+                    event.has_referral,
+                    &event.swap_result,
+                    event.current_timestamp,
+                )?;
+            }
+            DbcEvent::SwapWithPriceLimit(event) if event.pool == self.pool => {
+                self.swap(
+                    event.trade_direction,
+                    event.has_referral,
+                    &event.swap_result,
+                    event.current_timestamp,
+                )?;
+            }
+            DbcEvent::CurveComplete(event) if event.pool == self.pool => {
+                self.check_curve_complete(event)?;
+            }
+            DbcEvent::ClaimProtocolFee(event) if event.pool == self.pool => {
+                let pool = self.get_pool_mut()?;
+                let (base_amount, quote_amount) = pool.claim_protocol_fee();
+                ensure!(
+                    base_amount == event.token_base_amount
+                        && quote_amount == event.token_quote_amount,
+                    "claimed protocol fee doesn't match replayed fee"
+                );
+            }
+            DbcEvent::ClaimTradingFee(event) if event.pool == self.pool => {
+                let pool = self.get_pool_mut()?;
+                let claimed = pool
+                    .claim_partner_trading_fee(event.token_base_amount, event.token_quote_amount)?;
+                ensure!(
+                    claimed == (event.token_base_amount, event.token_quote_amount),
+                    "claimed partner fee exceeds replayed fee"
+                );
+            }
+            DbcEvent::ClaimCreatorTradingFee(event) if event.pool == self.pool => {
+                let pool = self.get_pool_mut()?;
+                let claimed = pool
+                    .claim_creator_trading_fee(event.token_base_amount, event.token_quote_amount)?;
+                ensure!(
+                    claimed == (event.token_base_amount, event.token_quote_amount),
+                    "claimed creator fee exceeds replayed fee"
+                );
+            }
+            DbcEvent::ProtocolWithdrawSurplus(event) if event.pool == self.pool => {
+                self.get_completed_pool_mut()?
+                    .update_protocol_withdraw_surplus();
+            }
+            DbcEvent::PartnerWithdrawSurplus(event) if event.pool == self.pool => {
+                self.get_completed_pool_mut()?
+                    .update_partner_withdraw_surplus();
+            }
+            DbcEvent::CreatorWithdrawSurplus(event) if event.pool == self.pool => {
+                self.get_completed_pool_mut()?
+                    .update_creator_withdraw_surplus();
+            }
+            DbcEvent::WithdrawLeftover(event) if event.pool == self.pool => {
+                // leftover can only be withdrawn from migrated pools
+                self.mark_migrated()?;
+                self.get_pool_mut()?.update_withdraw_leftover();
+            }
+            DbcEvent::WithdrawMigrationFee(event) if event.pool == self.pool => {
+                // 0 as partner and 1 as creator
+                let mask = match event.flag {
+                    0 => PARTNER_MASK,
+                    1 => CREATOR_MASK,
+                    _ => bail!("invalid migration fee flag {}", event.flag),
+                };
+                let pool = self.get_completed_pool_mut()?;
+                ensure!(
+                    pool.eligible_to_withdraw_migration_fee(mask),
+                    "migration fee has been withdrawn"
+                );
+                pool.update_withdraw_migration_fee(mask);
+            }
+            DbcEvent::UpdatePoolCreator(event) if event.pool == self.pool => {
+                let pool = self.get_pool_mut()?;
+                ensure!(pool.creator == event.creator, "pool creator mismatch");
+                pool.creator = event.new_creator;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Mark the pool as migrated, migration instructions don't emit events
+    pub fn mark_migrated(&mut self) -> Result<()> {
+        let pool = self.get_completed_pool_mut()?;
+        pool.update_after_create_pool();
+        pool.set_migration_progress(MigrationProgress::CreatedPool.into());
+        Ok(())
+    }
+
+    /// Compare the replayed pool with an account snapshot
+    pub fn diff(&self, snapshot: &VirtualPool) -> Result<Vec<Divergence>> {
+        let pool = self.get_pool()?;
+        let mut divergences = vec![];
+        macro_rules! compare {
+            ($($field:ident).+) => {
+                let (replayed, snapshot) = (pool.$($field).+, snapshot.$($field).+);
+                if replayed != snapshot {
+                    divergences.push(Divergence {
+                        field: stringify!($($field).+),
+                        replayed: replayed.to_string(),
+                        snapshot: snapshot.to_string(),
+                    });
+                }
+            };
+        }
+        compare!(config);
+        compare!(creator);
+        compare!(base_mint);
+        compare!(base_vault);
+        compare!(quote_vault);
+        compare!(base_reserve);
+        compare!(quote_reserve);
+        compare!(protocol_base_fee);
+        compare!(protocol_quote_fee);
+        compare!(partner_base_fee);
+        compare!(partner_quote_fee);
+        compare!(creator_base_fee);
+        compare!(creator_quote_fee);
+        compare!(sqrt_price);
+        compare!(activation_point);
+        compare!(allowlist_end_point);
+        compare!(allowlist_quote_cap_per_wallet);
+        compare!(pool_type);
+        compare!(is_migrated);
+        compare!(is_partner_withdraw_surplus);
+        compare!(is_protocol_withdraw_surplus);
+        compare!(is_creator_withdraw_surplus);
+        compare!(migration_progress);
+        compare!(is_withdraw_leftover);
+        compare!(migration_fee_withdraw_status);
+        compare!(finish_curve_timestamp);
+        compare!(metrics.total_protocol_base_fee);
+        compare!(metrics.total_protocol_quote_fee);
+        compare!(metrics.total_trading_base_fee);
+        compare!(metrics.total_trading_quote_fee);
+        compare!(volatility_tracker.last_update_timestamp);
+        compare!(volatility_tracker.sqrt_price_reference);
+        compare!(volatility_tracker.volatility_accumulator);
+        compare!(volatility_tracker.volatility_reference);
+        if pool.allowlist_merkle_root != snapshot.allowlist_merkle_root {
+            divergences.push(Divergence {
+                field: "allowlist_merkle_root",
+                replayed: format!("{:?}", pool.allowlist_merkle_root),
+                snapshot: format!("{:?}", snapshot.allowlist_merkle_root),
+            });
+        }
+        Ok(divergences)
+    }
+
+    fn get_pool(&self) -> Result<&VirtualPool> {
+        self.virtual_pool
+            .as_ref()
+            .context("pool is not initialized")
+    }
+
+    fn get_pool_mut(&mut self) -> Result<&mut VirtualPool> {
+        self.virtual_pool
+            .as_mut()
+            .context("pool is not initialized")
+    }
+
+    fn get_completed_pool_mut(&mut self) -> Result<&mut VirtualPool> {
+        let migration_quote_threshold = self.config.migration_quote_threshold;
+        let pool = self.get_pool_mut()?;
+        ensure!(
+            pool.is_curve_complete(migration_quote_threshold),
+            "pool is not completed"
+        );
+        Ok(pool)
+    }
+
+    fn initialize(&mut self, event: &EvtInitializePool) -> Result<()> {
+        ensure!(self.virtual_pool.is_none(), "pool is already initialized");
+        let mut pool = VirtualPool::default();
+        pool.initialize(
+            Default::default(),
+            event.config,
+            event.creator,
+            event.base_mint,
+            derive_token_vault(&event.base_mint, &self.pool),
+            derive_token_vault(&self.config.quote_mint, &self.pool),
+            self.config.sqrt_start_price,
+            event.pool_type,
+            event.activation_point,
+            self.config.get_initial_base_supply()?,
+        );
+        if let Some(allowlist) = &self.allowlist {
+            pool.initialize_allowlist(allowlist);
+        }
+        self.virtual_pool = Some(pool);
+        Ok(())
+    }
+
+    fn swap(
+        &mut self,
+        trade_direction: u8,
+        has_referral: bool,
+        swap_result: &SwapResult2,
+        current_timestamp: u64,
+    ) -> Result<()> {
+        let trade_direction =
+            TradeDirection::try_from(trade_direction).context("invalid trade direction")?;
+        let config = &self.config;
+        let pool = self
+            .virtual_pool
+            .as_mut()
+            .context("pool is not initialized")?;
+        ensure!(
+            !pool.is_curve_complete(config.migration_quote_threshold),
+            "virtual pool is completed"
+        );
+
+        pool.update_pre_swap(config, current_timestamp)?;
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral)?;
+        pool.apply_swap_result(
+            config,
+            swap_result,
+            &fee_mode,
+            trade_direction,
+            current_timestamp,
+        )?;
+
+        if pool.is_curve_complete(config.migration_quote_threshold) {
+            // the whole supply is minted to the base vault, only the reserve and unclaimed
+            // base fees are left in it before migration
+            let base_vault_balance = pool
+                .base_reserve
+                .checked_add(pool.get_protocol_and_trading_base_fee()?)
+                .context("base vault balance overflow")?;
+            process_curve_complete(pool, config, base_vault_balance, current_timestamp)?;
+        }
+        Ok(())
+    }
+
+    fn check_curve_complete(&self, event: &EvtCurveComplete) -> Result<()> {
+        let pool = self.get_pool()?;
+        ensure!(
+            pool.is_curve_complete(self.config.migration_quote_threshold),
+            "curve is not completed by replayed swaps"
+        );
+        ensure!(
+            pool.base_reserve == event.base_reserve && pool.quote_reserve == event.quote_reserve,
+            "reserves at curve completion don't match replayed reserves"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::{
+        constants::MAX_SQRT_PRICE, params::liquidity_distribution::LiquidityDistributionParameters,
+        state::CollectFeeMode, EvtClaimTradingFee, EvtSwap, SwapParameters,
+    };
+
+    use super::*;
+
+    fn get_config() -> PoolConfig {
+        let mut config = PoolConfig {
+            quote_mint: Pubkey::new_unique(),
+            migration_quote_threshold: 1_000_000_000,
+            sqrt_start_price: 2916686334356757,
+            collect_fee_mode: CollectFeeMode::QuoteToken.into(),
+            fixed_token_supply_flag: 1,
+            ..Default::default()
+        };
+        config.pool_fees.base_fee.cliff_fee_numerator = 10_000_000;
+        config.pool_fees.protocol_fee_percent = 20;
+        config.creator_trading_fee_percentage = 50;
+        config.pre_migration_token_supply = u64::MAX / 2;
+        config.curve[0] = LiquidityDistributionParameters {
+            sqrt_price: 11666745337427032,
+            liquidity: 3111132089980541388292920297291756,
+        }
+        .to_liquidity_distribution_config();
+        config.curve[1] = LiquidityDistributionParameters {
+            sqrt_price: MAX_SQRT_PRICE,
+            liquidity: 1,
+        }
+        .to_liquidity_distribution_config();
+        config
+    }
+
+    fn get_swap_event(
+        pool: &Pubkey,
+        virtual_pool: &mut VirtualPool,
+        config: &PoolConfig,
+        amount_in: u64,
+        current_timestamp: u64,
+    ) -> DbcEvent {
+        let trade_direction = TradeDirection::QuoteToBase;
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+        let swap_result = virtual_pool
+            .get_swap_result(config, amount_in, &fee_mode, trade_direction, 0)
+            .unwrap();
+        virtual_pool
+            .apply_swap_result(
+                config,
+                &swap_result,
+                &fee_mode,
+                trade_direction,
+                current_timestamp,
+            )
+            .unwrap();
+        DbcEvent::Swap(EvtSwap {
+            pool: *pool,
+            config: virtual_pool.config,
+            trade_direction: trade_direction.into(),
+            has_referral: false,
+            params: SwapParameters {
+                amount_in,
+                minimum_amount_out: 0,
+            },
+            amount_in: swap_result.consumed_input_amount,
+            swap_result: swap_result.into(),
+            current_timestamp,
+        })
+    }
+
+    #[test]
+    fn test_replay_swaps_and_claims() {
+        let pool = Pubkey::new_unique();
+        let config = get_config();
+        let mut replay = PoolReplay::new(pool, config);
+
+        let initialize = DbcEvent::InitializePool(EvtInitializePool {
+            pool,
+            config: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            pool_type: 0,
+            activation_point: 100,
+        });
+        assert!(replay.apply(&initialize).unwrap());
+        let mut expected = *replay.virtual_pool().unwrap();
+
+        let mut events = vec![
+            get_swap_event(&pool, &mut expected, &config, 100_000_000, 1),
+            get_swap_event(&pool, &mut expected, &config, 200_000_000, 2),
+        ];
+        let (base_amount, quote_amount) = expected.claim_partner_trading_fee(0, 1_000).unwrap();
+        events.push(DbcEvent::ClaimTradingFee(EvtClaimTradingFee {
+            pool,
+            token_base_amount: base_amount,
+            token_quote_amount: quote_amount,
+        }));
+        // event of another pool
+        let mut other_pool = expected;
+        events.push(get_swap_event(
+            &Pubkey::new_unique(),
+            &mut other_pool,
+            &config,
+            100_000_000,
+            3,
+        ));
+
+        replay.apply_all(&events).unwrap();
+        assert!(replay.diff(&expected).unwrap().is_empty());
+        assert!(expected.partner_quote_fee > 0);
+
+        let mut snapshot = expected;
+        snapshot.quote_reserve += 1;
+        let divergences = replay.diff(&snapshot).unwrap();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].field, "quote_reserve");
+
+        // claiming more than accumulated is inconsistent
+        let claim = DbcEvent::ClaimTradingFee(EvtClaimTradingFee {
+            pool,
+            token_base_amount: 0,
+            token_quote_amount: u64::MAX,
+        });
+        assert!(replay.apply(&claim).is_err());
+    }
+
+    #[test]
+    fn test_replay_allowlist() {
+        let pool = Pubkey::new_unique();
+        let allowlist = AllowlistParameters {
+            merkle_root: [1; 32],
+            end_point: 200,
+            quote_cap_per_wallet: 1_000_000,
+        };
+        let mut replay = PoolReplay::new(pool, get_config()).with_allowlist(allowlist);
+        replay
+            .apply(&DbcEvent::InitializePool(EvtInitializePool {
+                pool,
+                config: Pubkey::new_unique(),
+                creator: Pubkey::new_unique(),
+                base_mint: Pubkey::new_unique(),
+                pool_type: 0,
+                activation_point: 100,
+            }))
+            .unwrap();
+        let mut snapshot = *replay.virtual_pool().unwrap();
+        assert!(snapshot.is_allowlist_phase(100));
+        assert!(replay.diff(&snapshot).unwrap().is_empty());
+
+        snapshot.allowlist_merkle_root = [2; 32];
+        snapshot.allowlist_end_point = 300;
+        snapshot.allowlist_quote_cap_per_wallet = 0;
+        let fields: Vec<_> = replay
+            .diff(&snapshot)
+            .unwrap()
+            .into_iter()
+            .map(|divergence| divergence.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "allowlist_end_point",
+                "allowlist_quote_cap_per_wallet",
+                "allowlist_merkle_root"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay_curve_complete() {
+        let pool = Pubkey::new_unique();
+        let config = get_config();
+        let mut replay = PoolReplay::new(pool, config);
+        replay
+            .apply(&DbcEvent::InitializePool(EvtInitializePool {
+                pool,
+                config: Pubkey::new_unique(),
+                creator: Pubkey::new_unique(),
+                base_mint: Pubkey::new_unique(),
+                pool_type: 0,
+                activation_point: 0,
+            }))
+            .unwrap();
+        let mut expected = *replay.virtual_pool().unwrap();
+
+        let swap = get_swap_event(&pool, &mut expected, &config, 2_000_000_000, 10);
+        replay.apply(&swap).unwrap();
+        let virtual_pool = replay.virtual_pool().unwrap();
+        assert!(virtual_pool.is_curve_complete(config.migration_quote_threshold));
+        assert_eq!(virtual_pool.finish_curve_timestamp, 10);
+        assert_eq!(
+            virtual_pool.get_migration_progress().unwrap(),
+            MigrationProgress::LockedVesting
+        );
+
+        let curve_complete = DbcEvent::CurveComplete(EvtCurveComplete {
+            pool,
+            config: virtual_pool.config,
+            base_reserve: virtual_pool.base_reserve,
+            quote_reserve: virtual_pool.quote_reserve,
+        });
+        replay.apply(&curve_complete).unwrap();
+
+        // no swap after curve is completed
+        assert!(replay.apply(&swap).is_err());
+    }
+}
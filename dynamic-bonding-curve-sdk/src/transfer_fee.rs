@@ -0,0 +1,152 @@
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+use anyhow::{Context, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeIncludedAmount {
+    pub amount: u64,
+    pub transfer_fee: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeExcludedAmount {
+    pub amount: u64,
+    pub transfer_fee: u64,
+}
+
+/// Returns the transfer fee of the mint for the epoch, or `None` if the mint has no transfer fee extension.
+/// Works for both spl token and token 2022 mint account data.
+pub fn get_epoch_transfer_fee(mint_account_data: &[u8], epoch: u64) -> Result<Option<TransferFee>> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_account_data)
+        .context("invalid mint account data")?;
+    let transfer_fee = mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|transfer_fee_config| *transfer_fee_config.get_epoch_fee(epoch));
+    Ok(transfer_fee)
+}
+
+/// Amount received by destination when `transfer_fee_included_amount` is sent
+pub fn calculate_transfer_fee_excluded_amount(
+    mint_account_data: &[u8],
+    transfer_fee_included_amount: u64,
+    epoch: u64,
+) -> Result<TransferFeeExcludedAmount> {
+    if let Some(transfer_fee) = get_epoch_transfer_fee(mint_account_data, epoch)? {
+        let transfer_fee = transfer_fee
+            .calculate_fee(transfer_fee_included_amount)
+            .context("transfer fee overflow")?;
+        let transfer_fee_excluded_amount = transfer_fee_included_amount
+            .checked_sub(transfer_fee)
+            .context("transfer fee overflow")?;
+        return Ok(TransferFeeExcludedAmount {
+            amount: transfer_fee_excluded_amount,
+            transfer_fee,
+        });
+    }
+
+    Ok(TransferFeeExcludedAmount {
+        amount: transfer_fee_included_amount,
+        transfer_fee: 0,
+    })
+}
+
+/// Amount must be sent so destination receives `transfer_fee_excluded_amount`
+pub fn calculate_transfer_fee_included_amount(
+    mint_account_data: &[u8],
+    transfer_fee_excluded_amount: u64,
+    epoch: u64,
+) -> Result<TransferFeeIncludedAmount> {
+    if transfer_fee_excluded_amount == 0 {
+        return Ok(TransferFeeIncludedAmount {
+            amount: 0,
+            transfer_fee: 0,
+        });
+    }
+
+    if let Some(transfer_fee) = get_epoch_transfer_fee(mint_account_data, epoch)? {
+        let transfer_fee_included_amount = transfer_fee
+            .calculate_pre_fee_amount(transfer_fee_excluded_amount)
+            .context("transfer fee overflow")?;
+        return Ok(TransferFeeIncludedAmount {
+            amount: transfer_fee_included_amount,
+            transfer_fee: transfer_fee_included_amount - transfer_fee_excluded_amount,
+        });
+    }
+
+    Ok(TransferFeeIncludedAmount {
+        amount: transfer_fee_excluded_amount,
+        transfer_fee: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_spl::token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut},
+        solana_program::program_pack::Pack,
+    };
+
+    use super::*;
+
+    fn get_token_2022_mint_data(transfer_fee_basis_points: u16, maximum_fee: u64) -> Vec<u8> {
+        let len =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig])
+                .unwrap();
+        let mut data = vec![0u8; len];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: transfer_fee_basis_points.into(),
+        };
+        let transfer_fee_config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+        transfer_fee_config.older_transfer_fee = transfer_fee;
+        transfer_fee_config.newer_transfer_fee = transfer_fee;
+        mint.base.decimals = 6;
+        mint.base.is_initialized = true;
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_transfer_fee_on_spl_token_mint() {
+        let mut data = vec![0u8; Mint::LEN];
+        Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+
+        assert!(get_epoch_transfer_fee(&data, 0).unwrap().is_none());
+        let excluded = calculate_transfer_fee_excluded_amount(&data, 1_000_000, 0).unwrap();
+        assert_eq!(excluded.amount, 1_000_000);
+        assert_eq!(excluded.transfer_fee, 0);
+    }
+
+    #[test]
+    fn test_transfer_fee_on_token_2022_mint() {
+        let data = get_token_2022_mint_data(100, 1_000_000); // 1%
+
+        let excluded = calculate_transfer_fee_excluded_amount(&data, 1_000_000, 0).unwrap();
+        assert_eq!(excluded.amount, 990_000);
+        assert_eq!(excluded.transfer_fee, 10_000);
+
+        let included = calculate_transfer_fee_included_amount(&data, 990_000, 0).unwrap();
+        assert_eq!(included.amount, 1_000_000);
+        assert_eq!(included.transfer_fee, 10_000);
+
+        // capped at maximum fee
+        let excluded = calculate_transfer_fee_excluded_amount(&data, 1_000_000_000_000, 0).unwrap();
+        assert_eq!(excluded.transfer_fee, 1_000_000);
+        let included = calculate_transfer_fee_included_amount(&data, excluded.amount, 0).unwrap();
+        assert_eq!(included.amount, 1_000_000_000_000);
+    }
+}
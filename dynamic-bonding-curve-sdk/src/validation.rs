@@ -0,0 +1,637 @@
+//! Validate config parameters off chain.
+//!
+//! Mirrors `ConfigParameters::validate` and the checks done by `create_config`, but takes the
+//! quote mint as plain data and collects every violated rule instead of stopping at the first.
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+};
+use anyhow::{Context, Result};
+use dynamic_bonding_curve::{
+    activation_handler::ActivationType,
+    constants::{
+        MAX_ACTIVATION_SLOT_DELAY, MAX_ACTIVATION_TIMESTAMP_DELAY, MAX_BUY_LIMIT_SLOT_DURATION,
+        MAX_BUY_LIMIT_TIMESTAMP_DURATION, MAX_CURVE_POINT, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
+    params::{
+        fee_parameters::PoolFeeParameters,
+        liquidity_distribution::{
+            get_base_token_for_swap, get_migration_base_token, get_migration_threshold_price,
+        },
+    },
+    state::{
+        CollectFeeMode, MigrationFeeOption, MigrationFillMode, MigrationOption, PoolConfig,
+        TokenType, TokenUpdateAuthorityOption,
+    },
+    ConfigParameters, TokenSupplyParams,
+};
+
+/// Quote mint data needed to validate a config
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteMintInfo {
+    pub mint: Pubkey,
+    /// token program owning the mint
+    pub owner: Pubkey,
+    /// extensions of a token 2022 mint
+    pub extensions: Vec<ExtensionType>,
+}
+
+impl QuoteMintInfo {
+    pub fn from_account(mint: Pubkey, owner: Pubkey, data: &[u8]) -> Result<Self> {
+        let extensions = if owner == spl_token_2022::ID {
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(data)
+                .context("invalid token 2022 mint")?
+                .get_extension_types()
+                .context("invalid token 2022 mint extensions")?
+        } else {
+            vec![]
+        };
+        Ok(Self {
+            mint,
+            owner,
+            extensions,
+        })
+    }
+}
+
+/// A rule violated by config parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigViolation {
+    pub rule: &'static str,
+    pub message: String,
+}
+
+fn error_message(err: anchor_lang::error::Error) -> String {
+    match err {
+        anchor_lang::error::Error::AnchorError(err) => err.error_msg,
+        anchor_lang::error::Error::ProgramError(err) => err.program_error.to_string(),
+    }
+}
+
+/// Validate config parameters, returns all violated rules, empty if the config can be created.
+/// `leftover_receiver` is only checked for fixed supply tokens
+pub fn validate_config_parameters(
+    params: &ConfigParameters,
+    quote_mint: &QuoteMintInfo,
+    leftover_receiver: &Pubkey,
+) -> Vec<ConfigViolation> {
+    let mut violations = vec![];
+    let mut violate = |rule: &'static str, message: String| {
+        violations.push(ConfigViolation { rule, message });
+    };
+
+    // quote mint
+    if quote_mint.owner == spl_token_2022::ID {
+        if spl_token_2022::native_mint::check_id(&quote_mint.mint) {
+            violate(
+                "quote_mint",
+                "token 2022 native mint is not supported as quote mint".to_string(),
+            );
+        }
+        let unsupported = quote_mint
+            .extensions
+            .iter()
+            .filter(|e| {
+                **e != ExtensionType::MetadataPointer && **e != ExtensionType::TokenMetadata
+            })
+            .map(|e| format!("{e:?}"))
+            .collect::<Vec<_>>();
+        if !unsupported.is_empty() {
+            violate(
+                "quote_mint",
+                format!(
+                    "quote mint has unsupported extensions: {}",
+                    unsupported.join(", ")
+                ),
+            );
+        }
+    } else if quote_mint.owner != anchor_spl::token::ID {
+        violate(
+            "quote_mint",
+            "quote mint must be owned by the token or token 2022 program".to_string(),
+        );
+    }
+
+    // fees
+    let base_fee = PoolFeeParameters {
+        base_fee: params.pool_fees.base_fee,
+        dynamic_fee: None,
+    };
+    if let Err(err) = base_fee.validate(params.collect_fee_mode, params.activation_type) {
+        violate(
+            "pool_fees.base_fee",
+            format!(
+                "base fee must be between the min and max fee and the fee scheduler must have non zero parameters, a rate limiter needs quote token fees and a bounded duration: {}",
+                error_message(err)
+            ),
+        );
+    }
+    if let Some(dynamic_fee) = params.pool_fees.dynamic_fee {
+        if let Err(err) = dynamic_fee.validate() {
+            violate(
+                "pool_fees.dynamic_fee",
+                format!(
+                    "dynamic fee must use the default bin step, filter period smaller than decay period and bounded factors: {}",
+                    error_message(err)
+                ),
+            );
+        }
+    }
+    if params.creator_trading_fee_percentage > 100 {
+        violate(
+            "creator_trading_fee_percentage",
+            format!(
+                "creator trading fee percentage is {}, must be at most 100",
+                params.creator_trading_fee_percentage
+            ),
+        );
+    }
+    if params.migration_fee.validate().is_err() {
+        violate(
+            "migration_fee",
+            format!(
+                "migration fee percentage is {} and creator share is {}, fee must be at most 50 and creator share at most 100, or 0 without fee",
+                params.migration_fee.fee_percentage, params.migration_fee.creator_fee_percentage
+            ),
+        );
+    }
+
+    // enums
+    if CollectFeeMode::try_from(params.collect_fee_mode).is_err() {
+        violate(
+            "collect_fee_mode",
+            format!("invalid collect fee mode {}", params.collect_fee_mode),
+        );
+    }
+    let migration_option = MigrationOption::try_from(params.migration_option).ok();
+    if migration_option.is_none() {
+        violate(
+            "migration_option",
+            format!("invalid migration option {}", params.migration_option),
+        );
+    }
+    let token_type = TokenType::try_from(params.token_type).ok();
+    if token_type.is_none() {
+        violate(
+            "token_type",
+            format!("invalid token type {}", params.token_type),
+        );
+    }
+    if migration_option == Some(MigrationOption::MeteoraDamm) {
+        if token_type == Some(TokenType::Token2022) {
+            violate(
+                "token_type",
+                "meteora damm migration only supports spl token base mint".to_string(),
+            );
+        }
+        if quote_mint.owner != anchor_spl::token::ID {
+            violate(
+                "quote_mint",
+                "meteora damm migration only supports spl token quote mint".to_string(),
+            );
+        }
+    }
+    match ActivationType::try_from(params.activation_type) {
+        Ok(activation_type) => {
+            let max_activation_delay = match activation_type {
+                ActivationType::Slot => MAX_ACTIVATION_SLOT_DELAY,
+                ActivationType::Timestamp => MAX_ACTIVATION_TIMESTAMP_DELAY,
+            };
+            if params.max_activation_delay > max_activation_delay {
+                violate(
+                    "max_activation_delay",
+                    format!(
+                        "max activation delay {} is above {}",
+                        params.max_activation_delay, max_activation_delay
+                    ),
+                );
+            }
+            let max_buy_limit_duration = match activation_type {
+                ActivationType::Slot => MAX_BUY_LIMIT_SLOT_DURATION,
+                ActivationType::Timestamp => MAX_BUY_LIMIT_TIMESTAMP_DURATION,
+            };
+            if params.buy_limit_duration > max_buy_limit_duration {
+                violate(
+                    "buy_limit_duration",
+                    format!(
+                        "buy limit duration {} is above {}",
+                        params.buy_limit_duration, max_buy_limit_duration
+                    ),
+                );
+            }
+        }
+        Err(_) => violate(
+            "activation_type",
+            format!("invalid activation type {}", params.activation_type),
+        ),
+    }
+    let has_buy_limit = params.max_buy_quote_per_swap > 0 || params.max_buy_quote_per_wallet > 0;
+    if params.buy_limit_duration == 0 && has_buy_limit {
+        violate(
+            "buy_limit_duration",
+            "buy limits need a non zero duration".to_string(),
+        );
+    }
+    if params.buy_limit_duration > 0 && !has_buy_limit {
+        violate(
+            "max_buy_quote_per_swap",
+            "buy limit duration needs a per swap or per wallet limit".to_string(),
+        );
+    }
+    if params.max_buy_quote_per_wallet > 0
+        && params.max_buy_quote_per_swap > params.max_buy_quote_per_wallet
+    {
+        violate(
+            "max_buy_quote_per_swap",
+            format!(
+                "max buy quote per swap {} is above the per wallet limit {}",
+                params.max_buy_quote_per_swap, params.max_buy_quote_per_wallet
+            ),
+        );
+    }
+    if TokenUpdateAuthorityOption::try_from(params.token_update_authority).is_err() {
+        violate(
+            "token_update_authority",
+            format!(
+                "invalid token update authority option {}",
+                params.token_update_authority
+            ),
+        );
+    }
+    if MigrationFillMode::try_from(params.migration_fill_mode).is_err() {
+        violate(
+            "migration_fill_mode",
+            format!("invalid migration fill mode {}", params.migration_fill_mode),
+        );
+    }
+    if MigrationFeeOption::try_from(params.migration_fee_option).is_err() {
+        violate(
+            "migration_fee_option",
+            format!(
+                "invalid migration fee option {}",
+                params.migration_fee_option
+            ),
+        );
+    }
+
+    if !(6..=9).contains(&params.token_decimal) {
+        violate(
+            "token_decimal",
+            format!(
+                "token decimal is {}, must be between 6 and 9",
+                params.token_decimal
+            ),
+        );
+    }
+
+    let sum_lp_percentage = u16::from(params.partner_lp_percentage)
+        + u16::from(params.partner_locked_lp_percentage)
+        + u16::from(params.creator_lp_percentage)
+        + u16::from(params.creator_locked_lp_percentage);
+    if sum_lp_percentage != 100 {
+        violate(
+            "lp_percentage",
+            format!("partner and creator lp percentages sum to {sum_lp_percentage}, must be 100"),
+        );
+    }
+
+    if params.migration_quote_threshold == 0 {
+        violate(
+            "migration_quote_threshold",
+            "migration quote threshold must be greater than 0".to_string(),
+        );
+    }
+
+    if params.locked_vesting.validate().is_err() {
+        violate(
+            "locked_vesting",
+            "locked vesting must have a non zero frequency and total amount".to_string(),
+        );
+    }
+
+    // curve
+    let mut is_curve_valid = true;
+    let mut violate_curve = |message: String| {
+        is_curve_valid = false;
+        violate("curve", message);
+    };
+    if params.sqrt_start_price < MIN_SQRT_PRICE || params.sqrt_start_price >= MAX_SQRT_PRICE {
+        violate_curve(format!(
+            "sqrt start price {} is out of range [{MIN_SQRT_PRICE}, {MAX_SQRT_PRICE})",
+            params.sqrt_start_price
+        ));
+    }
+    let curve = &params.curve;
+    if curve.is_empty() || curve.len() > MAX_CURVE_POINT {
+        violate_curve(format!(
+            "curve has {} points, must have between 1 and {MAX_CURVE_POINT}",
+            curve.len()
+        ));
+    }
+    for (i, point) in curve.iter().enumerate() {
+        let lower_sqrt_price = if i == 0 {
+            params.sqrt_start_price
+        } else {
+            curve[i - 1].sqrt_price
+        };
+        if point.sqrt_price <= lower_sqrt_price {
+            violate_curve(format!(
+                "curve point {i} sqrt price {} must be greater than the previous sqrt price {lower_sqrt_price}",
+                point.sqrt_price
+            ));
+        }
+        if point.liquidity == 0 {
+            violate_curve(format!("curve point {i} has zero liquidity"));
+        }
+        if point.sqrt_price > MAX_SQRT_PRICE {
+            violate_curve(format!(
+                "curve point {i} sqrt price {} exceeds max sqrt price",
+                point.sqrt_price
+            ));
+        }
+    }
+
+    // amounts computed by create config, only meaningful on a valid curve
+    if is_curve_valid && params.migration_quote_threshold > 0 {
+        match get_curve_amounts(params, migration_option) {
+            Ok((swap_base_amount, migration_base_amount)) => {
+                if swap_base_amount == 0 || migration_base_amount == 0 {
+                    violate(
+                        "curve",
+                        "swap and migration base amounts must be greater than 0".to_string(),
+                    );
+                }
+                if let Some(token_supply) = params.token_supply {
+                    if *leftover_receiver == Pubkey::default() {
+                        violate(
+                            "leftover_receiver",
+                            "leftover receiver is required for fixed supply tokens".to_string(),
+                        );
+                    }
+                    if let Err(message) = validate_token_supply(
+                        params,
+                        &token_supply,
+                        swap_base_amount,
+                        migration_base_amount,
+                    ) {
+                        violate("token_supply", message);
+                    }
+                }
+            }
+            Err(message) => violate("curve", message),
+        }
+    }
+
+    violations
+}
+
+/// Swap and migration base amounts, same as `create_config`
+fn get_curve_amounts(
+    params: &ConfigParameters,
+    migration_option: Option<MigrationOption>,
+) -> std::result::Result<(u64, u64), String> {
+    let sqrt_migration_price = get_migration_threshold_price(
+        params.migration_quote_threshold,
+        params.sqrt_start_price,
+        &params.curve,
+    )
+    .map_err(|err| {
+        format!(
+            "curve can't absorb the migration quote threshold: {}",
+            error_message(err)
+        )
+    })?;
+    if sqrt_migration_price >= MAX_SQRT_PRICE {
+        return Err("migration sqrt price must be smaller than max sqrt price".to_string());
+    }
+    let swap_base_amount =
+        get_base_token_for_swap(params.sqrt_start_price, sqrt_migration_price, &params.curve)
+            .ok()
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or("swap base amount overflows")?;
+    let Some(migration_option) = migration_option else {
+        return Err("migration base amount depends on the migration option".to_string());
+    };
+    let migration_base_amount = get_migration_base_token(
+        params.migration_quote_threshold,
+        params.migration_fee.fee_percentage,
+        sqrt_migration_price,
+        migration_option,
+    )
+    .map_err(|err| format!("invalid migration base amount: {}", error_message(err)))?;
+    Ok((swap_base_amount, migration_base_amount))
+}
+
+fn validate_token_supply(
+    params: &ConfigParameters,
+    token_supply: &TokenSupplyParams,
+    swap_base_amount: u64,
+    migration_base_amount: u64,
+) -> std::result::Result<(), String> {
+    let to_message = |err| format!("invalid token supply: {}", error_message(err));
+    let swap_base_amount_buffer = PoolConfig::get_swap_amount_with_buffer(
+        swap_base_amount,
+        params.sqrt_start_price,
+        &params.curve,
+    )
+    .map_err(to_message)?;
+    let minimum_base_supply_with_buffer = PoolConfig::get_total_token_supply(
+        swap_base_amount_buffer,
+        migration_base_amount,
+        &params.locked_vesting,
+    )
+    .map_err(to_message)?;
+    let minimum_base_supply_without_buffer = PoolConfig::get_total_token_supply(
+        swap_base_amount,
+        migration_base_amount,
+        &params.locked_vesting,
+    )
+    .map_err(to_message)?;
+    let TokenSupplyParams {
+        pre_migration_token_supply,
+        post_migration_token_supply,
+    } = *token_supply;
+    if post_migration_token_supply < minimum_base_supply_without_buffer {
+        return Err(format!(
+            "post migration supply {post_migration_token_supply} is less than the minimum supply {minimum_base_supply_without_buffer}"
+        ));
+    }
+    if post_migration_token_supply > pre_migration_token_supply {
+        return Err(format!(
+            "post migration supply {post_migration_token_supply} is greater than pre migration supply {pre_migration_token_supply}"
+        ));
+    }
+    if pre_migration_token_supply < minimum_base_supply_with_buffer {
+        return Err(format!(
+            "pre migration supply {pre_migration_token_supply} is less than the minimum supply with buffer {minimum_base_supply_with_buffer}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use dynamic_bonding_curve::{
+        params::fee_parameters::BaseFeeParameters, LockedVestingParams, MigrationFee,
+    };
+
+    use super::*;
+    use crate::design::{design_curve, CurveDesignParams};
+
+    fn get_spl_quote_mint() -> QuoteMintInfo {
+        QuoteMintInfo {
+            mint: Pubkey::new_unique(),
+            owner: anchor_spl::token::ID,
+            extensions: vec![],
+        }
+    }
+
+    fn get_config_parameters() -> ConfigParameters {
+        let params = CurveDesignParams {
+            total_token_supply: 1_000_000_000,
+            percentage_supply_on_curve: 70,
+            initial_market_cap: 30.0,
+            migration_market_cap: 300.0,
+            token_base_decimal: 6,
+            token_quote_decimal: 9,
+            migration_option: MigrationOption::DammV2,
+            migration_fee_percentage: 0,
+            locked_vesting: LockedVestingParams::default(),
+        };
+        let mut config_parameters = ConfigParameters {
+            pool_fees: PoolFeeParameters {
+                base_fee: BaseFeeParameters {
+                    cliff_fee_numerator: 2_500_000,
+                    ..Default::default()
+                },
+                dynamic_fee: None,
+            },
+            collect_fee_mode: 0,
+            migration_option: 0,
+            activation_type: 0,
+            token_type: 0,
+            token_decimal: 0,
+            partner_lp_percentage: 0,
+            partner_locked_lp_percentage: 100,
+            creator_lp_percentage: 0,
+            creator_locked_lp_percentage: 0,
+            migration_quote_threshold: 0,
+            sqrt_start_price: 0,
+            locked_vesting: LockedVestingParams::default(),
+            migration_fee_option: 0,
+            token_supply: None,
+            creator_trading_fee_percentage: 0,
+            token_update_authority: 0,
+            migration_fee: MigrationFee::default(),
+            migration_fill_mode: 0,
+            max_activation_delay: 0,
+            buy_limit_duration: 0,
+            max_buy_quote_per_swap: 0,
+            max_buy_quote_per_wallet: 0,
+            padding_0: [0; 3],
+            padding_1: [0; 3],
+            curve: vec![],
+        };
+        design_curve(&params)
+            .unwrap()
+            .apply_to(&params, &mut config_parameters);
+        config_parameters
+    }
+
+    #[test]
+    fn test_validate_valid_config() {
+        let params = get_config_parameters();
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::new_unique());
+        assert_eq!(violations, vec![]);
+    }
+
+    #[test]
+    fn test_validate_returns_all_violations() {
+        let mut params = get_config_parameters();
+        params.token_decimal = 5;
+        params.partner_locked_lp_percentage = 90;
+        params.creator_trading_fee_percentage = 101;
+        params.curve[1].liquidity = 0;
+        let quote_mint = QuoteMintInfo {
+            mint: Pubkey::new_unique(),
+            owner: spl_token_2022::ID,
+            extensions: vec![
+                ExtensionType::TransferFeeConfig,
+                ExtensionType::MetadataPointer,
+            ],
+        };
+
+        let violations = validate_config_parameters(&params, &quote_mint, &Pubkey::default());
+        let rules = violations.iter().map(|v| v.rule).collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![
+                "quote_mint",
+                "creator_trading_fee_percentage",
+                "token_decimal",
+                "lp_percentage",
+                "curve"
+            ]
+        );
+        assert!(violations[0].message.contains("TransferFeeConfig"));
+    }
+
+    #[test]
+    fn test_validate_max_activation_delay() {
+        let mut params = get_config_parameters();
+        params.activation_type = ActivationType::Timestamp.into();
+        params.max_activation_delay = MAX_ACTIVATION_TIMESTAMP_DELAY;
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::new_unique());
+        assert_eq!(violations, vec![]);
+
+        params.max_activation_delay += 1;
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::new_unique());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "max_activation_delay");
+    }
+
+    #[test]
+    fn test_validate_buy_limit() {
+        let mut params = get_config_parameters();
+        params.activation_type = ActivationType::Timestamp.into();
+        params.buy_limit_duration = MAX_BUY_LIMIT_TIMESTAMP_DURATION;
+        params.max_buy_quote_per_swap = 1_000_000_000;
+        params.max_buy_quote_per_wallet = 5_000_000_000;
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::new_unique());
+        assert_eq!(violations, vec![]);
+
+        params.buy_limit_duration += 1;
+        params.max_buy_quote_per_swap = 10_000_000_000;
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::new_unique());
+        let rules = violations.iter().map(|v| v.rule).collect::<Vec<_>>();
+        assert_eq!(rules, vec!["buy_limit_duration", "max_buy_quote_per_swap"]);
+
+        params.buy_limit_duration = 0;
+        params.max_buy_quote_per_swap = 0;
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::new_unique());
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, "buy_limit_duration");
+    }
+
+    #[test]
+    fn test_validate_token_supply() {
+        let mut params = get_config_parameters();
+        let token_supply = params.token_supply.unwrap();
+        params.token_supply = Some(TokenSupplyParams {
+            pre_migration_token_supply: token_supply.pre_migration_token_supply / 2,
+            post_migration_token_supply: token_supply.post_migration_token_supply / 2,
+        });
+        params.migration_option = MigrationOption::MeteoraDamm.into();
+        let violations =
+            validate_config_parameters(&params, &get_spl_quote_mint(), &Pubkey::default());
+        let rules = violations.iter().map(|v| v.rule).collect::<Vec<_>>();
+        assert_eq!(rules, vec!["leftover_receiver", "token_supply"]);
+    }
+}
@@ -28,6 +28,18 @@ static_assertions::const_assert!(PARTNER_AND_CREATOR_SURPLUS_SHARE <= 100);
 
 pub const MAX_SWALLOW_PERCENTAGE: u8 = 20; // 20 %
 
+/// Max delay partner can allow between pool creation and activation
+pub const MAX_ACTIVATION_SLOT_DELAY: u64 = 1_512_000; // 7 days with 400ms slot
+pub const MAX_ACTIVATION_TIMESTAMP_DELAY: u64 = 604_800; // 7 days
+
+/// Max duration of the allowlist phase, counted from the activation point
+pub const MAX_ALLOWLIST_SLOT_DURATION: u64 = 216_000; // 1 day with 400ms slot
+pub const MAX_ALLOWLIST_TIMESTAMP_DURATION: u64 = 86_400; // 1 day
+
+/// Max duration of the buy limits, counted from the activation point
+pub const MAX_BUY_LIMIT_SLOT_DURATION: u64 = 216_000; // 1 day with 400ms slot
+pub const MAX_BUY_LIMIT_TIMESTAMP_DURATION: u64 = 86_400; // 1 day
+
 /// Store constants related to fees
 pub mod fee {
 
@@ -57,6 +69,10 @@ pub mod fee {
     pub const PROTOCOL_FEE_PERCENT: u8 = 20; // 20%
 
     pub const HOST_FEE_PERCENT: u8 = 20; // 20%
+
+    /// Max window of the rate limiter fee after activation
+    pub const MAX_RATE_LIMITER_SLOT_DURATION: u64 = 108_000; // 12 hours with 400ms slot
+    pub const MAX_RATE_LIMITER_TIMESTAMP_DURATION: u64 = 43_200; // 12 hours
 }
 
 pub mod seeds {
@@ -75,4 +91,6 @@ pub mod seeds {
     pub const PARTNER_METADATA_PREFIX: &[u8] = b"partner_metadata";
     pub const VIRTUAL_POOL_METADATA_PREFIX: &[u8] = b"virtual_pool_metadata";
     pub const BASE_LOCKER_PREFIX: &[u8] = b"base_locker";
+    pub const ALLOWLIST_ENTRY_PREFIX: &[u8] = b"allowlist_entry";
+    pub const BUY_TRACKER_PREFIX: &[u8] = b"buy_tracker";
 }
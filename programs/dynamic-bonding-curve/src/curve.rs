@@ -248,3 +248,76 @@ pub fn get_next_sqrt_price_from_amount_quote_rounding_down(
     let result = U256::from(sqrt_price).safe_add(quotient)?;
     Ok(result.try_into().map_err(|_| PoolError::TypeCastFailed)?)
 }
+
+/// Gets the next sqrt price given an output amount of token_a or token_b
+/// Throws if price or liquidity are 0, or if the output amount is more than the liquidity can provide
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_out: u64,
+    base_for_quote: bool,
+) -> Result<u128> {
+    assert!(sqrt_price > 0);
+    assert!(liquidity > 0);
+
+    // round to make sure that we pass the target price, so the exact output is met
+    if base_for_quote {
+        get_next_sqrt_price_from_amount_quote_rounding_down_for_output(
+            sqrt_price, liquidity, amount_out,
+        )
+    } else {
+        get_next_sqrt_price_from_amount_base_rounding_up_for_output(
+            sqrt_price, liquidity, amount_out,
+        )
+    }
+}
+
+/// Gets the next sqrt price √P' given a delta of token_base is taken out of the pool
+///
+/// Always round up, so price moves up enough to release at least the requested base amount
+///
+/// # Formula
+///
+/// * `√P' = √P * L / (L - Δx * √P)`
+///
+pub fn get_next_sqrt_price_from_amount_base_rounding_up_for_output(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+) -> Result<u128> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+    let sqrt_price = U256::from(sqrt_price);
+    let liquidity = U256::from(liquidity);
+
+    let product = U256::from(amount).safe_mul(sqrt_price)?;
+    require!(liquidity > product, PoolError::NotEnoughLiquidity);
+    let denominator = liquidity.safe_sub(product)?;
+    let result = mul_div_u256(liquidity, sqrt_price, denominator, Rounding::Up)
+        .ok_or(PoolError::MathOverflow)?;
+    Ok(result.try_into().map_err(|_| PoolError::TypeCastFailed)?)
+}
+
+/// Gets the next sqrt price given a delta of token_quote is taken out of the pool
+///
+/// Always round down, so price moves down enough to release at least the requested quote amount
+///
+/// # Formula
+///
+/// * `√P' = √P - Δy / L`
+///
+pub fn get_next_sqrt_price_from_amount_quote_rounding_down_for_output(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount: u64,
+) -> Result<u128> {
+    let quotient = U256::from(amount)
+        .safe_shl((RESOLUTION * 2) as usize)?
+        .div_ceil(U256::from(liquidity));
+
+    let result = U256::from(sqrt_price)
+        .checked_sub(quotient)
+        .ok_or(PoolError::NotEnoughLiquidity)?;
+    Ok(result.try_into().map_err(|_| PoolError::TypeCastFailed)?)
+}
@@ -127,4 +127,49 @@ pub enum PoolError {
 
     #[msg("Migration fee has been withdraw")]
     MigrationFeeHasBeenWithdraw,
+
+    #[msg("Invalid sqrt price limit")]
+    InvalidSqrtPriceLimit,
+
+    #[msg("Invalid migration fill mode")]
+    InvalidMigrationFillMode,
+
+    #[msg("Invalid activation delay")]
+    InvalidActivationDelay,
+
+    #[msg("Invalid activation point")]
+    InvalidActivationPoint,
+
+    #[msg("Pool is not activated")]
+    PoolIsNotActivated,
+
+    #[msg("Invalid allowlist parameters")]
+    InvalidAllowlistParameters,
+
+    #[msg("Pool is in allowlist phase")]
+    PoolIsInAllowlistPhase,
+
+    #[msg("Pool is not in allowlist phase")]
+    PoolIsNotInAllowlistPhase,
+
+    #[msg("Invalid allowlist proof")]
+    InvalidAllowlistProof,
+
+    #[msg("Exceeded allowlist quote cap")]
+    ExceededAllowlistQuoteCap,
+
+    #[msg("Invalid fee rate limiter")]
+    InvalidFeeRateLimiter,
+
+    #[msg("Invalid buy limit parameters")]
+    InvalidBuyLimitParameters,
+
+    #[msg("Exceeded buy limit")]
+    ExceededBuyLimit,
+
+    #[msg("Buy tracker is required")]
+    BuyTrackerRequired,
+
+    #[msg("Fail to validate single swap instruction in rate limiter")]
+    FailToValidateSingleSwapInstruction,
 }
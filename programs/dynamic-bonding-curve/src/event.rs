@@ -5,29 +5,40 @@ use crate::{
     params::{
         fee_parameters::PoolFeeParameters, liquidity_distribution::LiquidityDistributionParameters,
     },
-    state::SwapResult,
-    LockedVestingParams, SwapParameters,
+    state::{SwapResult, SwapResult2},
+    LockedVestingParams, SwapExactOutParameters, SwapParameters, SwapWithPriceLimitParameters,
 };
 
 /// Create partner metadata
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtPartnerMetadata {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub partner_metadata: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub fee_claimer: Pubkey,
 }
 
 /// Create virtual pool metadata
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtVirtualPoolMetadata {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub virtual_pool_metadata: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub virtual_pool: Pubkey,
 }
 /// Create config
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCreateConfig {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub config: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub quote_mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub fee_claimer: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub owner: Pubkey,
     pub pool_fees: PoolFeeParameters,
     pub collect_fee_mode: u8,
@@ -42,6 +53,10 @@ pub struct EvtCreateConfig {
     pub swap_base_amount: u64,
     pub migration_quote_threshold: u64,
     pub migration_base_amount: u64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_start_price: u128,
     pub locked_vesting: LockedVestingParams,
     pub migration_fee_option: u8,
@@ -53,30 +68,43 @@ pub struct EvtCreateConfig {
 
 /// Create claim fee operator
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCreateClaimFeeOperator {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub operator: Pubkey,
 }
 
 /// Close claim fee operator
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCloseClaimFeeOperator {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub claim_fee_operator: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub operator: Pubkey,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtInitializePool {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub config: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub creator: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub base_mint: Pubkey,
     pub pool_type: u8,
     pub activation_point: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtSwap {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub config: Pubkey,
     pub trade_direction: u8,
     pub has_referral: bool,
@@ -87,85 +115,145 @@ pub struct EvtSwap {
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvtSwapExactOut {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
+    pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
+    pub config: Pubkey,
+    pub trade_direction: u8,
+    pub has_referral: bool,
+    pub params: SwapExactOutParameters,
+    pub swap_result: SwapResult2,
+    pub amount_in: u64,
+    pub current_timestamp: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvtSwapWithPriceLimit {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
+    pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
+    pub config: Pubkey,
+    pub trade_direction: u8,
+    pub has_referral: bool,
+    pub params: SwapWithPriceLimitParameters,
+    pub swap_result: SwapResult2,
+    pub amount_in: u64,
+    pub current_timestamp: u64,
+}
+
+#[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCurveComplete {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub config: Pubkey,
     pub base_reserve: u64,
     pub quote_reserve: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtClaimProtocolFee {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub token_base_amount: u64,
     pub token_quote_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtClaimTradingFee {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub token_base_amount: u64,
     pub token_quote_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtClaimCreatorTradingFee {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub token_base_amount: u64,
     pub token_quote_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCreateMeteoraMigrationMetadata {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub virtual_pool: Pubkey,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCreateDammV2MigrationMetadata {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub virtual_pool: Pubkey,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtProtocolWithdrawSurplus {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub surplus_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtPartnerWithdrawSurplus {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub surplus_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtCreatorWithdrawSurplus {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub surplus_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtWithdrawLeftover {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub leftover_receiver: Pubkey,
     pub leftover_amount: u64,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtUpdatePoolCreator {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub creator: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub new_creator: Pubkey,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtWithdrawMigrationFee {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub fee: u64,
     pub flag: u8,
 }
 
 #[event]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EvtPartnerWithdrawMigrationFee {
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub pool: Pubkey,
     pub fee: u64,
 }
@@ -9,9 +9,10 @@ use anchor_spl::{
 use std::cmp::{max, min};
 
 use crate::{
-    activation_handler::get_current_point,
+    activation_handler::{get_activation_point, get_current_point},
     const_pda,
     constants::seeds::{POOL_PREFIX, TOKEN_VAULT_PREFIX},
+    params::allowlist::AllowlistParameters,
     process_create_token_metadata,
     state::{fee::VolatilityTracker, PoolConfig, PoolType, TokenType, VirtualPool},
     EvtInitializePool, PoolError, ProcessCreateTokenMetadataParams,
@@ -22,6 +23,12 @@ pub struct InitializePoolParameters {
     pub name: String,
     pub symbol: String,
     pub uri: String,
+    /// trading opens at this point, bounded by the config `max_activation_delay`. None to
+    /// activate the pool immediately
+    pub activation_point: Option<u64>,
+    /// only allowlisted wallets can buy from the activation point to the allowlist end point.
+    /// None to open trading to everyone at activation
+    pub allowlist: Option<AllowlistParameters>,
 }
 
 // To fix IDL generation: https://github.com/coral-xyz/anchor/issues/3209
@@ -145,7 +152,13 @@ pub fn handle_initialize_virtual_pool_with_spl_token<'c: 'info, 'info>(
         PoolError::InvalidTokenType
     );
 
-    let InitializePoolParameters { name, symbol, uri } = params;
+    let InitializePoolParameters {
+        name,
+        symbol,
+        uri,
+        activation_point,
+        allowlist,
+    } = params;
 
     // create token metadata
     process_create_token_metadata(ProcessCreateTokenMetadataParams {
@@ -195,7 +208,11 @@ pub fn handle_initialize_virtual_pool_with_spl_token<'c: 'info, 'info>(
     // init pool
     let mut pool = ctx.accounts.pool.load_init()?;
 
-    let activation_point = get_current_point(config.activation_type)?;
+    let activation_point = get_activation_point(
+        get_current_point(config.activation_type)?,
+        activation_point,
+        config.max_activation_delay,
+    )?;
 
     pool.initialize(
         VolatilityTracker::default(),
@@ -210,6 +227,11 @@ pub fn handle_initialize_virtual_pool_with_spl_token<'c: 'info, 'info>(
         initial_base_supply,
     );
 
+    if let Some(allowlist) = allowlist {
+        allowlist.validate(config.get_activation_type()?, activation_point)?;
+        pool.initialize_allowlist(&allowlist);
+    }
+
     emit_cpi!(EvtInitializePool {
         pool: ctx.accounts.pool.key(),
         config: ctx.accounts.config.key(),
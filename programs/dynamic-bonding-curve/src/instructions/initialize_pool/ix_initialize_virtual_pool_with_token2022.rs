@@ -2,7 +2,7 @@ use super::InitializePoolParameters;
 use super::{max_key, min_key};
 use crate::state::TokenUpdateAuthorityOption;
 use crate::{
-    activation_handler::get_current_point,
+    activation_handler::{get_activation_point, get_current_point},
     const_pda,
     constants::seeds::{POOL_PREFIX, TOKEN_VAULT_PREFIX},
     state::fee::VolatilityTracker,
@@ -123,7 +123,13 @@ pub fn handle_initialize_virtual_pool_with_token2022<'c: 'info, 'info>(
         PoolError::InvalidTokenType
     );
 
-    let InitializePoolParameters { name, symbol, uri } = params;
+    let InitializePoolParameters {
+        name,
+        symbol,
+        uri,
+        activation_point,
+        allowlist,
+    } = params;
 
     // initialize metadata
     let cpi_accounts = TokenMetadataInitialize {
@@ -203,7 +209,11 @@ pub fn handle_initialize_virtual_pool_with_token2022<'c: 'info, 'info>(
     // init pool
     let mut pool = ctx.accounts.pool.load_init()?;
 
-    let activation_point = get_current_point(config.activation_type)?;
+    let activation_point = get_activation_point(
+        get_current_point(config.activation_type)?,
+        activation_point,
+        config.max_activation_delay,
+    )?;
 
     pool.initialize(
         VolatilityTracker::default(),
@@ -218,6 +228,11 @@ pub fn handle_initialize_virtual_pool_with_token2022<'c: 'info, 'info>(
         initial_base_supply,
     );
 
+    if let Some(allowlist) = allowlist {
+        allowlist.validate(config.get_activation_type()?, activation_point)?;
+        pool.initialize_allowlist(&allowlist);
+    }
+
     emit_cpi!(EvtInitializePool {
         pool: ctx.accounts.pool.key(),
         config: ctx.accounts.config.key(),
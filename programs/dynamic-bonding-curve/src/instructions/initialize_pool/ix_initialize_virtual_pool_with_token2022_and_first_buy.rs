@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{TokenAccount, TokenInterface},
+};
+
+// glob import, the composite accounts derive needs the generated client and bumps types
+use super::ix_initialize_virtual_pool_with_token2022::*;
+use super::{process_first_buy, InitializePoolParameters, ProcessFirstBuyParams};
+use crate::SwapParameters;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeVirtualPoolWithToken2022AndFirstBuyCtx<'info> {
+    pub initialize_pool: InitializeVirtualPoolWithToken2022Ctx<'info>,
+
+    /// creator token account paying for the first buy
+    #[account(
+        mut,
+        token::mint = initialize_pool.quote_mint,
+        token::authority = initialize_pool.creator,
+        token::token_program = initialize_pool.token_quote_program,
+    )]
+    pub creator_quote_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// creator token account receiving the first buy, the base mint is created in this instruction
+    #[account(
+        init,
+        payer = initialize_pool.payer,
+        associated_token::mint = initialize_pool.base_mint,
+        associated_token::authority = initialize_pool.creator,
+        associated_token::token_program = token_program,
+    )]
+    pub creator_base_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// base token program, same as the one of `initialize_pool`
+    #[account(address = initialize_pool.token_program.key())]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle_initialize_virtual_pool_with_token2022_and_first_buy<'c: 'info, 'info>(
+    mut ctx: Context<'_, '_, 'c, 'info, InitializeVirtualPoolWithToken2022AndFirstBuyCtx<'info>>,
+    params: InitializePoolParameters,
+    first_buy: Option<SwapParameters>,
+) -> Result<()> {
+    handle_initialize_virtual_pool_with_token2022(
+        Context::new(
+            ctx.program_id,
+            &mut ctx.accounts.initialize_pool,
+            ctx.remaining_accounts,
+            std::mem::take(&mut ctx.bumps.initialize_pool),
+        ),
+        params,
+    )?;
+
+    let Some(first_buy) = first_buy else {
+        return Ok(());
+    };
+
+    let initialize_pool = &ctx.accounts.initialize_pool;
+    let events = process_first_buy(ProcessFirstBuyParams {
+        config: &initialize_pool.config,
+        pool: &initialize_pool.pool,
+        pool_authority: initialize_pool.pool_authority.to_account_info(),
+        creator: initialize_pool.creator.to_account_info(),
+        base_mint: initialize_pool.base_mint.to_account_info(),
+        quote_mint: initialize_pool.quote_mint.to_account_info(),
+        quote_decimal: initialize_pool.quote_mint.decimals,
+        base_vault: initialize_pool.base_vault.to_account_info(),
+        quote_vault: initialize_pool.quote_vault.to_account_info(),
+        creator_base_token_account: ctx.accounts.creator_base_token_account.to_account_info(),
+        creator_quote_token_account: ctx.accounts.creator_quote_token_account.to_account_info(),
+        token_base_program: ctx.accounts.token_program.to_account_info(),
+        token_quote_program: initialize_pool.token_quote_program.to_account_info(),
+        params: first_buy,
+    })?;
+
+    emit_cpi!(events.swap);
+    if let Some(evt_curve_complete) = events.curve_complete {
+        emit_cpi!(evt_curve_complete);
+    }
+
+    Ok(())
+}
@@ -4,3 +4,9 @@ pub mod ix_initialize_virtual_pool_with_token2022;
 pub use ix_initialize_virtual_pool_with_token2022::*;
 pub mod process_create_token_metadata;
 pub use process_create_token_metadata::*;
+pub mod ix_initialize_virtual_pool_with_spl_token_and_first_buy;
+pub use ix_initialize_virtual_pool_with_spl_token_and_first_buy::*;
+pub mod ix_initialize_virtual_pool_with_token2022_and_first_buy;
+pub use ix_initialize_virtual_pool_with_token2022_and_first_buy::*;
+pub mod process_first_buy;
+pub use process_first_buy::*;
@@ -0,0 +1,185 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::accessor,
+    token_interface::{transfer_checked, TransferChecked},
+};
+
+use crate::{
+    activation_handler::get_current_point,
+    const_pda,
+    params::swap::TradeDirection,
+    process_curve_complete,
+    state::{fee::FeeMode, PoolConfig, SwapResult2, VirtualPool},
+    EvtCurveComplete, EvtSwap, PoolError, SwapParameters,
+};
+
+pub struct ProcessFirstBuyParams<'a, 'info> {
+    pub config: &'a AccountLoader<'info, PoolConfig>,
+    pub pool: &'a AccountLoader<'info, VirtualPool>,
+    pub pool_authority: AccountInfo<'info>,
+    pub creator: AccountInfo<'info>,
+    pub base_mint: AccountInfo<'info>,
+    pub quote_mint: AccountInfo<'info>,
+    pub quote_decimal: u8,
+    pub base_vault: AccountInfo<'info>,
+    pub quote_vault: AccountInfo<'info>,
+    pub creator_base_token_account: AccountInfo<'info>,
+    pub creator_quote_token_account: AccountInfo<'info>,
+    pub token_base_program: AccountInfo<'info>,
+    pub token_quote_program: AccountInfo<'info>,
+    pub params: SwapParameters,
+}
+
+/// Events of the first buy, emitted by the caller through its event cpi accounts
+pub struct FirstBuyEvents {
+    pub swap: EvtSwap,
+    pub curve_complete: Option<EvtCurveComplete>,
+}
+
+/// Buy base token for the creator in the pool initialization, priced like an exact in swap
+/// without referral
+pub fn process_first_buy(params: ProcessFirstBuyParams) -> Result<FirstBuyEvents> {
+    let ProcessFirstBuyParams {
+        config,
+        pool,
+        pool_authority,
+        creator,
+        base_mint,
+        quote_mint,
+        quote_decimal,
+        base_vault,
+        quote_vault,
+        creator_base_token_account,
+        creator_quote_token_account,
+        token_base_program,
+        token_quote_program,
+        params,
+    } = params;
+
+    let pool_key = pool.key();
+    let config_key = config.key();
+    let config = config.load()?;
+    // the pool discriminator is written on exit, so the pool still loads as a new account
+    let mut pool = pool.load_init()?;
+    let current_timestamp = Clock::get()?.unix_timestamp as u64;
+    let current_point = get_current_point(config.activation_type)?;
+    let swap_result = apply_first_buy(
+        &config,
+        &mut pool,
+        &params,
+        current_point,
+        current_timestamp,
+    )?;
+
+    // send to reserve
+    transfer_checked(
+        CpiContext::new(
+            token_quote_program,
+            TransferChecked {
+                from: creator_quote_token_account,
+                mint: quote_mint,
+                to: quote_vault,
+                authority: creator,
+            },
+        ),
+        swap_result.consumed_input_amount,
+        quote_decimal,
+    )?;
+
+    // send to creator
+    let seeds = pool_authority_seeds!(const_pda::pool_authority::BUMP);
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_base_program,
+            TransferChecked {
+                from: base_vault.clone(),
+                mint: base_mint,
+                to: creator_base_token_account,
+                authority: pool_authority,
+            },
+            &[&seeds[..]],
+        ),
+        swap_result.output_amount,
+        config.token_decimal,
+    )?;
+
+    let curve_complete = if pool.is_curve_complete(config.migration_quote_threshold) {
+        process_curve_complete(
+            &mut pool,
+            &config,
+            accessor::amount(&base_vault)?,
+            current_timestamp,
+        )?;
+        Some(EvtCurveComplete {
+            pool: pool_key,
+            config: config_key,
+            base_reserve: pool.base_reserve,
+            quote_reserve: pool.quote_reserve,
+        })
+    } else {
+        None
+    };
+
+    let amount_in = swap_result.consumed_input_amount;
+    Ok(FirstBuyEvents {
+        swap: EvtSwap {
+            pool: pool_key,
+            config: config_key,
+            trade_direction: TradeDirection::QuoteToBase.into(),
+            has_referral: false,
+            params,
+            swap_result: swap_result.into(),
+            amount_in,
+            current_timestamp,
+        },
+        curve_complete,
+    })
+}
+
+/// Price the first buy and apply it to the pool. The creator buys in the pool initialization,
+/// before anyone else can trade, so the first buy isn't gated by the activation point or the
+/// allowlist phase, it is priced at the activation point if that is still ahead. The creator
+/// quote spent isn't tracked, so only the per swap limit applies
+pub fn apply_first_buy(
+    config: &PoolConfig,
+    pool: &mut VirtualPool,
+    params: &SwapParameters,
+    current_point: u64,
+    current_timestamp: u64,
+) -> Result<SwapResult2> {
+    require!(params.amount_in > 0, PoolError::AmountIsZero);
+
+    pool.update_pre_swap(config, current_timestamp)?;
+
+    let trade_direction = TradeDirection::QuoteToBase;
+    let current_point = current_point.max(pool.activation_point);
+    let fee_mode = &FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false)?;
+    let swap_result = pool.get_swap_result(
+        config,
+        params.amount_in,
+        fee_mode,
+        trade_direction,
+        current_point,
+    )?;
+    require!(
+        swap_result.output_amount >= params.minimum_amount_out,
+        PoolError::ExceededSlippage
+    );
+    config.validate_buy_limit(
+        swap_result.consumed_input_amount,
+        current_point,
+        pool.activation_point,
+        trade_direction,
+        true,
+    )?;
+
+    pool.apply_swap_result(
+        config,
+        &swap_result,
+        fee_mode,
+        trade_direction,
+        current_timestamp,
+    )?;
+
+    Ok(swap_result)
+}
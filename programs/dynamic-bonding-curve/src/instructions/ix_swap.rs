@@ -8,15 +8,56 @@ use crate::{
     state::fee::FeeMode,
     state::{PoolConfig, VirtualPool},
     token::{transfer_from_pool, transfer_from_user},
-    EvtSwap, PoolError,
+    EvtSwap, EvtSwapExactOut, EvtSwapWithPriceLimit, PoolError,
 };
-use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{
+    get_processed_sibling_instruction, get_stack_height, Instruction,
+};
+use anchor_lang::{prelude::*, Discriminator};
 use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SwapParameters {
-    amount_in: u64,
-    minimum_amount_out: u64,
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapExactOutParameters {
+    pub amount_out: u64,
+    pub maximum_amount_in: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapWithPriceLimitParameters {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    /// swap stops at this price, unused input is kept by user
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
+    pub sqrt_price_limit: u128,
+}
+
+/// Endpoint a swap comes from, the allowlist and buy limit checks depend on it
+#[derive(Clone, Copy, PartialEq)]
+enum SwapSource {
+    Public,
+    /// the allowlist proof of the payer has been verified
+    Allowlisted,
+    /// the quote spent by the payer is tracked in a buy tracker
+    BuyTracked,
+}
+
+enum SwapMode {
+    ExactIn(SwapParameters),
+    ExactOut(SwapExactOutParameters),
+    PriceLimit(SwapWithPriceLimitParameters),
 }
 
 #[event_cpi]
@@ -81,13 +122,47 @@ impl<'info> SwapCtx<'info> {
     }
 }
 
-// TODO impl swap exact out
 pub fn handle_swap(ctx: Context<SwapCtx>, params: SwapParameters) -> Result<()> {
-    let SwapParameters {
-        amount_in,
-        minimum_amount_out,
-    } = params;
+    handle_swap_wrapper(ctx, SwapMode::ExactIn(params), SwapSource::Public)?;
+    Ok(())
+}
 
+pub fn handle_swap_exact_out(ctx: Context<SwapCtx>, params: SwapExactOutParameters) -> Result<()> {
+    handle_swap_wrapper(ctx, SwapMode::ExactOut(params), SwapSource::Public)?;
+    Ok(())
+}
+
+pub fn handle_swap_with_price_limit(
+    ctx: Context<SwapCtx>,
+    params: SwapWithPriceLimitParameters,
+) -> Result<()> {
+    handle_swap_wrapper(ctx, SwapMode::PriceLimit(params), SwapSource::Public)?;
+    Ok(())
+}
+
+/// Exact in swap of a wallet whose allowlist proof has been verified, returns the consumed input
+pub(crate) fn handle_allowlisted_swap(
+    ctx: Context<SwapCtx>,
+    params: SwapParameters,
+) -> Result<u64> {
+    handle_swap_wrapper(ctx, SwapMode::ExactIn(params), SwapSource::Allowlisted)
+}
+
+/// Exact in swap of a wallet whose spent quote is tracked by the caller, returns the consumed
+/// input
+pub(crate) fn handle_buy_tracked_swap(
+    ctx: Context<SwapCtx>,
+    params: SwapParameters,
+) -> Result<u64> {
+    handle_swap_wrapper(ctx, SwapMode::ExactIn(params), SwapSource::BuyTracked)
+}
+
+/// Returns the consumed input amount
+fn handle_swap_wrapper(
+    ctx: Context<SwapCtx>,
+    swap_mode: SwapMode,
+    swap_source: SwapSource,
+) -> Result<u64> {
     let trade_direction = ctx.accounts.get_trade_direction();
     let (
         token_in_mint,
@@ -115,8 +190,6 @@ pub fn handle_swap(ctx: Context<SwapCtx>, params: SwapParameters) -> Result<()>
         ),
     };
 
-    require!(amount_in > 0, PoolError::AmountIsZero);
-
     let has_referral = ctx.accounts.referral_token_account.is_some();
 
     let config = ctx.accounts.config.load()?;
@@ -133,15 +206,106 @@ pub fn handle_swap(ctx: Context<SwapCtx>, params: SwapParameters) -> Result<()>
     pool.update_pre_swap(&config, current_timestamp)?;
 
     let current_point = get_current_point(config.activation_type)?;
+    require!(
+        current_point >= pool.activation_point,
+        PoolError::PoolIsNotActivated
+    );
+    // allowlisted swaps are only allowed in the allowlist phase, where they are required for buys,
+    // anyone can still sell
+    let is_allowlisted = swap_source == SwapSource::Allowlisted;
+    if pool.is_allowlist_phase(current_point) {
+        if trade_direction == TradeDirection::QuoteToBase {
+            require!(is_allowlisted, PoolError::PoolIsInAllowlistPhase);
+        }
+    } else {
+        require!(!is_allowlisted, PoolError::PoolIsNotInAllowlistPhase);
+    }
+    // a buy split into smaller swaps in one transaction would only pay the lower rate limiter fee
+    if config.pool_fees.base_fee.is_rate_limiter_applied(
+        current_point,
+        pool.activation_point,
+        trade_direction,
+    )? {
+        validate_single_swap_instruction(&ctx.accounts.pool.key(), ctx.remaining_accounts)?;
+    }
     let fee_mode = &FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral)?;
 
-    let swap_result =
-        pool.get_swap_result(&config, amount_in, fee_mode, trade_direction, current_point)?;
+    let swap_result = match &swap_mode {
+        SwapMode::ExactIn(SwapParameters {
+            amount_in,
+            minimum_amount_out,
+        }) => {
+            require!(*amount_in > 0, PoolError::AmountIsZero);
+
+            let swap_result = pool.get_swap_result(
+                &config,
+                *amount_in,
+                fee_mode,
+                trade_direction,
+                current_point,
+            )?;
 
-    require!(
-        swap_result.output_amount >= minimum_amount_out,
-        PoolError::ExceededSlippage
-    );
+            require!(
+                swap_result.output_amount >= *minimum_amount_out,
+                PoolError::ExceededSlippage
+            );
+            swap_result
+        }
+        SwapMode::ExactOut(SwapExactOutParameters {
+            amount_out,
+            maximum_amount_in,
+        }) => {
+            require!(*amount_out > 0, PoolError::AmountIsZero);
+
+            let swap_result = pool.get_swap_result_from_exact_output(
+                &config,
+                *amount_out,
+                fee_mode,
+                trade_direction,
+                current_point,
+            )?;
+
+            require!(
+                swap_result.consumed_input_amount <= *maximum_amount_in,
+                PoolError::ExceededSlippage
+            );
+            swap_result
+        }
+        SwapMode::PriceLimit(SwapWithPriceLimitParameters {
+            amount_in,
+            minimum_amount_out,
+            sqrt_price_limit,
+        }) => {
+            require!(*amount_in > 0, PoolError::AmountIsZero);
+
+            let swap_result = pool.get_swap_result_with_sqrt_price_limit(
+                &config,
+                *amount_in,
+                fee_mode,
+                trade_direction,
+                current_point,
+                Some(*sqrt_price_limit),
+            )?;
+
+            require!(
+                swap_result.output_amount >= *minimum_amount_out,
+                PoolError::ExceededSlippage
+            );
+            swap_result
+        }
+    };
+
+    // only transfer the consumed amount, the rest stays with user
+    let amount_in = swap_result.consumed_input_amount;
+    require!(amount_in > 0, PoolError::AmountIsZero);
+    // allowlisted wallets are capped by the allowlist quote cap instead of the per wallet limit
+    config.validate_buy_limit(
+        amount_in,
+        current_point,
+        pool.activation_point,
+        trade_direction,
+        swap_source != SwapSource::Public,
+    )?;
 
     pool.apply_swap_result(
         &config,
@@ -197,46 +361,53 @@ pub fn handle_swap(ctx: Context<SwapCtx>, params: SwapParameters) -> Result<()>
         }
     }
 
-    emit_cpi!(EvtSwap {
-        pool: ctx.accounts.pool.key(),
-        config: ctx.accounts.config.key(),
-        trade_direction: trade_direction.into(),
-        params,
-        swap_result,
-        has_referral,
-        amount_in,
-        current_timestamp,
-    });
+    match swap_mode {
+        SwapMode::ExactIn(params) => {
+            emit_cpi!(EvtSwap {
+                pool: ctx.accounts.pool.key(),
+                config: ctx.accounts.config.key(),
+                trade_direction: trade_direction.into(),
+                params,
+                swap_result: swap_result.into(),
+                has_referral,
+                amount_in,
+                current_timestamp,
+            });
+        }
+        SwapMode::ExactOut(params) => {
+            emit_cpi!(EvtSwapExactOut {
+                pool: ctx.accounts.pool.key(),
+                config: ctx.accounts.config.key(),
+                trade_direction: trade_direction.into(),
+                params,
+                swap_result,
+                has_referral,
+                amount_in,
+                current_timestamp,
+            });
+        }
+        SwapMode::PriceLimit(params) => {
+            emit_cpi!(EvtSwapWithPriceLimit {
+                pool: ctx.accounts.pool.key(),
+                config: ctx.accounts.config.key(),
+                trade_direction: trade_direction.into(),
+                params,
+                swap_result,
+                has_referral,
+                amount_in,
+                current_timestamp,
+            });
+        }
+    }
 
     if pool.is_curve_complete(config.migration_quote_threshold) {
         ctx.accounts.base_vault.reload()?;
-        // validate if base reserve is enough token for migration
-        let base_vault_balance = ctx.accounts.base_vault.amount;
-
-        let required_base_balance = config
-            .migration_base_threshold
-            .safe_add(pool.get_protocol_and_trading_base_fee()?)?
-            .safe_add(
-                config
-                    .locked_vesting_config
-                    .to_locked_vesting_params()
-                    .get_total_amount()?,
-            )?;
-
-        require!(
-            base_vault_balance >= required_base_balance,
-            PoolError::InsufficientLiquidityForMigration
-        );
-
-        // set finish time and migration progress
-        pool.finish_curve_timestamp = current_timestamp;
-
-        let locked_vesting_params = config.locked_vesting_config.to_locked_vesting_params();
-        if locked_vesting_params.has_vesting() {
-            pool.set_migration_progress(MigrationProgress::PostBondingCurve.into());
-        } else {
-            pool.set_migration_progress(MigrationProgress::LockedVesting.into());
-        }
+        process_curve_complete(
+            &mut pool,
+            &config,
+            ctx.accounts.base_vault.amount,
+            current_timestamp,
+        )?;
 
         emit_cpi!(EvtCurveComplete {
             pool: ctx.accounts.pool.key(),
@@ -246,5 +417,108 @@ pub fn handle_swap(ctx: Context<SwapCtx>, params: SwapParameters) -> Result<()>
         })
     }
 
+    Ok(amount_in)
+}
+
+/// Instructions of this program that swap in a pool
+const SWAP_INSTRUCTION_DISCRIMINATORS: [&[u8]; 7] = [
+    crate::instruction::Swap::DISCRIMINATOR,
+    crate::instruction::SwapExactOut::DISCRIMINATOR,
+    crate::instruction::SwapWithPriceLimit::DISCRIMINATOR,
+    crate::instruction::SwapAllowlisted::DISCRIMINATOR,
+    crate::instruction::SwapWithBuyTracker::DISCRIMINATOR,
+    crate::instruction::InitializeVirtualPoolWithSplTokenAndFirstBuy::DISCRIMINATOR,
+    crate::instruction::InitializeVirtualPoolWithToken2022AndFirstBuy::DISCRIMINATOR,
+];
+
+fn is_swap_instruction_on_pool(instruction: &Instruction, pool: &Pubkey) -> bool {
+    let has_pool = instruction
+        .accounts
+        .iter()
+        .any(|account| account.pubkey.eq(pool));
+    if instruction.program_id != crate::ID {
+        // any instruction of another program on the pool could be a swap through a CPI
+        return has_pool;
+    }
+    has_pool
+        && SWAP_INSTRUCTION_DISCRIMINATORS
+            .iter()
+            .any(|discriminator| instruction.data.starts_with(discriminator))
+}
+
+/// Validates the current swap is the only swap on the pool in the transaction so far, the
+/// instructions sysvar is the first remaining account. Called through a CPI, the caller must be a
+/// top level instruction that didn't swap on the pool before
+pub fn validate_single_swap_instruction(
+    pool: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    let instruction_sysvar_account_info = remaining_accounts
+        .first()
+        .ok_or(PoolError::FailToValidateSingleSwapInstruction)?;
+
+    let current_index = load_current_index_checked(instruction_sysvar_account_info)?;
+    let current_instruction =
+        load_instruction_at_checked(current_index.into(), instruction_sysvar_account_info)?;
+
+    if current_instruction.program_id != crate::ID {
+        // only a single CPI level, so the sibling instructions are all the caller swaps
+        require!(
+            get_stack_height() <= 2,
+            PoolError::FailToValidateSingleSwapInstruction
+        );
+        let mut sibling_index = 0;
+        while let Some(sibling_instruction) = get_processed_sibling_instruction(sibling_index) {
+            require!(
+                !is_swap_instruction_on_pool(&sibling_instruction, pool),
+                PoolError::FailToValidateSingleSwapInstruction
+            );
+            sibling_index = sibling_index.safe_add(1)?;
+        }
+    }
+
+    for i in 0..current_index {
+        let instruction = load_instruction_at_checked(i.into(), instruction_sysvar_account_info)?;
+        if is_swap_instruction_on_pool(&instruction, pool) {
+            msg!("Multiple swaps not allowed");
+            return Err(PoolError::FailToValidateSingleSwapInstruction.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Called once a swap completes the curve, validates the base vault holds enough token for
+/// migration, then sets the finish time and migration progress
+pub fn process_curve_complete(
+    pool: &mut VirtualPool,
+    config: &PoolConfig,
+    base_vault_balance: u64,
+    current_timestamp: u64,
+) -> Result<()> {
+    let required_base_balance = config
+        .migration_base_threshold
+        .safe_add(pool.get_protocol_and_trading_base_fee()?)?
+        .safe_add(
+            config
+                .locked_vesting_config
+                .to_locked_vesting_params()
+                .get_total_amount()?,
+        )?;
+
+    require!(
+        base_vault_balance >= required_base_balance,
+        PoolError::InsufficientLiquidityForMigration
+    );
+
+    pool.finish_curve_timestamp = current_timestamp;
+
+    let locked_vesting_params = config.locked_vesting_config.to_locked_vesting_params();
+    if locked_vesting_params.has_vesting() {
+        pool.set_migration_progress(MigrationProgress::PostBondingCurve.into());
+    } else {
+        pool.set_migration_progress(MigrationProgress::LockedVesting.into());
+    }
+
     Ok(())
 }
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+
+// glob import, the composite accounts derive needs the generated client and bumps types
+use super::ix_swap::*;
+use crate::{
+    allowlist::verify_allowlist_proof, constants::seeds::ALLOWLIST_ENTRY_PREFIX,
+    params::swap::TradeDirection, safe_math::SafeMath, state::AllowlistEntry, PoolError,
+};
+
+#[derive(Accounts)]
+pub struct SwapAllowlistedCtx<'info> {
+    /// accounts of the swap, `swap.payer` is the allowlisted wallet
+    pub swap: SwapCtx<'info>,
+
+    /// Quote spent by `swap.payer` in the allowlist phase
+    #[account(
+        init_if_needed,
+        seeds = [
+            ALLOWLIST_ENTRY_PREFIX,
+            swap.pool.key().as_ref(),
+            swap.payer.key().as_ref(),
+        ],
+        bump,
+        payer = rent_payer,
+        space = 8 + AllowlistEntry::INIT_SPACE
+    )]
+    pub allowlist_entry: Box<Account<'info, AllowlistEntry>>,
+
+    /// pays the allowlist entry rent on the first buy
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle_swap_allowlisted<'c: 'info, 'info>(
+    mut ctx: Context<'_, '_, 'c, 'info, SwapAllowlistedCtx<'info>>,
+    params: SwapParameters,
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    require!(
+        ctx.accounts.swap.get_trade_direction() == TradeDirection::QuoteToBase,
+        PoolError::InvalidInput
+    );
+
+    let wallet = ctx.accounts.swap.payer.key();
+    let (merkle_root, quote_cap_per_wallet) = {
+        let pool = ctx.accounts.swap.pool.load()?;
+        (
+            pool.allowlist_merkle_root,
+            pool.allowlist_quote_cap_per_wallet,
+        )
+    };
+    require!(
+        verify_allowlist_proof(&merkle_root, &wallet, &proof),
+        PoolError::InvalidAllowlistProof
+    );
+
+    // the phase is checked in the swap
+    let amount_in = handle_allowlisted_swap(
+        Context::new(
+            ctx.program_id,
+            &mut ctx.accounts.swap,
+            ctx.remaining_accounts,
+            std::mem::take(&mut ctx.bumps.swap),
+        ),
+        params,
+    )?;
+
+    let allowlist_entry = &mut ctx.accounts.allowlist_entry;
+    if allowlist_entry.pool == Pubkey::default() {
+        allowlist_entry.pool = ctx.accounts.swap.pool.key();
+        allowlist_entry.owner = wallet;
+    }
+    allowlist_entry.bought_quote_amount =
+        allowlist_entry.bought_quote_amount.safe_add(amount_in)?;
+    require!(
+        allowlist_entry.bought_quote_amount <= quote_cap_per_wallet,
+        PoolError::ExceededAllowlistQuoteCap
+    );
+
+    Ok(())
+}
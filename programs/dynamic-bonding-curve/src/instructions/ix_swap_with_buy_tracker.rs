@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+
+// glob import, the composite accounts derive needs the generated client and bumps types
+use super::ix_swap::*;
+use crate::{
+    activation_handler::get_current_point, constants::seeds::BUY_TRACKER_PREFIX,
+    params::swap::TradeDirection, safe_math::SafeMath, state::BuyTracker, PoolError,
+};
+
+#[derive(Accounts)]
+pub struct SwapWithBuyTrackerCtx<'info> {
+    /// accounts of the swap, `swap.payer` is the tracked wallet
+    pub swap: SwapCtx<'info>,
+
+    /// Quote spent by `swap.payer` while the buy limits apply
+    #[account(
+        init_if_needed,
+        seeds = [
+            BUY_TRACKER_PREFIX,
+            swap.pool.key().as_ref(),
+            swap.payer.key().as_ref(),
+        ],
+        bump,
+        payer = rent_payer,
+        space = 8 + BuyTracker::INIT_SPACE
+    )]
+    pub buy_tracker: Box<Account<'info, BuyTracker>>,
+
+    /// pays the buy tracker rent on the first buy
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle_swap_with_buy_tracker<'c: 'info, 'info>(
+    mut ctx: Context<'_, '_, 'c, 'info, SwapWithBuyTrackerCtx<'info>>,
+    params: SwapParameters,
+) -> Result<()> {
+    require!(
+        ctx.accounts.swap.get_trade_direction() == TradeDirection::QuoteToBase,
+        PoolError::InvalidInput
+    );
+
+    let wallet = ctx.accounts.swap.payer.key();
+    let (is_buy_limit_applied, max_buy_quote_per_wallet) = {
+        let config = ctx.accounts.swap.config.load()?;
+        let pool = ctx.accounts.swap.pool.load()?;
+        let current_point = get_current_point(config.activation_type)?;
+        (
+            config.is_buy_limit_applied(
+                current_point,
+                pool.activation_point,
+                TradeDirection::QuoteToBase,
+            )?,
+            config.max_buy_quote_per_wallet,
+        )
+    };
+
+    // the per swap limit is checked in the swap
+    let amount_in = handle_buy_tracked_swap(
+        Context::new(
+            ctx.program_id,
+            &mut ctx.accounts.swap,
+            ctx.remaining_accounts,
+            std::mem::take(&mut ctx.bumps.swap),
+        ),
+        params,
+    )?;
+
+    // buys after the buy limits are not tracked
+    if !is_buy_limit_applied {
+        return Ok(());
+    }
+
+    let buy_tracker = &mut ctx.accounts.buy_tracker;
+    if buy_tracker.pool == Pubkey::default() {
+        buy_tracker.pool = ctx.accounts.swap.pool.key();
+        buy_tracker.owner = wallet;
+    }
+    buy_tracker.bought_quote_amount = buy_tracker.bought_quote_amount.safe_add(amount_in)?;
+    require!(
+        max_buy_quote_per_wallet == 0
+            || buy_tracker.bought_quote_amount <= max_buy_quote_per_wallet,
+        PoolError::ExceededBuyLimit
+    );
+
+    Ok(())
+}
@@ -2,6 +2,10 @@ pub mod admin;
 pub use admin::*;
 pub mod ix_swap;
 pub use ix_swap::*;
+pub mod ix_swap_allowlisted;
+pub use ix_swap_allowlisted::*;
+pub mod ix_swap_with_buy_tracker;
+pub use ix_swap_with_buy_tracker::*;
 pub mod initialize_pool;
 pub use initialize_pool::*;
 pub mod partner;
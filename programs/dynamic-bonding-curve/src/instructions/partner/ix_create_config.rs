@@ -5,7 +5,10 @@ use static_assertions::const_assert_eq;
 
 use crate::{
     activation_handler::ActivationType,
-    constants::{MAX_CURVE_POINT, MAX_SQRT_PRICE, MIN_SQRT_PRICE},
+    constants::{
+        MAX_ACTIVATION_SLOT_DELAY, MAX_ACTIVATION_TIMESTAMP_DELAY, MAX_BUY_LIMIT_SLOT_DURATION,
+        MAX_BUY_LIMIT_TIMESTAMP_DURATION, MAX_CURVE_POINT, MAX_SQRT_PRICE, MIN_SQRT_PRICE,
+    },
     params::{
         fee_parameters::PoolFeeParameters,
         liquidity_distribution::{
@@ -15,14 +18,15 @@ use crate::{
     },
     safe_math::SafeMath,
     state::{
-        CollectFeeMode, LockedVestingConfig, MigrationFeeOption, MigrationOption, PoolConfig,
-        TokenType, TokenUpdateAuthorityOption,
+        CollectFeeMode, LockedVestingConfig, MigrationFeeOption, MigrationFillMode,
+        MigrationOption, PoolConfig, TokenType, TokenUpdateAuthorityOption,
     },
     token::{get_token_program_flags, is_supported_quote_mint},
     EvtCreateConfig, PoolError,
 };
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConfigParameters {
     pub pool_fees: PoolFeeParameters,
     pub collect_fee_mode: u8,
@@ -35,6 +39,10 @@ pub struct ConfigParameters {
     pub creator_lp_percentage: u8,
     pub creator_locked_lp_percentage: u8,
     pub migration_quote_threshold: u64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_start_price: u128,
     pub locked_vesting: LockedVestingParams,
     pub migration_fee_option: u8,
@@ -42,13 +50,27 @@ pub struct ConfigParameters {
     pub creator_trading_fee_percentage: u8, // percentage of trading fee creator can share with partner
     pub token_update_authority: u8,
     pub migration_fee: MigrationFee,
-    pub padding_0: [u8; 4],
+    pub migration_fill_mode: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub padding_0: [u8; 3],
+    /// max delay between pool creation and activation, in slot or second depending on
+    /// `activation_type`
+    pub max_activation_delay: u64,
+    /// buy limits apply from the activation point for this duration, in slot or second depending
+    /// on `activation_type`. 0 disables the buy limits
+    pub buy_limit_duration: u64,
+    /// max quote amount of a buy while the buy limits apply, 0 means no limit
+    pub max_buy_quote_per_swap: u64,
+    /// max quote amount a wallet can spend while the buy limits apply, 0 means no limit
+    pub max_buy_quote_per_wallet: u64,
     /// padding for future use
-    pub padding_1: [u64; 7],
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub padding_1: [u64; 3],
     pub curve: Vec<LiquidityDistributionParameters>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, InitSpace)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MigrationFee {
     pub fee_percentage: u8,
     pub creator_fee_percentage: u8,
@@ -77,6 +99,7 @@ impl MigrationFee {
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TokenSupplyParams {
     /// pre migration token supply
     pub pre_migration_token_supply: u64,
@@ -86,6 +109,7 @@ pub struct TokenSupplyParams {
     pub post_migration_token_supply: u64,
 }
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LockedVestingParams {
     pub amount_per_period: u64,
     pub cliff_duration_from_migration_time: u64,
@@ -155,7 +179,8 @@ impl ConfigParameters {
         );
 
         // validate fee
-        self.pool_fees.validate()?;
+        self.pool_fees
+            .validate(self.collect_fee_mode, self.activation_type)?;
 
         // validate creator trading fee percentage
         require!(
@@ -192,18 +217,51 @@ impl ConfigParameters {
             }
         }
 
-        // validate activation type
+        // validate activation type and delay
+        let activation_type = ActivationType::try_from(self.activation_type)
+            .map_err(|_| PoolError::InvalidActivationType)?;
+        let max_activation_delay = match activation_type {
+            ActivationType::Slot => MAX_ACTIVATION_SLOT_DELAY,
+            ActivationType::Timestamp => MAX_ACTIVATION_TIMESTAMP_DELAY,
+        };
         require!(
-            ActivationType::try_from(self.activation_type).is_ok(),
-            PoolError::InvalidActivationType
+            self.max_activation_delay <= max_activation_delay,
+            PoolError::InvalidActivationDelay
         );
 
+        // validate buy limits
+        if self.buy_limit_duration > 0 {
+            let max_buy_limit_duration = match activation_type {
+                ActivationType::Slot => MAX_BUY_LIMIT_SLOT_DURATION,
+                ActivationType::Timestamp => MAX_BUY_LIMIT_TIMESTAMP_DURATION,
+            };
+            require!(
+                self.buy_limit_duration <= max_buy_limit_duration
+                    && (self.max_buy_quote_per_swap > 0 || self.max_buy_quote_per_wallet > 0)
+                    && (self.max_buy_quote_per_swap == 0
+                        || self.max_buy_quote_per_wallet == 0
+                        || self.max_buy_quote_per_swap <= self.max_buy_quote_per_wallet),
+                PoolError::InvalidBuyLimitParameters
+            );
+        } else {
+            require!(
+                self.max_buy_quote_per_swap == 0 && self.max_buy_quote_per_wallet == 0,
+                PoolError::InvalidBuyLimitParameters
+            );
+        }
+
         // validate token update authority
         require!(
             TokenUpdateAuthorityOption::try_from(self.token_update_authority).is_ok(),
             PoolError::InvalidTokenUpdateAuthorityOption
         );
 
+        // validate migration fill mode
+        require!(
+            MigrationFillMode::try_from(self.migration_fill_mode).is_ok(),
+            PoolError::InvalidMigrationFillMode
+        );
+
         // validate token decimals
         require!(
             self.token_decimal >= 6 && self.token_decimal <= 9,
@@ -316,6 +374,11 @@ pub fn handle_create_config(
         creator_trading_fee_percentage,
         token_update_authority,
         migration_fee,
+        migration_fill_mode,
+        max_activation_delay,
+        buy_limit_duration,
+        max_buy_quote_per_swap,
+        max_buy_quote_per_wallet,
         ..
     } = config_parameters;
 
@@ -395,6 +458,11 @@ pub fn handle_create_config(
         creator_trading_fee_percentage,
         token_update_authority,
         migration_fee,
+        migration_fill_mode,
+        max_activation_delay,
+        buy_limit_duration,
+        max_buy_quote_per_swap,
+        max_buy_quote_per_wallet,
         collect_fee_mode,
         migration_option,
         activation_type,
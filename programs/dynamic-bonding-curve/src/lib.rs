@@ -89,6 +89,26 @@ pub mod dynamic_bonding_curve {
         instructions::handle_initialize_virtual_pool_with_token2022(ctx, params)
     }
 
+    pub fn initialize_virtual_pool_with_spl_token_and_first_buy<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, InitializeVirtualPoolWithSplTokenAndFirstBuyCtx<'info>>,
+        params: InitializePoolParameters,
+        first_buy: Option<SwapParameters>,
+    ) -> Result<()> {
+        instructions::handle_initialize_virtual_pool_with_spl_token_and_first_buy(
+            ctx, params, first_buy,
+        )
+    }
+
+    pub fn initialize_virtual_pool_with_token2022_and_first_buy<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, InitializeVirtualPoolWithToken2022AndFirstBuyCtx<'info>>,
+        params: InitializePoolParameters,
+        first_buy: Option<SwapParameters>,
+    ) -> Result<()> {
+        instructions::handle_initialize_virtual_pool_with_token2022_and_first_buy(
+            ctx, params, first_buy,
+        )
+    }
+
     pub fn create_virtual_pool_metadata(
         ctx: Context<CreateVirtualPoolMetadataCtx>,
         metadata: CreateVirtualPoolMetadataParameters,
@@ -125,6 +145,35 @@ pub mod dynamic_bonding_curve {
         instructions::handle_swap(ctx, params)
     }
 
+    pub fn swap_exact_out(ctx: Context<SwapCtx>, params: SwapExactOutParameters) -> Result<()> {
+        instructions::handle_swap_exact_out(ctx, params)
+    }
+
+    pub fn swap_with_price_limit(
+        ctx: Context<SwapCtx>,
+        params: SwapWithPriceLimitParameters,
+    ) -> Result<()> {
+        instructions::handle_swap_with_price_limit(ctx, params)
+    }
+
+    /// buy in the allowlist phase, `proof` shows the payer is in the pool allowlist
+    pub fn swap_allowlisted<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, SwapAllowlistedCtx<'info>>,
+        params: SwapParameters,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        instructions::handle_swap_allowlisted(ctx, params, proof)
+    }
+
+    /// buy while the config buy limits apply, the quote spent by the payer is tracked against the
+    /// per wallet limit
+    pub fn swap_with_buy_tracker<'c: 'info, 'info>(
+        ctx: Context<'_, '_, 'c, 'info, SwapWithBuyTrackerCtx<'info>>,
+        params: SwapParameters,
+    ) -> Result<()> {
+        instructions::handle_swap_with_buy_tracker(ctx, params)
+    }
+
     /// PERMISSIONLESS FUNCTIONS ///
     /// create locker
     pub fn create_locker(ctx: Context<CreateLockerCtx>) -> Result<()> {
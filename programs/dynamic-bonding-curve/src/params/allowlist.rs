@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    activation_handler::ActivationType,
+    constants::{MAX_ALLOWLIST_SLOT_DURATION, MAX_ALLOWLIST_TIMESTAMP_DURATION},
+    PoolError,
+};
+
+/// Allowlist phase set by the creator when initializing a pool
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AllowlistParameters {
+    /// root of the merkle tree of allowlisted wallets, built with `get_allowlist_leaf` and
+    /// `hash_allowlist_nodes`
+    pub merkle_root: [u8; 32],
+    /// only allowlisted wallets can buy until this point, in slot or timestamp
+    pub end_point: u64,
+    /// max quote amount a wallet can spend in the allowlist phase, trading fee included
+    pub quote_cap_per_wallet: u64,
+}
+
+impl AllowlistParameters {
+    pub fn validate(&self, activation_type: ActivationType, activation_point: u64) -> Result<()> {
+        let max_duration = match activation_type {
+            ActivationType::Slot => MAX_ALLOWLIST_SLOT_DURATION,
+            ActivationType::Timestamp => MAX_ALLOWLIST_TIMESTAMP_DURATION,
+        };
+        require!(
+            self.merkle_root != [0; 32]
+                && self.quote_cap_per_wallet > 0
+                && self.end_point > activation_point
+                && self.end_point - activation_point <= max_duration,
+            PoolError::InvalidAllowlistParameters
+        );
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use anchor_lang::prelude::*;
 
 /// Information regarding fee charges
 #[derive(Copy, Clone, Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PoolFeeParameters {
     /// Base fee
     pub base_fee: BaseFeeParameters,
@@ -19,6 +20,7 @@ pub struct PoolFeeParameters {
 }
 
 #[derive(Copy, Clone, Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BaseFeeParameters {
     pub cliff_fee_numerator: u64,
     pub number_of_period: u16,
@@ -45,7 +47,11 @@ impl FeeSchedulerParameters {
 }
 
 impl BaseFeeParameters {
-    fn validate(&self) -> Result<()> {
+    fn validate(&self, collect_fee_mode: u8, activation_type: u8) -> Result<()> {
+        if let Some(rate_limiter) = self.to_base_fee_config().get_fee_rate_limiter()? {
+            return rate_limiter.validate(collect_fee_mode, activation_type);
+        }
+
         let base_fee_scheduler = self.to_base_fee_scheduler();
         if base_fee_scheduler != FeeSchedulerParameters::default() {
             base_fee_scheduler.validate_non_zero()?;
@@ -109,8 +115,13 @@ impl PoolFeeParameters {
 }
 
 #[derive(Copy, Clone, Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DynamicFeeParameters {
     pub bin_step: u16,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub bin_step_u128: u128,
     pub filter_period: u16,
     pub decay_period: u16,
@@ -209,8 +220,8 @@ pub fn to_bps(numerator: u128, denominator: u128) -> Result<u64> {
 
 impl PoolFeeParameters {
     /// Validate that the fees are reasonable
-    pub fn validate(&self) -> Result<()> {
-        self.base_fee.validate()?;
+    pub fn validate(&self, collect_fee_mode: u8, activation_type: u8) -> Result<()> {
+        self.base_fee.validate(collect_fee_mode, activation_type)?;
 
         if let Some(dynamic_fee) = self.dynamic_fee {
             dynamic_fee.validate()?;
@@ -19,8 +19,17 @@ use crate::{
 use crate::curve::get_initialize_amounts;
 
 #[derive(Copy, Clone, Debug, AnchorSerialize, AnchorDeserialize, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LiquidityDistributionParameters {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_price: u128,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub liquidity: u128,
 }
 
@@ -1,3 +1,4 @@
+pub mod allowlist;
 pub mod fee_parameters;
 pub mod liquidity_distribution;
 pub mod swap;
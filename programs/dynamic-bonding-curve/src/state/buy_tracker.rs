@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+/// Quote spent by a wallet while the buy limits of a pool apply
+#[account]
+#[derive(InitSpace, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuyTracker {
+    /// pool
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
+    pub pool: Pubkey,
+    /// tracked wallet
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    /// quote amount spent, trading fee included
+    pub bought_quote_amount: u64,
+    /// padding for future use
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub padding: [u64; 4],
+}
@@ -4,22 +4,32 @@ use ruint::aliases::U256;
 use static_assertions::const_assert_eq;
 
 use crate::{
+    activation_handler::ActivationType,
     constants::{
-        fee::{FEE_DENOMINATOR, MAX_FEE_NUMERATOR},
-        MAX_CURVE_POINT_CONFIG, MAX_SQRT_PRICE, MAX_SWALLOW_PERCENTAGE, SWAP_BUFFER_PERCENTAGE,
+        fee::{
+            FEE_DENOMINATOR, MAX_FEE_BPS, MAX_FEE_NUMERATOR, MAX_RATE_LIMITER_SLOT_DURATION,
+            MAX_RATE_LIMITER_TIMESTAMP_DURATION, MIN_FEE_NUMERATOR,
+        },
+        BASIS_POINT_MAX, MAX_CURVE_POINT_CONFIG, MAX_SQRT_PRICE, MAX_SWALLOW_PERCENTAGE,
+        SWAP_BUFFER_PERCENTAGE,
     },
     fee_math::get_fee_in_period,
     params::{
         fee_parameters::PoolFeeParameters,
         liquidity_distribution::{get_base_token_for_swap, LiquidityDistributionParameters},
+        swap::TradeDirection,
     },
     safe_math::SafeMath,
+    token::TokenProgramFlags,
     u128x128_math::Rounding,
     utils_math::{safe_mul_div_cast_u128, safe_mul_div_cast_u64},
     LockedVestingParams, MigrationFee, PoolError,
 };
 
-use super::fee::{FeeOnAmountResult, VolatilityTracker};
+use super::{
+    fee::{FeeOnAmountResult, VolatilityTracker},
+    CollectFeeMode,
+};
 
 /// collect fee mode
 #[repr(u8)]
@@ -39,14 +49,21 @@ pub enum FeeSchedulerMode {
     Linear,
     // fee = cliff_fee_numerator * (1-reduction_factor/10_000)^passed_period
     Exponential,
+    // buys in the first `period_frequency` after activation pay cliff_fee_numerator on the first
+    // `reduction_factor` quote amount, and `number_of_period` bps more on each next one, up to
+    // MAX_FEE_NUMERATOR. Other trades pay cliff_fee_numerator
+    RateLimiter,
 }
 
 #[zero_copy]
 #[derive(Debug, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PoolFeesConfig {
     pub base_fee: BaseFeeConfig,
     pub dynamic_fee: DynamicFeeConfig,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub padding_0: [u64; 5],
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub padding_1: [u8; 6],
     pub protocol_fee_percent: u8,
     pub referral_fee_percent: u8,
@@ -66,10 +83,15 @@ impl PoolFeesConfig {
         volatility_tracker: &VolatilityTracker,
         current_point: u64,
         activation_point: u64,
+        amount: u64,
+        trade_direction: TradeDirection,
     ) -> Result<u64> {
-        let base_fee_numerator = self
-            .base_fee
-            .get_base_fee_numerator(current_point, activation_point)?;
+        let base_fee_numerator = self.base_fee.get_base_fee_numerator_on_amount(
+            current_point,
+            activation_point,
+            amount,
+            trade_direction,
+        )?;
 
         let total_fee_numerator = self
             .dynamic_fee
@@ -93,15 +115,107 @@ impl PoolFeesConfig {
         has_referral: bool,
         current_point: u64,
         activation_point: u64,
+        trade_direction: TradeDirection,
     ) -> Result<FeeOnAmountResult> {
-        let trade_fee_numerator =
-            self.get_total_trading_fee(volatility_tracker, current_point, activation_point)?;
-
-        let trading_fee: u64 =
-            safe_mul_div_cast_u64(amount, trade_fee_numerator, FEE_DENOMINATOR, Rounding::Up)?;
+        let trading_fee = self.get_trading_fee_on_amount(
+            volatility_tracker,
+            amount,
+            current_point,
+            activation_point,
+            trade_direction,
+        )?;
         // update amount
         let amount = amount.safe_sub(trading_fee)?;
 
+        self.split_fees(amount, trading_fee, has_referral)
+    }
+
+    fn get_trading_fee_on_amount(
+        &self,
+        volatility_tracker: &VolatilityTracker,
+        amount: u64,
+        current_point: u64,
+        activation_point: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<u64> {
+        let trade_fee_numerator = self.get_total_trading_fee(
+            volatility_tracker,
+            current_point,
+            activation_point,
+            amount,
+            trade_direction,
+        )?;
+        safe_mul_div_cast_u64(amount, trade_fee_numerator, FEE_DENOMINATOR, Rounding::Up)
+    }
+
+    /// Inverse of `get_fee_on_amount`, used for exact output swap.
+    /// Given the amount that must remain after fee, returns the fee-inclusive amount in `amount`
+    /// and the fee split that is charged on top of `excluded_fee_amount`.
+    pub fn get_fee_on_excluded_amount(
+        &self,
+        volatility_tracker: &VolatilityTracker,
+        excluded_fee_amount: u64,
+        has_referral: bool,
+        current_point: u64,
+        activation_point: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<FeeOnAmountResult> {
+        if self.base_fee.is_rate_limiter_applied(
+            current_point,
+            activation_point,
+            trade_direction,
+        )? {
+            // the fee numerator depends on the included amount, search the smallest included
+            // amount that leaves `excluded_fee_amount` after fee. The fee is at most
+            // MAX_FEE_NUMERATOR (50%), so it is at most twice the excluded amount
+            let mut low = excluded_fee_amount;
+            let mut high = excluded_fee_amount.safe_mul(2)?.safe_add(1)?;
+            while low < high {
+                let mid = low + (high - low) / 2;
+                let trading_fee = self.get_trading_fee_on_amount(
+                    volatility_tracker,
+                    mid,
+                    current_point,
+                    activation_point,
+                    trade_direction,
+                )?;
+                if mid.safe_sub(trading_fee)? >= excluded_fee_amount {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            let trading_fee = low.safe_sub(excluded_fee_amount)?;
+            return self.split_fees(low, trading_fee, has_referral);
+        }
+
+        let trade_fee_numerator = self.get_total_trading_fee(
+            volatility_tracker,
+            current_point,
+            activation_point,
+            excluded_fee_amount,
+            trade_direction,
+        )?;
+
+        // included_fee_amount = excluded_fee_amount * FEE_DENOMINATOR / (FEE_DENOMINATOR - trade_fee_numerator)
+        // round up, so fee on included_fee_amount is never less than what get_fee_on_amount would charge
+        let included_fee_amount: u64 = safe_mul_div_cast_u64(
+            excluded_fee_amount,
+            FEE_DENOMINATOR,
+            FEE_DENOMINATOR.safe_sub(trade_fee_numerator)?,
+            Rounding::Up,
+        )?;
+        let trading_fee = included_fee_amount.safe_sub(excluded_fee_amount)?;
+
+        self.split_fees(included_fee_amount, trading_fee, has_referral)
+    }
+
+    fn split_fees(
+        &self,
+        amount: u64,
+        trading_fee: u64,
+        has_referral: bool,
+    ) -> Result<FeeOnAmountResult> {
         let protocol_fee = safe_mul_div_cast_u64(
             trading_fee,
             self.protocol_fee_percent.into(),
@@ -136,12 +250,14 @@ impl PoolFeesConfig {
 
 #[zero_copy]
 #[derive(Debug, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BaseFeeConfig {
     pub cliff_fee_numerator: u64,
     pub period_frequency: u64,
     pub reduction_factor: u64,
     pub number_of_period: u16,
     pub fee_scheduler_mode: u8,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub padding_0: [u8; 5],
 }
 
@@ -156,6 +272,51 @@ impl BaseFeeConfig {
         self.get_base_fee_numerator_by_period(self.number_of_period.into())
     }
 
+    /// Rate limiter view of the base fee, None for the other fee scheduler modes
+    pub fn get_fee_rate_limiter(&self) -> Result<Option<FeeRateLimiter>> {
+        let fee_scheduler_mode = FeeSchedulerMode::try_from(self.fee_scheduler_mode)
+            .map_err(|_| PoolError::TypeCastFailed)?;
+        if fee_scheduler_mode != FeeSchedulerMode::RateLimiter {
+            return Ok(None);
+        }
+        Ok(Some(FeeRateLimiter {
+            cliff_fee_numerator: self.cliff_fee_numerator,
+            fee_increment_bps: self.number_of_period,
+            max_limiter_duration: self.period_frequency,
+            reference_amount: self.reduction_factor,
+        }))
+    }
+
+    pub fn is_rate_limiter_applied(
+        &self,
+        current_point: u64,
+        activation_point: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<bool> {
+        match self.get_fee_rate_limiter()? {
+            Some(rate_limiter) => {
+                rate_limiter.is_applied(current_point, activation_point, trade_direction)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Base fee numerator of a trade of `amount`, only the rate limiter depends on the amount
+    pub fn get_base_fee_numerator_on_amount(
+        &self,
+        current_point: u64,
+        activation_point: u64,
+        amount: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<u64> {
+        if let Some(rate_limiter) = self.get_fee_rate_limiter()? {
+            if rate_limiter.is_applied(current_point, activation_point, trade_direction)? {
+                return rate_limiter.get_fee_numerator_on_amount(amount);
+            }
+        }
+        self.get_base_fee_numerator(current_point, activation_point)
+    }
+
     pub fn get_base_fee_numerator(&self, current_point: u64, activation_point: u64) -> Result<u64> {
         if self.period_frequency == 0 {
             return Ok(self.cliff_fee_numerator);
@@ -187,14 +348,125 @@ impl BaseFeeConfig {
                     get_fee_in_period(self.cliff_fee_numerator, self.reduction_factor, period)?;
                 Ok(fee_numerator)
             }
+            // the rate limiter depends on the trade, not on the period
+            FeeSchedulerMode::RateLimiter => Ok(self.cliff_fee_numerator),
+        }
+    }
+}
+
+/// Base fee of the rate limiter mode, stored in the fee scheduler fields of `BaseFeeConfig`.
+/// While it applies, a transaction can only contain one swap on the pool, so a buy can't be split
+/// into smaller swaps in the same transaction. Use `max_buy_quote_per_wallet` to cap buys split
+/// across transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeRateLimiter {
+    /// fee of the first `reference_amount`
+    pub cliff_fee_numerator: u64,
+    /// fee increase of each next `reference_amount`, stored in `number_of_period`
+    pub fee_increment_bps: u16,
+    /// the rate limiter applies before activation point + max_limiter_duration, stored in
+    /// `period_frequency`
+    pub max_limiter_duration: u64,
+    /// quote amount, stored in `reduction_factor`
+    pub reference_amount: u64,
+}
+
+impl FeeRateLimiter {
+    /// The rate limiter reads the quote input of buys, so fees must be collected in quote token
+    pub fn validate(&self, collect_fee_mode: u8, activation_type: u8) -> Result<()> {
+        require!(
+            collect_fee_mode == u8::from(CollectFeeMode::QuoteToken),
+            PoolError::InvalidFeeRateLimiter
+        );
+        let max_limiter_duration = match ActivationType::try_from(activation_type)
+            .map_err(|_| PoolError::InvalidActivationType)?
+        {
+            ActivationType::Slot => MAX_RATE_LIMITER_SLOT_DURATION,
+            ActivationType::Timestamp => MAX_RATE_LIMITER_TIMESTAMP_DURATION,
+        };
+        require!(
+            self.max_limiter_duration > 0
+                && self.max_limiter_duration <= max_limiter_duration
+                && self.reference_amount > 0
+                && self.fee_increment_bps > 0
+                && u64::from(self.fee_increment_bps) <= MAX_FEE_BPS,
+            PoolError::InvalidFeeRateLimiter
+        );
+        require!(
+            self.cliff_fee_numerator >= MIN_FEE_NUMERATOR
+                && self.cliff_fee_numerator <= MAX_FEE_NUMERATOR,
+            PoolError::ExceedMaxFeeBps
+        );
+        Ok(())
+    }
+
+    pub fn is_applied(
+        &self,
+        current_point: u64,
+        activation_point: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<bool> {
+        let last_effective_point = activation_point.safe_add(self.max_limiter_duration)?;
+        Ok(trade_direction == TradeDirection::QuoteToBase && current_point < last_effective_point)
+    }
+
+    /// Fee numerator over the whole `amount`, rounded up
+    pub fn get_fee_numerator_on_amount(&self, amount: u64) -> Result<u64> {
+        if amount <= self.reference_amount {
+            return Ok(self.cliff_fee_numerator);
         }
+        let cliff_fee_numerator = u128::from(self.cliff_fee_numerator);
+        let fee_increment_numerator = u128::from(self.fee_increment_bps)
+            .safe_mul(FEE_DENOMINATOR.into())?
+            .safe_div(BASIS_POINT_MAX.into())?;
+        let reference_amount = u128::from(self.reference_amount);
+        let max_fee_numerator = u128::from(MAX_FEE_NUMERATOR);
+
+        // the amount over the first reference amount, split in full reference amounts and a rest
+        let excess_amount = u128::from(amount).safe_sub(reference_amount)?;
+        let full_count = excess_amount.safe_div(reference_amount)?;
+        let rest_amount = excess_amount.safe_rem(reference_amount)?;
+        // the reference amount at this index reaches the max fee
+        let max_index = max_fee_numerator
+            .safe_sub(cliff_fee_numerator)?
+            .safe_div(fee_increment_numerator)?;
+
+        // sum of the fee numerators of the full reference amounts, index 0 to n
+        let get_sum_fee_numerator = |n: u128| -> Result<u128> {
+            Ok(cliff_fee_numerator.safe_mul(n.safe_add(1)?)?.safe_add(
+                fee_increment_numerator
+                    .safe_mul(n)?
+                    .safe_mul(n.safe_add(1)?)?
+                    .safe_div(2)?,
+            )?)
+        };
+        let total_fee = if full_count < max_index {
+            let rest_fee_numerator = cliff_fee_numerator
+                .safe_add(fee_increment_numerator.safe_mul(full_count.safe_add(1)?)?)?;
+            reference_amount
+                .safe_mul(get_sum_fee_numerator(full_count)?)?
+                .safe_add(rest_amount.safe_mul(rest_fee_numerator)?)?
+        } else {
+            let capped_amount = excess_amount.safe_sub(max_index.safe_mul(reference_amount)?)?;
+            reference_amount
+                .safe_mul(get_sum_fee_numerator(max_index)?)?
+                .safe_add(capped_amount.safe_mul(max_fee_numerator)?)?
+        };
+
+        let fee_numerator = total_fee
+            .safe_add(u128::from(amount).safe_sub(1)?)?
+            .safe_div(amount.into())?;
+        Ok(u64::try_from(fee_numerator.min(max_fee_numerator))
+            .map_err(|_| PoolError::MathOverflow)?)
     }
 }
 
 #[zero_copy]
 #[derive(Debug, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DynamicFeeConfig {
     pub initialized: u8, // 0, ignore for dynamic fee
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub padding: [u8; 7],
     pub max_volatility_accumulator: u32,
     pub variable_fee_control: u32,
@@ -202,7 +474,12 @@ pub struct DynamicFeeConfig {
     pub filter_period: u16,
     pub decay_period: u16,
     pub reduction_factor: u16,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub padding2: [u8; 8], // Add padding for u128 alignment
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub bin_step_u128: u128,
 }
 
@@ -240,12 +517,14 @@ impl DynamicFeeConfig {
 
 #[zero_copy]
 #[derive(Debug, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LockedVestingConfig {
     pub amount_per_period: u64,
     pub cliff_duration_from_migration_time: u64,
     pub frequency: u64,
     pub number_of_period: u64,
     pub cliff_unlock_amount: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub _padding: u64,
 }
 
@@ -297,6 +576,26 @@ pub enum MigrationOption {
     DammV2,
 }
 
+#[repr(u8)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    IntoPrimitive,
+    TryFromPrimitive,
+    AnchorDeserialize,
+    AnchorSerialize,
+    Default,
+)]
+pub enum MigrationFillMode {
+    /// Final buy can go over migration quote threshold, excess quote becomes surplus
+    #[default]
+    Swallow,
+    /// Final buy is clipped at migration quote threshold, unused input stays with buyer
+    ExactFill,
+}
+
 #[repr(u8)]
 #[derive(
     Clone,
@@ -361,12 +660,16 @@ impl MigrationFeeOption {
 
 #[account(zero_copy)]
 #[derive(InitSpace, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PoolConfig {
     /// quote mint
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub quote_mint: Pubkey,
     /// Address to get the fee
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub fee_claimer: Pubkey,
     /// Address to receive extra base token after migration, in case token is fixed supply
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub leftover_receiver: Pubkey,
     /// Pool fee
     pub pool_fees: PoolFeesConfig,
@@ -404,8 +707,11 @@ pub struct PoolConfig {
     pub migration_fee_percentage: u8,
     /// creator migration fee percentage
     pub creator_migration_fee_percentage: u8,
+    /// migration fill mode
+    pub migration_fill_mode: u8,
     /// padding 1
-    pub _padding_1: [u8; 7],
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding_1: [u8; 6],
     /// swap base amount
     pub swap_base_amount: u64,
     /// migration quote threshold (in quote token)
@@ -413,6 +719,10 @@ pub struct PoolConfig {
     /// migration base threshold (in base token)
     pub migration_base_threshold: u64,
     /// migration sqrt price
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub migration_sqrt_price: u128,
     /// locked vesting config
     pub locked_vesting_config: LockedVestingConfig,
@@ -420,9 +730,20 @@ pub struct PoolConfig {
     pub pre_migration_token_supply: u64,
     /// post migration token supply
     pub post_migration_token_supply: u64,
-    /// padding 2
-    pub _padding_2: [u128; 2],
+    /// max delay between pool creation and activation, in slot or second, 0 means the pool
+    /// activates at creation
+    pub max_activation_delay: u64,
+    /// buy limits apply before activation point + buy_limit_duration, in slot or second
+    pub buy_limit_duration: u64,
+    /// max quote amount of a buy while the buy limits apply, 0 means no limit
+    pub max_buy_quote_per_swap: u64,
+    /// max quote amount a wallet can spend while the buy limits apply, 0 means no limit
+    pub max_buy_quote_per_wallet: u64,
     /// minimum price
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_start_price: u128,
     /// curve, only use 20 point firstly, we can extend that latter
     // each distribution will include curve[i].sqrt_price + curve[i+1].sqrt_price + curve[i+1].liquidity
@@ -434,8 +755,17 @@ const_assert_eq!(PoolConfig::INIT_SPACE, 1040);
 
 #[zero_copy]
 #[derive(InitSpace, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LiquidityDistributionConfig {
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_price: u128,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub liquidity: u128,
 }
 
@@ -458,6 +788,11 @@ impl PoolConfig {
         creator_trading_fee_percentage: u8,
         token_update_authority: u8,
         migration_fee: MigrationFee,
+        migration_fill_mode: u8,
+        max_activation_delay: u64,
+        buy_limit_duration: u64,
+        max_buy_quote_per_swap: u64,
+        max_buy_quote_per_wallet: u64,
         collect_fee_mode: u8,
         migration_option: u8,
         activation_type: u8,
@@ -489,6 +824,11 @@ impl PoolConfig {
         self.token_update_authority = token_update_authority;
         self.migration_fee_percentage = migration_fee.fee_percentage;
         self.creator_migration_fee_percentage = migration_fee.creator_fee_percentage;
+        self.migration_fill_mode = migration_fill_mode;
+        self.max_activation_delay = max_activation_delay;
+        self.buy_limit_duration = buy_limit_duration;
+        self.max_buy_quote_per_swap = max_buy_quote_per_swap;
+        self.max_buy_quote_per_wallet = max_buy_quote_per_wallet;
         self.collect_fee_mode = collect_fee_mode;
         self.migration_option = migration_option;
         self.activation_type = activation_type;
@@ -525,6 +865,48 @@ impl PoolConfig {
         Ok(token_update_authority)
     }
 
+    pub fn get_migration_fill_mode(&self) -> Result<MigrationFillMode> {
+        let migration_fill_mode = MigrationFillMode::try_from(self.migration_fill_mode)
+            .map_err(|_| PoolError::InvalidMigrationFillMode)?;
+        Ok(migration_fill_mode)
+    }
+
+    pub fn get_collect_fee_mode(&self) -> Result<CollectFeeMode> {
+        let collect_fee_mode = CollectFeeMode::try_from(self.collect_fee_mode)
+            .map_err(|_| PoolError::InvalidCollectFeeMode)?;
+        Ok(collect_fee_mode)
+    }
+
+    pub fn get_migration_option(&self) -> Result<MigrationOption> {
+        let migration_option = MigrationOption::try_from(self.migration_option)
+            .map_err(|_| PoolError::InvalidMigrationOption)?;
+        Ok(migration_option)
+    }
+
+    pub fn get_activation_type(&self) -> Result<ActivationType> {
+        let activation_type = ActivationType::try_from(self.activation_type)
+            .map_err(|_| PoolError::InvalidActivationType)?;
+        Ok(activation_type)
+    }
+
+    pub fn get_token_type(&self) -> Result<TokenType> {
+        let token_type =
+            TokenType::try_from(self.token_type).map_err(|_| PoolError::InvalidTokenType)?;
+        Ok(token_type)
+    }
+
+    pub fn get_quote_token_flag(&self) -> Result<TokenProgramFlags> {
+        let quote_token_flag = TokenProgramFlags::try_from(self.quote_token_flag)
+            .map_err(|_| PoolError::TypeCastFailed)?;
+        Ok(quote_token_flag)
+    }
+
+    pub fn get_migration_fee_option(&self) -> Result<MigrationFeeOption> {
+        let migration_fee_option = MigrationFeeOption::try_from(self.migration_fee_option)
+            .map_err(|_| PoolError::InvalidMigrationFeeOption)?;
+        Ok(migration_fee_option)
+    }
+
     pub fn get_migration_quote_amount_for_config(&self) -> Result<MigrationAmount> {
         PoolConfig::get_migration_quote_amount(
             self.migration_quote_threshold,
@@ -637,6 +1019,40 @@ impl PoolConfig {
         self.fixed_token_supply_flag == 1
     }
 
+    pub fn is_buy_limit_applied(
+        &self,
+        current_point: u64,
+        activation_point: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<bool> {
+        let last_effective_point = activation_point.safe_add(self.buy_limit_duration)?;
+        Ok(trade_direction == TradeDirection::QuoteToBase && current_point < last_effective_point)
+    }
+
+    /// Checks a trade consuming `amount_in` against the buy limits. `is_wallet_tracked` is false
+    /// when the quote spent by the wallet isn't tracked, such buys fail under a per wallet limit
+    pub fn validate_buy_limit(
+        &self,
+        amount_in: u64,
+        current_point: u64,
+        activation_point: u64,
+        trade_direction: TradeDirection,
+        is_wallet_tracked: bool,
+    ) -> Result<()> {
+        if !self.is_buy_limit_applied(current_point, activation_point, trade_direction)? {
+            return Ok(());
+        }
+        require!(
+            self.max_buy_quote_per_swap == 0 || amount_in <= self.max_buy_quote_per_swap,
+            PoolError::ExceededBuyLimit
+        );
+        require!(
+            self.max_buy_quote_per_wallet == 0 || is_wallet_tracked,
+            PoolError::BuyTrackerRequired
+        );
+        Ok(())
+    }
+
     pub fn get_lp_distribution(&self, lp_amount: u64) -> Result<LiquidityDistributionU64> {
         let partner_locked_lp = safe_mul_div_cast_u64(
             lp_amount,
@@ -23,11 +23,25 @@ pub struct FeeOnAmountResult {
 
 #[zero_copy]
 #[derive(Debug, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VolatilityTracker {
     pub last_update_timestamp: u64,
-    pub padding: [u8; 8],           // Add padding for u128 alignment
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub padding: [u8; 8], // Add padding for u128 alignment
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_price_reference: u128, // reference sqrt price
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub volatility_accumulator: u128,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub volatility_reference: u128, // decayed volatility accumulator
 }
 
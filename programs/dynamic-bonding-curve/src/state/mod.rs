@@ -1,3 +1,7 @@
+pub mod allowlist_entry;
+pub use allowlist_entry::*;
+pub mod buy_tracker;
+pub use buy_tracker::*;
 pub mod config;
 pub use config::*;
 pub mod virtual_pool;
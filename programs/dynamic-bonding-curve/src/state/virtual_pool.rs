@@ -10,13 +10,13 @@ use crate::{
     curve::{
         get_delta_amount_base_unsigned, get_delta_amount_base_unsigned_256,
         get_delta_amount_quote_unsigned, get_delta_amount_quote_unsigned_256,
-        get_next_sqrt_price_from_input,
+        get_next_sqrt_price_from_input, get_next_sqrt_price_from_output,
     },
-    params::swap::TradeDirection,
+    params::{allowlist::AllowlistParameters, swap::TradeDirection},
     safe_math::SafeMath,
     state::{
         fee::{FeeMode, FeeOnAmountResult, VolatilityTracker},
-        PoolConfig,
+        MigrationFillMode, PoolConfig,
     },
     u128x128_math::Rounding,
     utils_math::safe_mul_div_cast_u64,
@@ -86,18 +86,24 @@ pub enum MigrationProgress {
 
 #[account(zero_copy)]
 #[derive(InitSpace, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VirtualPool {
     /// volatility tracker
     pub volatility_tracker: VolatilityTracker,
     /// config key
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub config: Pubkey,
     /// creator
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub creator: Pubkey,
     /// base mint
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub base_mint: Pubkey,
     /// base vault
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub base_vault: Pubkey,
     /// quote vault
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_helpers::pubkey"))]
     pub quote_vault: Pubkey,
     /// base reserve
     pub base_reserve: u64,
@@ -112,6 +118,10 @@ pub struct VirtualPool {
     /// trading quote fee
     pub partner_quote_fee: u64,
     /// current price
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub sqrt_price: u128,
     /// Activation point
     pub activation_point: u64,
@@ -139,8 +149,15 @@ pub struct VirtualPool {
     pub creator_base_fee: u64,
     /// creator quote fee
     pub creator_quote_fee: u64,
+    /// allowlist merkle root, zero if the pool has no allowlist phase
+    pub allowlist_merkle_root: [u8; 32],
+    /// only allowlisted wallets can buy before this point
+    pub allowlist_end_point: u64,
+    /// max quote amount a wallet can spend in the allowlist phase
+    pub allowlist_quote_cap_per_wallet: u64,
     /// Padding for further use
-    pub _padding_1: [u64; 7],
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _padding_1: [u64; 1],
 }
 
 const_assert_eq!(VirtualPool::INIT_SPACE, 416);
@@ -150,6 +167,7 @@ pub const CREATOR_MASK: u8 = 0b010;
 
 #[zero_copy]
 #[derive(Debug, InitSpace, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PoolMetrics {
     pub total_protocol_base_fee: u64,
     pub total_protocol_quote_fee: u64,
@@ -204,6 +222,16 @@ impl VirtualPool {
         self.base_reserve = base_reserve;
     }
 
+    pub fn initialize_allowlist(&mut self, allowlist: &AllowlistParameters) {
+        self.allowlist_merkle_root = allowlist.merkle_root;
+        self.allowlist_end_point = allowlist.end_point;
+        self.allowlist_quote_cap_per_wallet = allowlist.quote_cap_per_wallet;
+    }
+
+    pub fn is_allowlist_phase(&self, current_point: u64) -> bool {
+        self.allowlist_merkle_root != [0; 32] && current_point < self.allowlist_end_point
+    }
+
     pub fn get_swap_result(
         &self,
         config: &PoolConfig,
@@ -211,7 +239,40 @@ impl VirtualPool {
         fee_mode: &FeeMode,
         trade_direction: TradeDirection,
         current_point: u64,
-    ) -> Result<SwapResult> {
+    ) -> Result<SwapResult2> {
+        self.get_swap_result_with_sqrt_price_limit(
+            config,
+            amount_in,
+            fee_mode,
+            trade_direction,
+            current_point,
+            None,
+        )
+    }
+
+    /// Swap stops when the price reaches `sqrt_price_limit`, and only the input needed to reach it is consumed
+    pub fn get_swap_result_with_sqrt_price_limit(
+        &self,
+        config: &PoolConfig,
+        amount_in: u64,
+        fee_mode: &FeeMode,
+        trade_direction: TradeDirection,
+        current_point: u64,
+        sqrt_price_limit: Option<u128>,
+    ) -> Result<SwapResult2> {
+        if let Some(sqrt_price_limit) = sqrt_price_limit {
+            match trade_direction {
+                TradeDirection::BaseToQuote => require!(
+                    sqrt_price_limit < self.sqrt_price,
+                    PoolError::InvalidSqrtPriceLimit
+                ),
+                TradeDirection::QuoteToBase => require!(
+                    sqrt_price_limit > self.sqrt_price,
+                    PoolError::InvalidSqrtPriceLimit
+                ),
+            }
+        }
+
         let mut actual_protocol_fee = 0;
         let mut actual_trading_fee = 0;
         let mut actual_referral_fee = 0;
@@ -228,6 +289,7 @@ impl VirtualPool {
                 fee_mode.has_referral,
                 current_point,
                 self.activation_point,
+                trade_direction,
             )?;
 
             actual_protocol_fee = protocol_fee;
@@ -239,18 +301,67 @@ impl VirtualPool {
             amount_in
         };
 
+        // clip the final buy, so pool lands exactly on migration quote threshold
+        let curve_amount_in = if trade_direction == TradeDirection::QuoteToBase
+            && config.get_migration_fill_mode()? == MigrationFillMode::ExactFill
+        {
+            let max_amount_in = config
+                .migration_quote_threshold
+                .safe_sub(self.quote_reserve)?;
+            actual_amount_in.min(max_amount_in)
+        } else {
+            actual_amount_in
+        };
+
         let SwapAmount {
             output_amount,
             next_sqrt_price,
+            amount_left,
         } = match trade_direction {
             TradeDirection::BaseToQuote => {
-                self.get_swap_amount_from_base_to_quote(config, actual_amount_in)
+                self.get_swap_amount_from_base_to_quote(config, curve_amount_in, sqrt_price_limit)
             }
             TradeDirection::QuoteToBase => {
-                self.get_swap_amount_from_quote_to_base(config, actual_amount_in)
+                self.get_swap_amount_from_quote_to_base(config, curve_amount_in, sqrt_price_limit)
             }
         }?;
 
+        // part of input is refunded, so fee is only charged on the consumed amount
+        let consumed_amount_in = curve_amount_in.safe_sub(amount_left)?;
+        let actual_amount_in = if consumed_amount_in < actual_amount_in {
+            if fee_mode.fees_on_input {
+                let FeeOnAmountResult {
+                    protocol_fee,
+                    trading_fee,
+                    referral_fee,
+                    ..
+                } = config.pool_fees.get_fee_on_excluded_amount(
+                    &self.volatility_tracker,
+                    consumed_amount_in,
+                    fee_mode.has_referral,
+                    current_point,
+                    self.activation_point,
+                    trade_direction,
+                )?;
+
+                actual_protocol_fee = protocol_fee;
+                actual_trading_fee = trading_fee;
+                actual_referral_fee = referral_fee;
+            }
+            consumed_amount_in
+        } else {
+            actual_amount_in
+        };
+
+        let consumed_input_amount = if fee_mode.fees_on_input {
+            actual_amount_in
+                .safe_add(actual_trading_fee)?
+                .safe_add(actual_protocol_fee)?
+                .safe_add(actual_referral_fee)?
+        } else {
+            actual_amount_in
+        };
+
         let actual_amount_out = if fee_mode.fees_on_input {
             output_amount
         } else {
@@ -265,6 +376,7 @@ impl VirtualPool {
                 fee_mode.has_referral,
                 current_point,
                 self.activation_point,
+                trade_direction,
             )?;
 
             actual_protocol_fee = protocol_fee;
@@ -274,13 +386,15 @@ impl VirtualPool {
             amount
         };
 
-        Ok(SwapResult {
+        Ok(SwapResult2 {
             actual_input_amount: actual_amount_in,
             output_amount: actual_amount_out,
             next_sqrt_price,
             trading_fee: actual_trading_fee,
             protocol_fee: actual_protocol_fee,
             referral_fee: actual_referral_fee,
+            consumed_input_amount,
+            refunded_input_amount: amount_in.safe_sub(consumed_input_amount)?,
         })
     }
 
@@ -288,19 +402,25 @@ impl VirtualPool {
         &self,
         config: &PoolConfig,
         amount_in: u64,
+        sqrt_price_limit: Option<u128>,
     ) -> Result<SwapAmount> {
+        let lower_sqrt_price_limit = sqrt_price_limit.unwrap_or(0);
         // finding new target price
         let mut total_output_amount = 0u64;
         let mut current_sqrt_price = self.sqrt_price;
         let mut amount_left = amount_in;
         // Use curve.len() for backward compatibility for existing pools with 20 points
         for i in (0..config.curve.len() - 1).rev() {
+            if current_sqrt_price <= lower_sqrt_price_limit {
+                break;
+            }
             if config.curve[i].sqrt_price == 0 || config.curve[i].liquidity == 0 {
                 continue;
             }
             if config.curve[i].sqrt_price < current_sqrt_price {
+                let target_sqrt_price = config.curve[i].sqrt_price.max(lower_sqrt_price_limit);
                 let max_amount_in = get_delta_amount_base_unsigned_256(
-                    config.curve[i].sqrt_price,
+                    target_sqrt_price,
                     current_sqrt_price,
                     config.curve[i + 1].liquidity,
                     Rounding::Up, // TODO check whether we should use round down or round up
@@ -324,7 +444,7 @@ impl VirtualPool {
                     amount_left = 0;
                     break;
                 } else {
-                    let next_sqrt_price = config.curve[i].sqrt_price;
+                    let next_sqrt_price = target_sqrt_price;
                     let output_amount = get_delta_amount_quote_unsigned(
                         next_sqrt_price,
                         current_sqrt_price,
@@ -341,27 +461,56 @@ impl VirtualPool {
                 }
             }
         }
-        if amount_left != 0 {
-            let next_sqrt_price = get_next_sqrt_price_from_input(
-                current_sqrt_price,
-                config.curve[0].liquidity,
-                amount_left,
-                true,
-            )?;
+        if amount_left != 0 && current_sqrt_price > lower_sqrt_price_limit {
+            let max_amount_in = if lower_sqrt_price_limit > 0 {
+                get_delta_amount_base_unsigned_256(
+                    lower_sqrt_price_limit,
+                    current_sqrt_price,
+                    config.curve[0].liquidity,
+                    Rounding::Up,
+                )?
+            } else {
+                U256::MAX
+            };
+
+            if U256::from(amount_left) < max_amount_in {
+                let next_sqrt_price = get_next_sqrt_price_from_input(
+                    current_sqrt_price,
+                    config.curve[0].liquidity,
+                    amount_left,
+                    true,
+                )?;
 
-            let output_amount = get_delta_amount_quote_unsigned(
-                next_sqrt_price,
-                current_sqrt_price,
-                config.curve[0].liquidity,
-                Rounding::Down,
-            )?;
-            total_output_amount = total_output_amount.safe_add(output_amount)?;
-            current_sqrt_price = next_sqrt_price;
+                let output_amount = get_delta_amount_quote_unsigned(
+                    next_sqrt_price,
+                    current_sqrt_price,
+                    config.curve[0].liquidity,
+                    Rounding::Down,
+                )?;
+                total_output_amount = total_output_amount.safe_add(output_amount)?;
+                current_sqrt_price = next_sqrt_price;
+                amount_left = 0;
+            } else {
+                let output_amount = get_delta_amount_quote_unsigned(
+                    lower_sqrt_price_limit,
+                    current_sqrt_price,
+                    config.curve[0].liquidity,
+                    Rounding::Down,
+                )?;
+                total_output_amount = total_output_amount.safe_add(output_amount)?;
+                current_sqrt_price = lower_sqrt_price_limit;
+                amount_left = amount_left.safe_sub(
+                    max_amount_in
+                        .try_into()
+                        .map_err(|_| PoolError::TypeCastFailed)?,
+                )?;
+            }
         }
 
         Ok(SwapAmount {
             output_amount: total_output_amount,
             next_sqrt_price: current_sqrt_price,
+            amount_left,
         })
     }
 
@@ -369,20 +518,26 @@ impl VirtualPool {
         &self,
         config: &PoolConfig,
         amount_in: u64,
+        sqrt_price_limit: Option<u128>,
     ) -> Result<SwapAmount> {
+        let upper_sqrt_price_limit = sqrt_price_limit.unwrap_or(u128::MAX);
         // finding new target price
         let mut total_output_amount = 0u64;
         let mut current_sqrt_price = self.sqrt_price;
         let mut amount_left = amount_in;
         // Use curve.len() for backward compatibility for existing pools with 20 points
         for i in 0..config.curve.len() {
+            if current_sqrt_price >= upper_sqrt_price_limit {
+                break;
+            }
             if config.curve[i].sqrt_price == 0 || config.curve[i].liquidity == 0 {
                 break;
             }
             if config.curve[i].sqrt_price > current_sqrt_price {
+                let target_sqrt_price = config.curve[i].sqrt_price.min(upper_sqrt_price_limit);
                 let max_amount_in = get_delta_amount_quote_unsigned_256(
                     current_sqrt_price,
-                    config.curve[i].sqrt_price,
+                    target_sqrt_price,
                     config.curve[i].liquidity,
                     Rounding::Up, // TODO check whether we should use round down or round up
                 )?;
@@ -405,7 +560,7 @@ impl VirtualPool {
                     amount_left = 0;
                     break;
                 } else {
-                    let next_sqrt_price = config.curve[i].sqrt_price;
+                    let next_sqrt_price = target_sqrt_price;
                     let output_amount = get_delta_amount_base_unsigned(
                         current_sqrt_price,
                         next_sqrt_price,
@@ -423,6 +578,15 @@ impl VirtualPool {
             }
         }
 
+        if current_sqrt_price >= upper_sqrt_price_limit {
+            // price limit is reached, the rest will be refunded
+            return Ok(SwapAmount {
+                output_amount: total_output_amount,
+                next_sqrt_price: current_sqrt_price,
+                amount_left,
+            });
+        }
+
         // allow pool swallow an extra amount
         require!(
             amount_left <= config.get_max_swallow_quote_amount()?,
@@ -432,24 +596,287 @@ impl VirtualPool {
         Ok(SwapAmount {
             output_amount: total_output_amount,
             next_sqrt_price: current_sqrt_price,
+            amount_left: 0,
+        })
+    }
+
+    pub fn get_swap_result_from_exact_output(
+        &self,
+        config: &PoolConfig,
+        amount_out: u64,
+        fee_mode: &FeeMode,
+        trade_direction: TradeDirection,
+        current_point: u64,
+    ) -> Result<SwapResult2> {
+        let mut actual_protocol_fee = 0;
+        let mut actual_trading_fee = 0;
+        let mut actual_referral_fee = 0;
+
+        // the curve must release the fee-inclusive amount when fees are collected on output
+        let included_fee_amount_out = if fee_mode.fees_on_input {
+            amount_out
+        } else {
+            let FeeOnAmountResult {
+                amount,
+                protocol_fee,
+                trading_fee,
+                referral_fee,
+            } = config.pool_fees.get_fee_on_excluded_amount(
+                &self.volatility_tracker,
+                amount_out,
+                fee_mode.has_referral,
+                current_point,
+                self.activation_point,
+                trade_direction,
+            )?;
+
+            actual_protocol_fee = protocol_fee;
+            actual_trading_fee = trading_fee;
+            actual_referral_fee = referral_fee;
+
+            amount
+        };
+
+        let SwapAmountIn {
+            input_amount,
+            next_sqrt_price,
+        } = match trade_direction {
+            TradeDirection::BaseToQuote => {
+                self.get_swap_amount_in_from_base_to_quote(config, included_fee_amount_out)
+            }
+            TradeDirection::QuoteToBase => {
+                self.get_swap_amount_in_from_quote_to_base(config, included_fee_amount_out)
+            }
+        }?;
+
+        if fee_mode.fees_on_input {
+            let FeeOnAmountResult {
+                protocol_fee,
+                trading_fee,
+                referral_fee,
+                ..
+            } = config.pool_fees.get_fee_on_excluded_amount(
+                &self.volatility_tracker,
+                input_amount,
+                fee_mode.has_referral,
+                current_point,
+                self.activation_point,
+                trade_direction,
+            )?;
+
+            actual_protocol_fee = protocol_fee;
+            actual_trading_fee = trading_fee;
+            actual_referral_fee = referral_fee;
+        }
+
+        let mut swap_result = SwapResult2 {
+            actual_input_amount: input_amount,
+            output_amount: amount_out,
+            next_sqrt_price,
+            trading_fee: actual_trading_fee,
+            protocol_fee: actual_protocol_fee,
+            referral_fee: actual_referral_fee,
+            consumed_input_amount: 0,
+            refunded_input_amount: 0,
+        };
+        swap_result.consumed_input_amount = swap_result.get_included_fee_input_amount(fee_mode)?;
+
+        Ok(swap_result)
+    }
+
+    fn get_swap_amount_in_from_base_to_quote(
+        &self,
+        config: &PoolConfig,
+        amount_out: u64,
+    ) -> Result<SwapAmountIn> {
+        // finding new target price
+        let mut total_input_amount = 0u64;
+        let mut current_sqrt_price = self.sqrt_price;
+        let mut amount_left = amount_out;
+        // Use curve.len() for backward compatibility for existing pools with 20 points
+        for i in (0..config.curve.len() - 1).rev() {
+            if amount_left == 0 {
+                break;
+            }
+            if config.curve[i].sqrt_price == 0 || config.curve[i].liquidity == 0 {
+                continue;
+            }
+            if config.curve[i].sqrt_price < current_sqrt_price {
+                let max_amount_out = get_delta_amount_quote_unsigned_256(
+                    config.curve[i].sqrt_price,
+                    current_sqrt_price,
+                    config.curve[i + 1].liquidity,
+                    Rounding::Down,
+                )?;
+                if U256::from(amount_left) < max_amount_out {
+                    let next_sqrt_price = get_next_sqrt_price_from_output(
+                        current_sqrt_price,
+                        config.curve[i + 1].liquidity,
+                        amount_left,
+                        true,
+                    )?;
+
+                    let input_amount = get_delta_amount_base_unsigned(
+                        next_sqrt_price,
+                        current_sqrt_price,
+                        config.curve[i + 1].liquidity,
+                        Rounding::Up,
+                    )?;
+                    total_input_amount = total_input_amount.safe_add(input_amount)?;
+                    current_sqrt_price = next_sqrt_price;
+                    amount_left = 0;
+                    break;
+                } else {
+                    let next_sqrt_price = config.curve[i].sqrt_price;
+                    let input_amount = get_delta_amount_base_unsigned(
+                        next_sqrt_price,
+                        current_sqrt_price,
+                        config.curve[i + 1].liquidity,
+                        Rounding::Up,
+                    )?;
+                    total_input_amount = total_input_amount.safe_add(input_amount)?;
+                    current_sqrt_price = next_sqrt_price;
+                    amount_left = amount_left.safe_sub(
+                        max_amount_out
+                            .try_into()
+                            .map_err(|_| PoolError::TypeCastFailed)?,
+                    )?;
+                }
+            }
+        }
+        if amount_left != 0 {
+            // the last range can only be traded down to the start price
+            let max_amount_out = get_delta_amount_quote_unsigned_256(
+                config.sqrt_start_price,
+                current_sqrt_price,
+                config.curve[0].liquidity,
+                Rounding::Down,
+            )?;
+            require!(
+                U256::from(amount_left) <= max_amount_out,
+                PoolError::NotEnoughLiquidity
+            );
+            let next_sqrt_price = get_next_sqrt_price_from_output(
+                current_sqrt_price,
+                config.curve[0].liquidity,
+                amount_left,
+                true,
+            )?;
+
+            let input_amount = get_delta_amount_base_unsigned(
+                next_sqrt_price,
+                current_sqrt_price,
+                config.curve[0].liquidity,
+                Rounding::Up,
+            )?;
+            total_input_amount = total_input_amount.safe_add(input_amount)?;
+            current_sqrt_price = next_sqrt_price;
+        }
+
+        Ok(SwapAmountIn {
+            input_amount: total_input_amount,
+            next_sqrt_price: current_sqrt_price,
+        })
+    }
+
+    fn get_swap_amount_in_from_quote_to_base(
+        &self,
+        config: &PoolConfig,
+        amount_out: u64,
+    ) -> Result<SwapAmountIn> {
+        // finding new target price
+        let mut total_input_amount = 0u64;
+        let mut current_sqrt_price = self.sqrt_price;
+        let mut amount_left = amount_out;
+        // Use curve.len() for backward compatibility for existing pools with 20 points
+        for i in 0..config.curve.len() {
+            if amount_left == 0 {
+                break;
+            }
+            if config.curve[i].sqrt_price == 0 || config.curve[i].liquidity == 0 {
+                break;
+            }
+            if config.curve[i].sqrt_price > current_sqrt_price {
+                let max_amount_out = get_delta_amount_base_unsigned_256(
+                    current_sqrt_price,
+                    config.curve[i].sqrt_price,
+                    config.curve[i].liquidity,
+                    Rounding::Down,
+                )?;
+                if U256::from(amount_left) < max_amount_out {
+                    let next_sqrt_price = get_next_sqrt_price_from_output(
+                        current_sqrt_price,
+                        config.curve[i].liquidity,
+                        amount_left,
+                        false,
+                    )?;
+
+                    let input_amount = get_delta_amount_quote_unsigned(
+                        current_sqrt_price,
+                        next_sqrt_price,
+                        config.curve[i].liquidity,
+                        Rounding::Up,
+                    )?;
+                    total_input_amount = total_input_amount.safe_add(input_amount)?;
+                    current_sqrt_price = next_sqrt_price;
+                    amount_left = 0;
+                    break;
+                } else {
+                    let next_sqrt_price = config.curve[i].sqrt_price;
+                    let input_amount = get_delta_amount_quote_unsigned(
+                        current_sqrt_price,
+                        next_sqrt_price,
+                        config.curve[i].liquidity,
+                        Rounding::Up,
+                    )?;
+                    total_input_amount = total_input_amount.safe_add(input_amount)?;
+                    current_sqrt_price = next_sqrt_price;
+                    amount_left = amount_left.safe_sub(
+                        max_amount_out
+                            .try_into()
+                            .map_err(|_| PoolError::TypeCastFailed)?,
+                    )?;
+                }
+            }
+        }
+
+        require!(amount_left == 0, PoolError::NotEnoughLiquidity);
+
+        // the output can't be clipped, so the buy fails if it takes the quote reserve further past
+        // migration quote threshold than the fill mode allows
+        let max_quote_reserve = match config.get_migration_fill_mode()? {
+            MigrationFillMode::ExactFill => config.migration_quote_threshold,
+            MigrationFillMode::Swallow => config
+                .migration_quote_threshold
+                .safe_add(config.get_max_swallow_quote_amount()?)?,
+        };
+        require!(
+            self.quote_reserve.safe_add(total_input_amount)? <= max_quote_reserve,
+            PoolError::NotEnoughLiquidity
+        );
+
+        Ok(SwapAmountIn {
+            input_amount: total_input_amount,
+            next_sqrt_price: current_sqrt_price,
         })
     }
 
     pub fn apply_swap_result(
         &mut self,
         config: &PoolConfig,
-        swap_result: &SwapResult,
+        swap_result: &SwapResult2,
         fee_mode: &FeeMode,
         trade_direction: TradeDirection,
         current_timestamp: u64,
     ) -> Result<()> {
-        let &SwapResult {
+        let &SwapResult2 {
             actual_input_amount,
             output_amount,
             next_sqrt_price,
             protocol_fee,
             trading_fee,
             referral_fee,
+            ..
         } = swap_result;
 
         let old_sqrt_price = self.sqrt_price;
@@ -643,23 +1070,88 @@ impl VirtualPool {
         Ok(migration_progress)
     }
 
+    pub fn get_pool_type(&self) -> Result<PoolType> {
+        let pool_type =
+            PoolType::try_from(self.pool_type).map_err(|_| PoolError::TypeCastFailed)?;
+        Ok(pool_type)
+    }
+
     pub fn set_migration_progress(&mut self, progress: u8) {
         self.migration_progress = progress;
     }
 }
 
-/// Encodes all results of swapping
+/// Encodes all results of swapping, embedded in `EvtSwap` so its layout can't change
 #[derive(Debug, PartialEq, AnchorDeserialize, AnchorSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SwapResult {
     pub actual_input_amount: u64, // if fees are on input, this can be different that the original input_amount.
     pub output_amount: u64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
+    pub next_sqrt_price: u128,
+    pub trading_fee: u64,
+    pub protocol_fee: u64,
+    pub referral_fee: u64,
+}
+
+impl From<SwapResult2> for SwapResult {
+    fn from(swap_result: SwapResult2) -> Self {
+        Self {
+            actual_input_amount: swap_result.actual_input_amount,
+            output_amount: swap_result.output_amount,
+            next_sqrt_price: swap_result.next_sqrt_price,
+            trading_fee: swap_result.trading_fee,
+            protocol_fee: swap_result.protocol_fee,
+            referral_fee: swap_result.referral_fee,
+        }
+    }
+}
+
+/// Encodes all results of swapping, with the consumed and refunded input
+#[derive(Debug, PartialEq, AnchorDeserialize, AnchorSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapResult2 {
+    pub actual_input_amount: u64, // if fees are on input, this can be different that the original input_amount.
+    pub output_amount: u64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::utils::serde_helpers::u128_string")
+    )]
     pub next_sqrt_price: u128,
     pub trading_fee: u64,
     pub protocol_fee: u64,
     pub referral_fee: u64,
+    /// amount taken from user, including fee if fees are collected on input
+    pub consumed_input_amount: u64,
+    /// amount left with user, when swap stops at price limit or migration quote threshold
+    pub refunded_input_amount: u64,
+}
+
+impl SwapResult2 {
+    /// Total amount the user has to transfer in, including fee if fees are collected on input
+    pub fn get_included_fee_input_amount(&self, fee_mode: &FeeMode) -> Result<u64> {
+        if fee_mode.fees_on_input {
+            Ok(self
+                .actual_input_amount
+                .safe_add(self.trading_fee)?
+                .safe_add(self.protocol_fee)?
+                .safe_add(self.referral_fee)?)
+        } else {
+            Ok(self.actual_input_amount)
+        }
+    }
 }
 
 pub struct SwapAmount {
     output_amount: u64,
     next_sqrt_price: u128,
+    amount_left: u64,
+}
+
+pub struct SwapAmountIn {
+    input_amount: u64,
+    next_sqrt_price: u128,
 }
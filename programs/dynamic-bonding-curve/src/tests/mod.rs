@@ -1,3 +1,21 @@
+#[cfg(test)]
+use anchor_lang::prelude::Pubkey;
+
+#[cfg(test)]
+use crate::{
+    constants::{
+        fee::{HOST_FEE_PERCENT, PROTOCOL_FEE_PERCENT},
+        MAX_CURVE_POINT, MAX_SQRT_PRICE,
+    },
+    params::liquidity_distribution::{
+        get_migration_threshold_price, LiquidityDistributionParameters,
+    },
+    state::{
+        fee::VolatilityTracker, CollectFeeMode, LiquidityDistributionConfig, PoolConfig,
+        VirtualPool,
+    },
+};
+
 #[cfg(test)]
 mod price_math;
 
@@ -15,3 +33,88 @@ mod test_total_supply;
 
 #[cfg(test)]
 mod test_migration_fee_status;
+
+#[cfg(test)]
+mod test_swap_exact_out;
+
+#[cfg(test)]
+mod test_swap_price_limit;
+
+#[cfg(test)]
+mod test_swap_exact_fill;
+
+#[cfg(test)]
+mod test_allowlist;
+
+#[cfg(test)]
+mod test_rate_limiter;
+
+#[cfg(test)]
+mod test_buy_limit;
+
+#[cfg(test)]
+mod test_first_buy;
+
+#[cfg(all(test, feature = "serde"))]
+mod test_serde;
+
+#[cfg(test)]
+pub const SQRT_START_PRICE: u128 = 2916686334356757;
+
+/// One curve point from `SQRT_START_PRICE`, then up to the max sqrt price
+#[cfg(test)]
+pub fn get_default_curve() -> Vec<LiquidityDistributionParameters> {
+    vec![
+        LiquidityDistributionParameters {
+            sqrt_price: 11666745337427032,
+            liquidity: 3111132089980541388292920297291756,
+        },
+        LiquidityDistributionParameters {
+            sqrt_price: MAX_SQRT_PRICE,
+            liquidity: 1,
+        },
+    ]
+}
+
+/// Pool at `SQRT_START_PRICE` and its config with a 1% base fee
+#[cfg(test)]
+pub fn get_pool_and_config(
+    curve: &[LiquidityDistributionParameters],
+    migration_quote_threshold: u64,
+    collect_fee_mode: CollectFeeMode,
+    activation_point: u64,
+) -> (VirtualPool, PoolConfig) {
+    let mut config = PoolConfig {
+        migration_quote_threshold,
+        sqrt_start_price: SQRT_START_PRICE,
+        collect_fee_mode: collect_fee_mode.into(),
+        ..Default::default()
+    };
+    config.pool_fees.base_fee.cliff_fee_numerator = 10_000_000; // 1%
+    config.pool_fees.protocol_fee_percent = PROTOCOL_FEE_PERCENT;
+    config.pool_fees.referral_fee_percent = HOST_FEE_PERCENT;
+    for i in 0..MAX_CURVE_POINT {
+        if i < curve.len() {
+            config.curve[i] = curve[i].to_liquidity_distribution_config();
+        } else {
+            config.curve[i] = LiquidityDistributionConfig::default();
+        }
+    }
+    config.migration_sqrt_price =
+        get_migration_threshold_price(migration_quote_threshold, SQRT_START_PRICE, curve).unwrap();
+
+    let mut pool = VirtualPool::default();
+    pool.initialize(
+        VolatilityTracker::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        Pubkey::default(),
+        SQRT_START_PRICE,
+        0,
+        activation_point,
+        u64::MAX / 2,
+    );
+    (pool, config)
+}
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::Pubkey;
+
+use crate::{
+    activation_handler::ActivationType,
+    allowlist::{get_allowlist_leaf, hash_allowlist_nodes, verify_allowlist_proof},
+    constants::MAX_ALLOWLIST_TIMESTAMP_DURATION,
+    params::allowlist::AllowlistParameters,
+    state::VirtualPool,
+};
+
+#[test]
+fn test_verify_allowlist_proof() {
+    let wallets = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let leaves = wallets.map(|wallet| get_allowlist_leaf(&wallet));
+    let node = hash_allowlist_nodes(&leaves[0], &leaves[1]);
+    let root = hash_allowlist_nodes(&node, &leaves[2]);
+
+    assert!(verify_allowlist_proof(
+        &root,
+        &wallets[0],
+        &[leaves[1], leaves[2]]
+    ));
+    // pairs are sorted, the proof doesn't depend on the side
+    assert!(verify_allowlist_proof(
+        &root,
+        &wallets[1],
+        &[leaves[0], leaves[2]]
+    ));
+    assert!(verify_allowlist_proof(&root, &wallets[2], &[node]));
+
+    assert!(!verify_allowlist_proof(&root, &wallets[2], &[leaves[0]]));
+    assert!(!verify_allowlist_proof(
+        &root,
+        &Pubkey::new_unique(),
+        &[node]
+    ));
+    // a partial proof stops below the root
+    assert!(!verify_allowlist_proof(&root, &wallets[0], &[]));
+}
+
+#[test]
+fn test_allowlist_phase() {
+    let activation_point = 1_000;
+    let allowlist = AllowlistParameters {
+        merkle_root: [1; 32],
+        end_point: activation_point + 100,
+        quote_cap_per_wallet: 1_000_000,
+    };
+    allowlist
+        .validate(ActivationType::Timestamp, activation_point)
+        .unwrap();
+
+    let mut pool = VirtualPool {
+        activation_point,
+        ..Default::default()
+    };
+    assert!(!pool.is_allowlist_phase(activation_point));
+    pool.initialize_allowlist(&allowlist);
+    assert!(pool.is_allowlist_phase(activation_point));
+    assert!(pool.is_allowlist_phase(allowlist.end_point - 1));
+    assert!(!pool.is_allowlist_phase(allowlist.end_point));
+
+    let invalid_allowlists = [
+        AllowlistParameters {
+            merkle_root: [0; 32],
+            ..allowlist
+        },
+        AllowlistParameters {
+            quote_cap_per_wallet: 0,
+            ..allowlist
+        },
+        AllowlistParameters {
+            end_point: activation_point,
+            ..allowlist
+        },
+        AllowlistParameters {
+            end_point: activation_point + MAX_ALLOWLIST_TIMESTAMP_DURATION + 1,
+            ..allowlist
+        },
+    ];
+    for invalid_allowlist in invalid_allowlists {
+        assert!(invalid_allowlist
+            .validate(ActivationType::Timestamp, activation_point)
+            .is_err());
+    }
+}
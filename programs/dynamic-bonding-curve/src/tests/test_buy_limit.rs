@@ -0,0 +1,82 @@
+use crate::{params::swap::TradeDirection, state::PoolConfig, PoolError};
+
+const ACTIVATION_POINT: u64 = 1_000;
+
+fn get_config() -> PoolConfig {
+    PoolConfig {
+        buy_limit_duration: 100,
+        max_buy_quote_per_swap: 1_000_000_000,   // 1 SOL
+        max_buy_quote_per_wallet: 3_000_000_000, // 3 SOL
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_buy_limit_window() {
+    let config = get_config();
+    let is_applied = |current_point, trade_direction| {
+        config
+            .is_buy_limit_applied(current_point, ACTIVATION_POINT, trade_direction)
+            .unwrap()
+    };
+    assert!(is_applied(ACTIVATION_POINT, TradeDirection::QuoteToBase));
+    assert!(is_applied(
+        ACTIVATION_POINT + 99,
+        TradeDirection::QuoteToBase
+    ));
+    assert!(!is_applied(
+        ACTIVATION_POINT + 100,
+        TradeDirection::QuoteToBase
+    ));
+    assert!(!is_applied(ACTIVATION_POINT, TradeDirection::BaseToQuote));
+
+    // no buy limits by default
+    assert!(!PoolConfig::default()
+        .is_buy_limit_applied(
+            ACTIVATION_POINT,
+            ACTIVATION_POINT,
+            TradeDirection::QuoteToBase
+        )
+        .unwrap());
+}
+
+#[test]
+fn test_validate_buy_limit() {
+    let config = get_config();
+    let validate = |amount_in, current_point, is_wallet_tracked| {
+        config.validate_buy_limit(
+            amount_in,
+            current_point,
+            ACTIVATION_POINT,
+            TradeDirection::QuoteToBase,
+            is_wallet_tracked,
+        )
+    };
+
+    assert!(validate(1_000_000_000, ACTIVATION_POINT, true).is_ok());
+    assert_eq!(
+        validate(1_000_000_001, ACTIVATION_POINT, true).unwrap_err(),
+        PoolError::ExceededBuyLimit.into()
+    );
+    assert_eq!(
+        validate(1, ACTIVATION_POINT, false).unwrap_err(),
+        PoolError::BuyTrackerRequired.into()
+    );
+    // any buy after the buy limits
+    assert!(validate(u64::MAX, ACTIVATION_POINT + 100, false).is_ok());
+
+    // without per wallet limit, buys don't need to be tracked
+    let config = PoolConfig {
+        max_buy_quote_per_wallet: 0,
+        ..get_config()
+    };
+    assert!(config
+        .validate_buy_limit(
+            1_000_000_000,
+            ACTIVATION_POINT,
+            ACTIVATION_POINT,
+            TradeDirection::QuoteToBase,
+            false,
+        )
+        .is_ok());
+}
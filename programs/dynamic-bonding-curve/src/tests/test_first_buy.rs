@@ -0,0 +1,135 @@
+use crate::{
+    apply_first_buy, process_curve_complete,
+    state::{CollectFeeMode, MigrationFillMode, MigrationProgress},
+    tests::{get_default_curve, get_pool_and_config},
+    PoolError, SwapParameters,
+};
+
+const ACTIVATION_POINT: u64 = 1_000;
+const CURRENT_TIMESTAMP: u64 = 1_700_000_000;
+
+#[test]
+fn test_first_buy_before_activation() {
+    let (mut pool, mut config) = get_pool_and_config(
+        &get_default_curve(),
+        80_000_000_000,
+        CollectFeeMode::QuoteToken,
+        ACTIVATION_POINT,
+    );
+    // base fee goes from 5% at the activation point down to 1%
+    config.pool_fees.base_fee.cliff_fee_numerator = 50_000_000;
+    config.pool_fees.base_fee.number_of_period = 4;
+    config.pool_fees.base_fee.period_frequency = 10;
+    config.pool_fees.base_fee.reduction_factor = 10_000_000;
+    // buy limits and allowlist phase, the first buy only needs the per swap limit
+    config.buy_limit_duration = 100;
+    config.max_buy_quote_per_swap = 10_000_000_000; // 10 SOL
+    config.max_buy_quote_per_wallet = 20_000_000_000; // 20 SOL
+    pool.allowlist_merkle_root = [1; 32];
+    pool.allowlist_end_point = ACTIVATION_POINT + 50;
+
+    let params = SwapParameters {
+        amount_in: 1_000_000_000, // 1 SOL
+        minimum_amount_out: 0,
+    };
+    let mut pool_at_activation = pool;
+    let swap_result = apply_first_buy(
+        &config,
+        &mut pool,
+        &params,
+        ACTIVATION_POINT - 500,
+        CURRENT_TIMESTAMP,
+    )
+    .unwrap();
+    assert!(swap_result.output_amount > 0);
+    assert_eq!(swap_result.consumed_input_amount, params.amount_in);
+    assert!(pool.sqrt_price > config.sqrt_start_price);
+
+    // priced at the activation point, with the max base fee
+    let swap_result_at_activation = apply_first_buy(
+        &config,
+        &mut pool_at_activation,
+        &params,
+        ACTIVATION_POINT,
+        CURRENT_TIMESTAMP,
+    )
+    .unwrap();
+    assert_eq!(
+        swap_result.output_amount,
+        swap_result_at_activation.output_amount
+    );
+    assert_eq!(
+        swap_result.trading_fee + swap_result.protocol_fee,
+        params.amount_in / 20
+    );
+}
+
+#[test]
+fn test_first_buy_invalid_params() {
+    let (pool, mut config) = get_pool_and_config(
+        &get_default_curve(),
+        80_000_000_000,
+        CollectFeeMode::QuoteToken,
+        ACTIVATION_POINT,
+    );
+    config.buy_limit_duration = 100;
+    config.max_buy_quote_per_swap = 10_000_000_000; // 10 SOL
+
+    let first_buy = |amount_in, minimum_amount_out| {
+        let mut pool = pool;
+        apply_first_buy(
+            &config,
+            &mut pool,
+            &SwapParameters {
+                amount_in,
+                minimum_amount_out,
+            },
+            ACTIVATION_POINT,
+            CURRENT_TIMESTAMP,
+        )
+    };
+    assert_eq!(first_buy(0, 0).unwrap_err(), PoolError::AmountIsZero.into());
+    assert_eq!(
+        first_buy(10_000_000_001, 0).unwrap_err(),
+        PoolError::ExceededBuyLimit.into()
+    );
+    assert_eq!(
+        first_buy(1_000_000_000, u64::MAX).unwrap_err(),
+        PoolError::ExceededSlippage.into()
+    );
+    assert!(first_buy(10_000_000_000, 0).is_ok());
+}
+
+#[test]
+fn test_first_buy_completes_curve() {
+    let (mut pool, mut config) = get_pool_and_config(
+        &get_default_curve(),
+        80_000_000_000,
+        CollectFeeMode::QuoteToken,
+        ACTIVATION_POINT,
+    );
+    config.migration_fill_mode = MigrationFillMode::ExactFill.into();
+    let params = SwapParameters {
+        amount_in: 100_000_000_000, // 100 SOL
+        minimum_amount_out: 0,
+    };
+    let swap_result = apply_first_buy(
+        &config,
+        &mut pool,
+        &params,
+        ACTIVATION_POINT,
+        CURRENT_TIMESTAMP,
+    )
+    .unwrap();
+    assert!(pool.is_curve_complete(config.migration_quote_threshold));
+    // the creator is only charged the quote needed to reach the threshold
+    assert!(swap_result.consumed_input_amount < params.amount_in);
+
+    let base_vault_balance = pool.base_reserve;
+    process_curve_complete(&mut pool, &config, base_vault_balance, CURRENT_TIMESTAMP).unwrap();
+    assert_eq!(pool.finish_curve_timestamp, CURRENT_TIMESTAMP);
+    assert_eq!(
+        pool.get_migration_progress().unwrap(),
+        MigrationProgress::LockedVesting
+    );
+}
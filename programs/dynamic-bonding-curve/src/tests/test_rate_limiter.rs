@@ -0,0 +1,269 @@
+use anchor_lang::{prelude::*, Discriminator};
+use solana_instruction::{BorrowedAccountMeta, BorrowedInstruction};
+use solana_instructions_sysvar::{construct_instructions_data, store_current_index};
+
+use crate::{
+    activation_handler::ActivationType,
+    constants::fee::{MAX_FEE_NUMERATOR, MAX_RATE_LIMITER_TIMESTAMP_DURATION},
+    params::{
+        fee_parameters::{BaseFeeParameters, PoolFeeParameters},
+        swap::TradeDirection,
+    },
+    state::{fee::VolatilityTracker, CollectFeeMode, FeeSchedulerMode},
+    validate_single_swap_instruction, PoolError,
+};
+
+const REFERENCE_AMOUNT: u64 = 1_000_000_000; // 1 SOL
+const ACTIVATION_POINT: u64 = 1_700_000_000;
+
+fn get_rate_limiter_fees() -> PoolFeeParameters {
+    PoolFeeParameters {
+        base_fee: BaseFeeParameters {
+            cliff_fee_numerator: 10_000_000, // 1%
+            number_of_period: 100,           // 1% more per reference amount
+            period_frequency: 600,           // 10 minutes
+            reduction_factor: REFERENCE_AMOUNT,
+            fee_scheduler_mode: FeeSchedulerMode::RateLimiter.into(),
+        },
+        dynamic_fee: None,
+    }
+}
+
+#[test]
+fn test_rate_limiter_fee_numerator() {
+    let rate_limiter = get_rate_limiter_fees()
+        .base_fee
+        .to_base_fee_config()
+        .get_fee_rate_limiter()
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        rate_limiter.get_fee_numerator_on_amount(1).unwrap(),
+        10_000_000
+    );
+    assert_eq!(
+        rate_limiter
+            .get_fee_numerator_on_amount(REFERENCE_AMOUNT)
+            .unwrap(),
+        10_000_000
+    );
+    // 1 SOL at 1% and 0.5 SOL at 2%
+    assert_eq!(
+        rate_limiter
+            .get_fee_numerator_on_amount(REFERENCE_AMOUNT * 3 / 2)
+            .unwrap(),
+        13_333_334
+    );
+
+    let mut last_fee = 0;
+    for i in 1..=100 {
+        let amount = REFERENCE_AMOUNT * i;
+        let fee_numerator = rate_limiter.get_fee_numerator_on_amount(amount).unwrap();
+        assert!(fee_numerator >= last_fee);
+        assert!(fee_numerator <= MAX_FEE_NUMERATOR);
+        // the total fee grows with the amount
+        assert!(
+            u128::from(fee_numerator) * u128::from(amount)
+                > u128::from(last_fee) * u128::from(amount - REFERENCE_AMOUNT)
+        );
+        last_fee = fee_numerator;
+    }
+    assert_eq!(
+        rate_limiter.get_fee_numerator_on_amount(u64::MAX).unwrap(),
+        MAX_FEE_NUMERATOR
+    );
+}
+
+#[test]
+fn test_rate_limiter_only_applies_to_buys_in_window() {
+    let pool_fees = get_rate_limiter_fees().to_pool_fees_config();
+    let volatility_tracker = VolatilityTracker::default();
+    let amount = REFERENCE_AMOUNT * 10;
+
+    let get_fee = |current_point, trade_direction| {
+        pool_fees
+            .get_total_trading_fee(
+                &volatility_tracker,
+                current_point,
+                ACTIVATION_POINT,
+                amount,
+                trade_direction,
+            )
+            .unwrap()
+    };
+    assert!(get_fee(ACTIVATION_POINT, TradeDirection::QuoteToBase) > 10_000_000);
+    assert!(get_fee(ACTIVATION_POINT + 599, TradeDirection::QuoteToBase) > 10_000_000);
+    assert_eq!(
+        get_fee(ACTIVATION_POINT + 600, TradeDirection::QuoteToBase),
+        10_000_000
+    );
+    assert_eq!(
+        get_fee(ACTIVATION_POINT, TradeDirection::BaseToQuote),
+        10_000_000
+    );
+}
+
+#[test]
+fn test_rate_limiter_fee_on_excluded_amount() {
+    let pool_fees = get_rate_limiter_fees().to_pool_fees_config();
+    let volatility_tracker = VolatilityTracker::default();
+
+    for excluded_amount in [
+        1,
+        REFERENCE_AMOUNT / 2,
+        REFERENCE_AMOUNT,
+        REFERENCE_AMOUNT * 7 / 3,
+        REFERENCE_AMOUNT * 100,
+    ] {
+        let included = pool_fees
+            .get_fee_on_excluded_amount(
+                &volatility_tracker,
+                excluded_amount,
+                false,
+                ACTIVATION_POINT,
+                ACTIVATION_POINT,
+                TradeDirection::QuoteToBase,
+            )
+            .unwrap();
+        let get_amount_after_fee = |amount| {
+            pool_fees
+                .get_fee_on_amount(
+                    &volatility_tracker,
+                    amount,
+                    false,
+                    ACTIVATION_POINT,
+                    ACTIVATION_POINT,
+                    TradeDirection::QuoteToBase,
+                )
+                .unwrap()
+                .amount
+        };
+        // the included amount covers the fee, and is the smallest that does
+        assert!(get_amount_after_fee(included.amount) >= excluded_amount);
+        assert!(get_amount_after_fee(included.amount - 1) < excluded_amount);
+    }
+}
+
+#[test]
+fn test_rate_limiter_validation() {
+    let quote_token = CollectFeeMode::QuoteToken.into();
+    let timestamp = ActivationType::Timestamp.into();
+
+    let pool_fees = get_rate_limiter_fees();
+    assert!(pool_fees.validate(quote_token, timestamp).is_ok());
+    assert!(pool_fees
+        .validate(CollectFeeMode::OutputToken.into(), timestamp)
+        .is_err());
+
+    let invalid_base_fees = [
+        BaseFeeParameters {
+            period_frequency: 0,
+            ..pool_fees.base_fee
+        },
+        BaseFeeParameters {
+            period_frequency: MAX_RATE_LIMITER_TIMESTAMP_DURATION + 1,
+            ..pool_fees.base_fee
+        },
+        BaseFeeParameters {
+            reduction_factor: 0,
+            ..pool_fees.base_fee
+        },
+        BaseFeeParameters {
+            number_of_period: 0,
+            ..pool_fees.base_fee
+        },
+        BaseFeeParameters {
+            cliff_fee_numerator: MAX_FEE_NUMERATOR + 1,
+            ..pool_fees.base_fee
+        },
+    ];
+    for base_fee in invalid_base_fees {
+        let pool_fees = PoolFeeParameters {
+            base_fee,
+            dynamic_fee: None,
+        };
+        assert!(pool_fees.validate(quote_token, timestamp).is_err());
+    }
+}
+
+/// Validates the swap at `current_index` of a transaction of `(program id, data, accounts)`
+/// instructions
+fn validate_swap_in_transaction(
+    instructions: &[(Pubkey, &[u8], Vec<Pubkey>)],
+    current_index: u16,
+    pool: &Pubkey,
+) -> Result<()> {
+    let borrowed_instructions = instructions
+        .iter()
+        .map(|(program_id, data, accounts)| BorrowedInstruction {
+            program_id,
+            accounts: accounts
+                .iter()
+                .map(|pubkey| BorrowedAccountMeta {
+                    pubkey,
+                    is_signer: false,
+                    is_writable: false,
+                })
+                .collect(),
+            data,
+        })
+        .collect::<Vec<_>>();
+    let mut data = construct_instructions_data(&borrowed_instructions);
+    store_current_index(&mut data, current_index);
+
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &solana_instructions_sysvar::ID,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &solana_instructions_sysvar::ID,
+        false,
+        0,
+    );
+    validate_single_swap_instruction(pool, &[account_info])
+}
+
+#[test]
+fn test_rate_limiter_rejects_split_buy() {
+    let pool = Pubkey::new_unique();
+    let other_pool = Pubkey::new_unique();
+    let aggregator = Pubkey::new_unique();
+    let swap_data = crate::instruction::Swap::DISCRIMINATOR;
+    let swap_exact_out_data = crate::instruction::SwapExactOut::DISCRIMINATOR;
+    let claim_data = crate::instruction::ClaimCreatorTradingFee::DISCRIMINATOR;
+    // pool is the third account of the swap accounts
+    let swap_accounts = |pool: Pubkey| vec![Pubkey::new_unique(), Pubkey::new_unique(), pool];
+    let fail = Err(PoolError::FailToValidateSingleSwapInstruction.into());
+
+    // a buy split into two swaps in one transaction
+    let split_buy = [
+        (crate::ID, swap_data, swap_accounts(pool)),
+        (crate::ID, swap_exact_out_data, swap_accounts(pool)),
+    ];
+    assert!(validate_swap_in_transaction(&split_buy, 0, &pool).is_ok());
+    assert_eq!(validate_swap_in_transaction(&split_buy, 1, &pool), fail);
+
+    // an instruction of another program on the pool could swap through a CPI
+    let aggregator_and_swap = [
+        (aggregator, &[][..], vec![pool]),
+        (crate::ID, swap_data, swap_accounts(pool)),
+    ];
+    assert_eq!(
+        validate_swap_in_transaction(&aggregator_and_swap, 1, &pool),
+        fail
+    );
+
+    // swaps on other pools and non swap instructions on the pool are allowed
+    let single_swap = [
+        (crate::ID, swap_data, swap_accounts(other_pool)),
+        (crate::ID, claim_data, swap_accounts(pool)),
+        (crate::ID, swap_data, swap_accounts(pool)),
+    ];
+    assert!(validate_swap_in_transaction(&single_swap, 2, &pool).is_ok());
+
+    // the instructions sysvar is required
+    assert_eq!(validate_single_swap_instruction(&pool, &[]), fail);
+}
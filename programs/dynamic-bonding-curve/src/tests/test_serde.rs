@@ -0,0 +1,91 @@
+use anchor_lang::prelude::Pubkey;
+use serde_json::json;
+
+use crate::{
+    params::liquidity_distribution::LiquidityDistributionParameters,
+    state::{PoolConfig, SwapResult, VirtualPool},
+    EvtSwap, SwapParameters,
+};
+
+#[test]
+fn test_serde_pool_config() {
+    let quote_mint = Pubkey::new_unique();
+    let mut config = PoolConfig {
+        quote_mint,
+        sqrt_start_price: 2916686334356757,
+        migration_sqrt_price: u128::MAX,
+        migration_quote_threshold: 80_000_000_000,
+        ..Default::default()
+    };
+    config.curve[0] = LiquidityDistributionParameters {
+        sqrt_price: 11666745337427032,
+        liquidity: 3111132089980541388292920297291756,
+    }
+    .to_liquidity_distribution_config();
+
+    let value = serde_json::to_value(config).unwrap();
+    assert_eq!(value["quote_mint"], quote_mint.to_string());
+    assert_eq!(value["sqrt_start_price"], "2916686334356757");
+    assert_eq!(value["migration_sqrt_price"], u128::MAX.to_string());
+    assert_eq!(value["migration_quote_threshold"], 80_000_000_000u64);
+    assert_eq!(
+        value["curve"][0]["liquidity"],
+        "3111132089980541388292920297291756"
+    );
+    assert_eq!(value["pool_fees"]["dynamic_fee"]["bin_step_u128"], "0");
+    // padding is not persisted
+    assert!(value.get("_padding_1").is_none());
+
+    let decoded: PoolConfig = serde_json::from_value(value).unwrap();
+    assert_eq!(bytemuck::bytes_of(&decoded), bytemuck::bytes_of(&config));
+
+    let mut invalid = serde_json::to_value(config).unwrap();
+    invalid["quote_mint"] = json!("not a pubkey");
+    assert!(serde_json::from_value::<PoolConfig>(invalid).is_err());
+    let mut invalid = serde_json::to_value(config).unwrap();
+    invalid["sqrt_start_price"] = json!(2916686334356757u64);
+    assert!(serde_json::from_value::<PoolConfig>(invalid).is_err());
+}
+
+#[test]
+fn test_serde_virtual_pool_and_event() {
+    let pool = VirtualPool {
+        config: Pubkey::new_unique(),
+        sqrt_price: 11666745337427032,
+        base_reserve: 1_000,
+        ..Default::default()
+    };
+    let value = serde_json::to_value(pool).unwrap();
+    assert_eq!(value["config"], pool.config.to_string());
+    assert_eq!(value["sqrt_price"], "11666745337427032");
+    assert_eq!(value["volatility_tracker"]["volatility_accumulator"], "0");
+    let decoded: VirtualPool = serde_json::from_value(value).unwrap();
+    assert_eq!(bytemuck::bytes_of(&decoded), bytemuck::bytes_of(&pool));
+
+    let event = EvtSwap {
+        pool: Pubkey::new_unique(),
+        config: pool.config,
+        trade_direction: 1,
+        has_referral: false,
+        params: SwapParameters {
+            amount_in: 100,
+            minimum_amount_out: 90,
+        },
+        swap_result: SwapResult {
+            actual_input_amount: 99,
+            output_amount: 95,
+            next_sqrt_price: 11666745337427033,
+            trading_fee: 1,
+            protocol_fee: 0,
+            referral_fee: 0,
+        },
+        amount_in: 100,
+        current_timestamp: 1,
+    };
+    let value = serde_json::to_value(&event).unwrap();
+    assert_eq!(value["pool"], event.pool.to_string());
+    assert_eq!(value["params"]["minimum_amount_out"], 90);
+    assert_eq!(value["swap_result"]["next_sqrt_price"], "11666745337427033");
+    let decoded: EvtSwap = serde_json::from_value(value).unwrap();
+    assert_eq!(decoded.swap_result, event.swap_result);
+}
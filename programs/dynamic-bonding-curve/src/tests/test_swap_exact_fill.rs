@@ -0,0 +1,153 @@
+use crate::{
+    params::swap::TradeDirection,
+    state::{fee::FeeMode, CollectFeeMode, MigrationFillMode, PoolConfig, VirtualPool},
+    tests::{get_default_curve, get_pool_and_config},
+    PoolError,
+};
+
+fn get_pool_and_config_with_fill_mode(
+    collect_fee_mode: CollectFeeMode,
+    migration_fill_mode: MigrationFillMode,
+) -> (VirtualPool, PoolConfig) {
+    let (pool, mut config) =
+        get_pool_and_config(&get_default_curve(), 80_000_000_000, collect_fee_mode, 0);
+    config.migration_fill_mode = migration_fill_mode.into();
+    (pool, config)
+}
+
+#[test]
+fn test_exact_fill_at_migration_threshold() {
+    for collect_fee_mode in [CollectFeeMode::QuoteToken, CollectFeeMode::OutputToken] {
+        for has_referral in [false, true] {
+            let (mut pool, config) =
+                get_pool_and_config_with_fill_mode(collect_fee_mode, MigrationFillMode::ExactFill);
+            let trade_direction = TradeDirection::QuoteToBase;
+            let fee_mode =
+                FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral)
+                    .unwrap();
+
+            let amount_in = 90_000_000_000; // 90 SOL
+            let swap_result = pool
+                .get_swap_result(&config, amount_in, &fee_mode, trade_direction, 0)
+                .unwrap();
+
+            assert!(swap_result.refunded_input_amount > 0);
+            assert_eq!(
+                swap_result.consumed_input_amount + swap_result.refunded_input_amount,
+                amount_in
+            );
+            assert_eq!(
+                swap_result.consumed_input_amount,
+                swap_result
+                    .get_included_fee_input_amount(&fee_mode)
+                    .unwrap()
+            );
+
+            pool.apply_swap_result(&config, &swap_result, &fee_mode, trade_direction, 0)
+                .unwrap();
+            assert_eq!(pool.quote_reserve, config.migration_quote_threshold);
+            assert!(pool.is_curve_complete(config.migration_quote_threshold));
+        }
+    }
+}
+
+#[test]
+fn test_exact_fill_below_migration_threshold() {
+    let (pool, config) = get_pool_and_config_with_fill_mode(
+        CollectFeeMode::QuoteToken,
+        MigrationFillMode::ExactFill,
+    );
+    let trade_direction = TradeDirection::QuoteToBase;
+    let fee_mode = FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+
+    let amount_in = 10_000_000_000; // 10 SOL
+    let swap_result = pool
+        .get_swap_result(&config, amount_in, &fee_mode, trade_direction, 0)
+        .unwrap();
+    assert_eq!(swap_result.consumed_input_amount, amount_in);
+    assert_eq!(swap_result.refunded_input_amount, 0);
+}
+
+#[test]
+fn test_swallow_over_migration_threshold() {
+    let (mut pool, config) =
+        get_pool_and_config_with_fill_mode(CollectFeeMode::QuoteToken, MigrationFillMode::Swallow);
+    let trade_direction = TradeDirection::QuoteToBase;
+    let fee_mode = FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+
+    let amount_in = 90_000_000_000; // 90 SOL
+    let swap_result = pool
+        .get_swap_result(&config, amount_in, &fee_mode, trade_direction, 0)
+        .unwrap();
+    assert_eq!(swap_result.consumed_input_amount, amount_in);
+    assert_eq!(swap_result.refunded_input_amount, 0);
+
+    pool.apply_swap_result(&config, &swap_result, &fee_mode, trade_direction, 0)
+        .unwrap();
+    assert!(pool.quote_reserve > config.migration_quote_threshold);
+}
+
+#[test]
+fn test_exact_out_capped_at_migration_threshold() {
+    let trade_direction = TradeDirection::QuoteToBase;
+    // base output bought with `amount_in` quote at the start price
+    let get_amount_out = |amount_in| {
+        let (pool, config) = get_pool_and_config_with_fill_mode(
+            CollectFeeMode::QuoteToken,
+            MigrationFillMode::Swallow,
+        );
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+        pool.get_swap_result(&config, amount_in, &fee_mode, trade_direction, 0)
+            .unwrap()
+            .output_amount
+    };
+    let small_amount_out = get_amount_out(500_000_000); // 0.5 SOL
+    let medium_amount_out = get_amount_out(2_000_000_000); // 2 SOL
+    let large_amount_out = get_amount_out(20_000_000_000); // 20 SOL
+
+    // (fill mode, amount out, is ok) with 1 SOL left to migration quote threshold and up to 16 SOL
+    // swallowed
+    let cases = [
+        (MigrationFillMode::ExactFill, small_amount_out, true),
+        (MigrationFillMode::ExactFill, medium_amount_out, false),
+        (MigrationFillMode::ExactFill, large_amount_out, false),
+        (MigrationFillMode::Swallow, small_amount_out, true),
+        (MigrationFillMode::Swallow, medium_amount_out, true),
+        (MigrationFillMode::Swallow, large_amount_out, false),
+    ];
+    for (migration_fill_mode, amount_out, is_ok) in cases {
+        let (mut pool, config) =
+            get_pool_and_config_with_fill_mode(CollectFeeMode::QuoteToken, migration_fill_mode);
+        pool.quote_reserve = config.migration_quote_threshold - 1_000_000_000;
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+
+        let swap_result = pool.get_swap_result_from_exact_output(
+            &config,
+            amount_out,
+            &fee_mode,
+            trade_direction,
+            0,
+        );
+        if is_ok {
+            let swap_result = swap_result.unwrap();
+            assert_eq!(swap_result.output_amount, amount_out);
+            pool.apply_swap_result(&config, &swap_result, &fee_mode, trade_direction, 0)
+                .unwrap();
+            let max_quote_reserve = match migration_fill_mode {
+                MigrationFillMode::ExactFill => config.migration_quote_threshold,
+                MigrationFillMode::Swallow => {
+                    config.migration_quote_threshold
+                        + config.get_max_swallow_quote_amount().unwrap()
+                }
+            };
+            assert!(pool.quote_reserve <= max_quote_reserve);
+        } else {
+            assert_eq!(
+                swap_result.unwrap_err(),
+                PoolError::NotEnoughLiquidity.into()
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    params::swap::TradeDirection,
+    state::{fee::FeeMode, CollectFeeMode, PoolConfig, VirtualPool},
+    tests::{get_default_curve, get_pool_and_config},
+};
+
+fn buy(pool: &mut VirtualPool, config: &PoolConfig, amount_in: u64) {
+    let fee_mode =
+        FeeMode::get_fee_mode(config.collect_fee_mode, TradeDirection::QuoteToBase, false).unwrap();
+    let result = pool
+        .get_swap_result(config, amount_in, &fee_mode, TradeDirection::QuoteToBase, 0)
+        .unwrap();
+    pool.apply_swap_result(config, &result, &fee_mode, TradeDirection::QuoteToBase, 0)
+        .unwrap();
+}
+
+fn assert_exact_out_matches_exact_in(
+    pool: &VirtualPool,
+    config: &PoolConfig,
+    amount_out: u64,
+    trade_direction: TradeDirection,
+    has_referral: bool,
+) {
+    let fee_mode =
+        FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral).unwrap();
+    let exact_out_result = pool
+        .get_swap_result_from_exact_output(config, amount_out, &fee_mode, trade_direction, 0)
+        .unwrap();
+    assert_eq!(exact_out_result.output_amount, amount_out);
+
+    let amount_in = exact_out_result
+        .get_included_fee_input_amount(&fee_mode)
+        .unwrap();
+
+    // swapping the required input must give at least the requested output
+    let exact_in_result = pool
+        .get_swap_result(config, amount_in, &fee_mode, trade_direction, 0)
+        .unwrap();
+    assert!(exact_in_result.output_amount >= amount_out);
+
+    // one less unit of input must not be enough, otherwise exact out overcharges
+    if amount_in > 1 {
+        let exact_in_result = pool
+            .get_swap_result(config, amount_in - 1, &fee_mode, trade_direction, 0)
+            .unwrap();
+        assert!(exact_in_result.output_amount < amount_out);
+    }
+}
+
+#[test]
+fn test_swap_exact_out_quote_to_base() {
+    for collect_fee_mode in [CollectFeeMode::QuoteToken, CollectFeeMode::OutputToken] {
+        let (pool, config) =
+            get_pool_and_config(&get_default_curve(), 80_000_000_000, collect_fee_mode, 0);
+        for amount_out in [1, 1_000_000, 1_000_000_000_000, 100_000_000_000_000] {
+            assert_exact_out_matches_exact_in(
+                &pool,
+                &config,
+                amount_out,
+                TradeDirection::QuoteToBase,
+                false,
+            );
+            assert_exact_out_matches_exact_in(
+                &pool,
+                &config,
+                amount_out,
+                TradeDirection::QuoteToBase,
+                true,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_swap_exact_out_base_to_quote() {
+    for collect_fee_mode in [CollectFeeMode::QuoteToken, CollectFeeMode::OutputToken] {
+        let (mut pool, config) =
+            get_pool_and_config(&get_default_curve(), 80_000_000_000, collect_fee_mode, 0);
+        buy(&mut pool, &config, 10_000_000_000);
+        for amount_out in [1, 1_000_000, 1_000_000_000, 5_000_000_000] {
+            assert_exact_out_matches_exact_in(
+                &pool,
+                &config,
+                amount_out,
+                TradeDirection::BaseToQuote,
+                false,
+            );
+            assert_exact_out_matches_exact_in(
+                &pool,
+                &config,
+                amount_out,
+                TradeDirection::BaseToQuote,
+                true,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_swap_exact_out_not_enough_liquidity() {
+    let (mut pool, config) = get_pool_and_config(
+        &get_default_curve(),
+        80_000_000_000,
+        CollectFeeMode::QuoteToken,
+        0,
+    );
+    buy(&mut pool, &config, 1_000_000_000);
+
+    let fee_mode =
+        FeeMode::get_fee_mode(config.collect_fee_mode, TradeDirection::BaseToQuote, false).unwrap();
+    // can't take more quote than the pool has received
+    let result = pool.get_swap_result_from_exact_output(
+        &config,
+        pool.quote_reserve + 1,
+        &fee_mode,
+        TradeDirection::BaseToQuote,
+        0,
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,209 @@
+use crate::{
+    constants::MAX_SQRT_PRICE,
+    params::{liquidity_distribution::LiquidityDistributionParameters, swap::TradeDirection},
+    state::{fee::FeeMode, CollectFeeMode, PoolConfig, VirtualPool},
+    tests::{get_pool_and_config, SQRT_START_PRICE},
+};
+
+const MIGRATION_QUOTE_THRESHOLD: u64 = 120_000_000_000; // 120 SOL
+
+fn get_curve() -> Vec<LiquidityDistributionParameters> {
+    vec![
+        LiquidityDistributionParameters {
+            sqrt_price: SQRT_START_PRICE * 2,
+            liquidity: 3111132089980541388292920297291756,
+        },
+        LiquidityDistributionParameters {
+            sqrt_price: SQRT_START_PRICE * 4,
+            liquidity: 6222264179961082776585840594583512,
+        },
+        LiquidityDistributionParameters {
+            sqrt_price: MAX_SQRT_PRICE,
+            liquidity: 1,
+        },
+    ]
+}
+
+fn swap(
+    pool: &mut VirtualPool,
+    config: &PoolConfig,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+) {
+    let fee_mode = FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+    let result = pool
+        .get_swap_result(config, amount_in, &fee_mode, trade_direction, 0)
+        .unwrap();
+    pool.apply_swap_result(config, &result, &fee_mode, trade_direction, 0)
+        .unwrap();
+}
+
+fn assert_stop_at_price_limit(
+    pool: &VirtualPool,
+    config: &PoolConfig,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+    sqrt_price_limit: u128,
+) {
+    for has_referral in [false, true] {
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, has_referral).unwrap();
+        let result = pool
+            .get_swap_result_with_sqrt_price_limit(
+                config,
+                amount_in,
+                &fee_mode,
+                trade_direction,
+                0,
+                Some(sqrt_price_limit),
+            )
+            .unwrap();
+        assert_eq!(result.next_sqrt_price, sqrt_price_limit);
+
+        let consumed_amount_in = result.get_included_fee_input_amount(&fee_mode).unwrap();
+        assert!(consumed_amount_in < amount_in);
+
+        // swapping only the consumed amount without limit must not get a better result
+        let result_without_limit = pool
+            .get_swap_result(config, consumed_amount_in, &fee_mode, trade_direction, 0)
+            .unwrap();
+        assert!(result_without_limit.output_amount >= result.output_amount);
+        match trade_direction {
+            TradeDirection::QuoteToBase => {
+                assert!(result_without_limit.next_sqrt_price >= sqrt_price_limit)
+            }
+            TradeDirection::BaseToQuote => {
+                assert!(result_without_limit.next_sqrt_price <= sqrt_price_limit)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_swap_price_limit_quote_to_base() {
+    for collect_fee_mode in [CollectFeeMode::QuoteToken, CollectFeeMode::OutputToken] {
+        let (pool, config) =
+            get_pool_and_config(&get_curve(), MIGRATION_QUOTE_THRESHOLD, collect_fee_mode, 0);
+        for sqrt_price_limit in [
+            SQRT_START_PRICE + 1_000_000,
+            SQRT_START_PRICE * 2,
+            SQRT_START_PRICE * 3,
+        ] {
+            assert_stop_at_price_limit(
+                &pool,
+                &config,
+                500_000_000_000,
+                TradeDirection::QuoteToBase,
+                sqrt_price_limit,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_swap_price_limit_base_to_quote() {
+    for collect_fee_mode in [CollectFeeMode::QuoteToken, CollectFeeMode::OutputToken] {
+        let (mut pool, config) =
+            get_pool_and_config(&get_curve(), MIGRATION_QUOTE_THRESHOLD, collect_fee_mode, 0);
+        swap(
+            &mut pool,
+            &config,
+            100_000_000_000,
+            TradeDirection::QuoteToBase,
+        );
+        assert!(pool.sqrt_price > SQRT_START_PRICE * 2);
+
+        for sqrt_price_limit in [
+            pool.sqrt_price - 1_000_000,
+            SQRT_START_PRICE * 2,
+            SQRT_START_PRICE * 3 / 2,
+        ] {
+            assert_stop_at_price_limit(
+                &pool,
+                &config,
+                pool.base_reserve,
+                TradeDirection::BaseToQuote,
+                sqrt_price_limit,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_swap_price_limit_not_reached() {
+    let (mut pool, config) = get_pool_and_config(
+        &get_curve(),
+        MIGRATION_QUOTE_THRESHOLD,
+        CollectFeeMode::QuoteToken,
+        0,
+    );
+    swap(
+        &mut pool,
+        &config,
+        1_000_000_000,
+        TradeDirection::QuoteToBase,
+    );
+
+    for (trade_direction, amount_in, sqrt_price_limit) in [
+        (
+            TradeDirection::QuoteToBase,
+            1_000_000_000,
+            SQRT_START_PRICE * 4,
+        ),
+        (TradeDirection::BaseToQuote, 1_000_000, SQRT_START_PRICE),
+    ] {
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+        let result = pool
+            .get_swap_result_with_sqrt_price_limit(
+                &config,
+                amount_in,
+                &fee_mode,
+                trade_direction,
+                0,
+                Some(sqrt_price_limit),
+            )
+            .unwrap();
+        let result_without_limit = pool
+            .get_swap_result(&config, amount_in, &fee_mode, trade_direction, 0)
+            .unwrap();
+        assert_eq!(result, result_without_limit);
+        assert_eq!(
+            result.get_included_fee_input_amount(&fee_mode).unwrap(),
+            amount_in
+        );
+    }
+}
+
+#[test]
+fn test_swap_price_limit_invalid_direction() {
+    let (mut pool, config) = get_pool_and_config(
+        &get_curve(),
+        MIGRATION_QUOTE_THRESHOLD,
+        CollectFeeMode::QuoteToken,
+        0,
+    );
+    swap(
+        &mut pool,
+        &config,
+        1_000_000_000,
+        TradeDirection::QuoteToBase,
+    );
+
+    for (trade_direction, sqrt_price_limit) in [
+        (TradeDirection::QuoteToBase, pool.sqrt_price),
+        (TradeDirection::BaseToQuote, pool.sqrt_price + 1),
+    ] {
+        let fee_mode =
+            FeeMode::get_fee_mode(config.collect_fee_mode, trade_direction, false).unwrap();
+        let result = pool.get_swap_result_with_sqrt_price_limit(
+            &config,
+            1_000_000,
+            &fee_mode,
+            trade_direction,
+            0,
+            Some(sqrt_price_limit),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -32,6 +32,24 @@ pub fn get_current_point(activation_type: u8) -> Result<u64> {
     Ok(current_point)
 }
 
+/// Activation point of a new pool, `current_point` unless the creator schedules a later one
+/// within `max_activation_delay`
+pub fn get_activation_point(
+    current_point: u64,
+    activation_point: Option<u64>,
+    max_activation_delay: u64,
+) -> Result<u64> {
+    let Some(activation_point) = activation_point else {
+        return Ok(current_point);
+    };
+    require!(
+        activation_point >= current_point
+            && activation_point - current_point <= max_activation_delay,
+        PoolError::InvalidActivationPoint
+    );
+    Ok(activation_point)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,6 +76,23 @@ mod tests {
         assert_eq!(result.unwrap_err(), PoolError::InvalidActivationType.into());
     }
 
+    #[test]
+    fn test_get_activation_point() {
+        assert_eq!(get_activation_point(100, None, 0).unwrap(), 100);
+        assert_eq!(get_activation_point(100, None, 50).unwrap(), 100);
+        assert_eq!(get_activation_point(100, Some(100), 0).unwrap(), 100);
+        assert_eq!(get_activation_point(100, Some(150), 50).unwrap(), 150);
+
+        // in the past or over the max delay
+        for (activation_point, max_activation_delay) in [(99, 50), (151, 50), (101, 0)] {
+            assert_eq!(
+                get_activation_point(100, Some(activation_point), max_activation_delay)
+                    .unwrap_err(),
+                PoolError::InvalidActivationPoint.into()
+            );
+        }
+    }
+
     // Note: We cannot directly test get_current_point with slot/timestamp
     // as it requires access to the Clock sysvar which is not available in unit tests.
     // These tests should be done in integration tests or program tests instead.
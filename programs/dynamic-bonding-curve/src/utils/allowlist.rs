@@ -0,0 +1,29 @@
+//! Merkle tree of allowlisted wallets. Leaves and nodes use different prefixes so a node can't be
+//! passed as a leaf, and each pair is hashed in sorted order so the proof doesn't need the side
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn get_allowlist_leaf(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, wallet.as_ref()]).to_bytes()
+}
+
+pub fn hash_allowlist_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+pub fn verify_allowlist_proof(merkle_root: &[u8; 32], wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let node = proof
+        .iter()
+        .fold(get_allowlist_leaf(wallet), |node, sibling| {
+            hash_allowlist_nodes(&node, sibling)
+        });
+    node == *merkle_root
+}
@@ -1,2 +1,5 @@
 pub mod activation_handler;
+pub mod allowlist;
+#[cfg(feature = "serde")]
+pub mod serde_helpers;
 pub mod token;
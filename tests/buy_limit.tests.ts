@@ -0,0 +1,205 @@
+import { ProgramTestContext } from "solana-bankrun";
+import {
+  createConfig,
+  CreateConfigParams,
+  createPoolWithSplToken,
+  swap,
+  SwapParams,
+  swapWithBuyTracker,
+} from "./instructions";
+import { VirtualCurveProgram } from "./utils/types";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  createVirtualCurveProgram,
+  designCurve,
+  expectThrowsProgramError,
+  fundSol,
+  startTest,
+} from "./utils";
+import { getBuyTracker, getVirtualPool } from "./utils/fetcher";
+import { createToken, mintSplTokenTo } from "./utils/token";
+import { expect } from "chai";
+import { BN } from "bn.js";
+
+describe("Buy limits", () => {
+  let context: ProgramTestContext;
+  let admin: Keypair;
+  let operator: Keypair;
+  let partner: Keypair;
+  let user: Keypair;
+  let poolCreator: Keypair;
+  let program: VirtualCurveProgram;
+  let quoteMint: PublicKey;
+  let config: PublicKey;
+  let virtualPool: PublicKey;
+  let buyParams: SwapParams;
+
+  const buyLimitDuration = new BN(1_000); // slots
+  const maxBuyQuotePerSwap = new BN(1_000_000_000);
+  const maxBuyQuotePerWallet = new BN(2_000_000_000);
+
+  before(async () => {
+    context = await startTest();
+    admin = context.payer;
+    operator = Keypair.generate();
+    partner = Keypair.generate();
+    user = Keypair.generate();
+    poolCreator = Keypair.generate();
+    const receivers = [
+      operator.publicKey,
+      partner.publicKey,
+      user.publicKey,
+      poolCreator.publicKey,
+    ];
+    await fundSol(context.banksClient, admin, receivers);
+    program = createVirtualCurveProgram();
+
+    quoteMint = await createToken(
+      context.banksClient,
+      admin,
+      admin.publicKey,
+      9
+    );
+    await mintSplTokenTo(
+      context.banksClient,
+      user,
+      quoteMint,
+      admin,
+      user.publicKey,
+      10_000_000_000
+    );
+
+    const instructionParams = designCurve(
+      1_000_000_000, // total token supply
+      10, // percentage supply on migration
+      300, // migration quote threshold
+      0, // damm v1
+      6, // token base decimal
+      9, // token quote decimal
+      0, // creator trading fee percentage
+      0, // collect fee mode
+      {
+        amountPerPeriod: new BN(0),
+        cliffDurationFromMigrationTime: new BN(0),
+        frequency: new BN(0),
+        numberOfPeriod: new BN(0),
+        cliffUnlockAmount: new BN(0),
+      }
+    );
+    // activation type slot
+    instructionParams.buyLimitDuration = buyLimitDuration;
+    instructionParams.maxBuyQuotePerSwap = maxBuyQuotePerSwap;
+    instructionParams.maxBuyQuotePerWallet = maxBuyQuotePerWallet;
+    const params: CreateConfigParams = {
+      payer: partner,
+      leftoverReceiver: partner.publicKey,
+      feeClaimer: partner.publicKey,
+      quoteMint,
+      instructionParams,
+    };
+    config = await createConfig(context.banksClient, program, params);
+
+    virtualPool = await createPoolWithSplToken(context.banksClient, program, {
+      poolCreator,
+      payer: operator,
+      quoteMint,
+      config,
+      instructionParams: {
+        name: "test token spl",
+        symbol: "TEST",
+        uri: "abc.com",
+      },
+    });
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    buyParams = {
+      config,
+      payer: user,
+      pool: virtualPool,
+      inputTokenMint: quoteMint,
+      outputTokenMint: virtualPoolState.baseMint,
+      amountIn: maxBuyQuotePerSwap,
+      minimumAmountOut: new BN(0),
+      referralTokenAccount: null,
+    };
+  });
+
+  it("Buy without buy tracker fails under per wallet limit", async () => {
+    await expectThrowsProgramError(
+      program,
+      swap(context.banksClient, program, buyParams),
+      "buyTrackerRequired"
+    );
+  });
+
+  it("Buy over per swap limit fails", async () => {
+    await expectThrowsProgramError(
+      program,
+      swapWithBuyTracker(context.banksClient, program, {
+        ...buyParams,
+        amountIn: maxBuyQuotePerSwap.addn(1),
+      }),
+      "exceededBuyLimit"
+    );
+  });
+
+  it("Buy with buy tracker up to per wallet limit", async () => {
+    const buyTracker = await swapWithBuyTracker(
+      context.banksClient,
+      program,
+      buyParams
+    );
+    let buyTrackerState = await getBuyTracker(
+      context.banksClient,
+      program,
+      buyTracker
+    );
+    expect(buyTrackerState.pool.toString()).eq(virtualPool.toString());
+    expect(buyTrackerState.owner.toString()).eq(user.publicKey.toString());
+    expect(buyTrackerState.boughtQuoteAmount.toString()).eq(
+      maxBuyQuotePerSwap.toString()
+    );
+
+    await swapWithBuyTracker(context.banksClient, program, {
+      ...buyParams,
+      amountIn: maxBuyQuotePerWallet.sub(maxBuyQuotePerSwap),
+      // different from the first buy, so the transaction isn't a duplicate
+      minimumAmountOut: new BN(1),
+    });
+    buyTrackerState = await getBuyTracker(
+      context.banksClient,
+      program,
+      buyTracker
+    );
+    expect(buyTrackerState.boughtQuoteAmount.toString()).eq(
+      maxBuyQuotePerWallet.toString()
+    );
+
+    await expectThrowsProgramError(
+      program,
+      swapWithBuyTracker(context.banksClient, program, {
+        ...buyParams,
+        amountIn: new BN(1_000_000),
+      }),
+      "exceededBuyLimit"
+    );
+  });
+
+  it("Buy limits don't apply after the buy limit duration", async () => {
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    context.warpToSlot(
+      BigInt(virtualPoolState.activationPoint.add(buyLimitDuration).toString())
+    );
+    await swap(context.banksClient, program, {
+      ...buyParams,
+      amountIn: maxBuyQuotePerSwap.addn(2),
+    });
+  });
+});
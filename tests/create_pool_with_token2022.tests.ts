@@ -124,6 +124,11 @@ describe("Create pool with token2022", () => {
                 feePercentage: 0,
                 creatorFeePercentage: 0,
             },
+            migrationFillMode: 0,
+            maxActivationDelay: new BN(0),
+            buyLimitDuration: new BN(0),
+            maxBuyQuotePerSwap: new BN(0),
+            maxBuyQuotePerWallet: new BN(0),
             padding0: [],
             padding: [],
             curve: curves,
@@ -106,6 +106,11 @@ describe("Fee Swap test", () => {
           feePercentage: 0,
           creatorFeePercentage: 0,
         },
+        migrationFillMode: 0,
+        maxActivationDelay: new BN(0),
+        buyLimitDuration: new BN(0),
+        maxBuyQuotePerSwap: new BN(0),
+        maxBuyQuotePerWallet: new BN(0),
         padding0: [],
         padding: [],
         curve: curves,
@@ -456,6 +461,11 @@ describe("Fee Swap test", () => {
           feePercentage: 0,
           creatorFeePercentage: 0,
         },
+        migrationFillMode: 0,
+        maxActivationDelay: new BN(0),
+        buyLimitDuration: new BN(0),
+        maxBuyQuotePerSwap: new BN(0),
+        maxBuyQuotePerWallet: new BN(0),
         padding0: [],
         padding: [],
         curve: curves,
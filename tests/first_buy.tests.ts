@@ -0,0 +1,227 @@
+import { ProgramTestContext } from "solana-bankrun";
+import {
+  createConfig,
+  CreateConfigParams,
+  createPoolWithSplTokenAndFirstBuy,
+  createPoolWithToken2022AndFirstBuy,
+  CreatePoolAndFirstBuyParams,
+} from "./instructions";
+import { VirtualCurveProgram } from "./utils/types";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  createVirtualCurveProgram,
+  designCurve,
+  expectThrowsProgramError,
+  fundSol,
+  getTokenAccount,
+  startTest,
+} from "./utils";
+import { getVirtualPool } from "./utils/fetcher";
+import { createToken, mintSplTokenTo } from "./utils/token";
+import {
+  getAssociatedTokenAddressSync,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { BN } from "bn.js";
+
+describe("Create pool and first buy", () => {
+  let context: ProgramTestContext;
+  let admin: Keypair;
+  let operator: Keypair;
+  let partner: Keypair;
+  let poolCreator: Keypair;
+  let program: VirtualCurveProgram;
+  let quoteMint: PublicKey;
+  let splTokenConfig: PublicKey;
+  let token2022Config: PublicKey;
+
+  const firstBuyAmount = new BN(1_000_000_000);
+
+  before(async () => {
+    context = await startTest();
+    admin = context.payer;
+    operator = Keypair.generate();
+    partner = Keypair.generate();
+    poolCreator = Keypair.generate();
+    const receivers = [
+      operator.publicKey,
+      partner.publicKey,
+      poolCreator.publicKey,
+    ];
+    await fundSol(context.banksClient, admin, receivers);
+    program = createVirtualCurveProgram();
+
+    quoteMint = await createToken(
+      context.banksClient,
+      admin,
+      admin.publicKey,
+      9
+    );
+    await mintSplTokenTo(
+      context.banksClient,
+      poolCreator,
+      quoteMint,
+      admin,
+      poolCreator.publicKey,
+      10_000_000_000
+    );
+
+    const instructionParams = designCurve(
+      1_000_000_000, // total token supply
+      10, // percentage supply on migration
+      300, // migration quote threshold
+      0, // damm v1
+      6, // token base decimal
+      9, // token quote decimal
+      0, // creator trading fee percentage
+      0, // collect fee mode quote token
+      {
+        amountPerPeriod: new BN(0),
+        cliffDurationFromMigrationTime: new BN(0),
+        frequency: new BN(0),
+        numberOfPeriod: new BN(0),
+        cliffUnlockAmount: new BN(0),
+      }
+    );
+    const params: CreateConfigParams = {
+      payer: partner,
+      leftoverReceiver: partner.publicKey,
+      feeClaimer: partner.publicKey,
+      quoteMint,
+      instructionParams,
+    };
+    splTokenConfig = await createConfig(context.banksClient, program, params);
+    token2022Config = await createConfig(context.banksClient, program, {
+      ...params,
+      instructionParams: { ...instructionParams, tokenType: 1 },
+    });
+  });
+
+  function getCreatePoolParams(
+    config: PublicKey,
+    symbol: string,
+    firstBuy: CreatePoolAndFirstBuyParams["firstBuy"]
+  ): CreatePoolAndFirstBuyParams {
+    return {
+      poolCreator,
+      payer: operator,
+      quoteMint,
+      config,
+      instructionParams: {
+        name: "test token first buy",
+        symbol,
+        uri: "abc.com",
+      },
+      firstBuy,
+    };
+  }
+
+  async function getCreatorQuoteAmount(): Promise<bigint> {
+    const account = await getTokenAccount(
+      context.banksClient,
+      getAssociatedTokenAddressSync(
+        quoteMint,
+        poolCreator.publicKey,
+        true,
+        TOKEN_PROGRAM_ID
+      )
+    );
+    return account.amount;
+  }
+
+  async function expectFirstBuy(
+    virtualPool: PublicKey,
+    tokenBaseProgram: PublicKey,
+    quoteAmountBefore: bigint
+  ) {
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    // trading fee is taken from the quote input
+    expect(virtualPoolState.quoteReserve.gtn(0)).to.be.true;
+    expect(virtualPoolState.quoteReserve.lt(firstBuyAmount)).to.be.true;
+
+    const quoteAmountAfter = await getCreatorQuoteAmount();
+    expect((quoteAmountBefore - quoteAmountAfter).toString()).eq(
+      firstBuyAmount.toString()
+    );
+
+    const creatorBaseTokenAccount = await getTokenAccount(
+      context.banksClient,
+      getAssociatedTokenAddressSync(
+        virtualPoolState.baseMint,
+        poolCreator.publicKey,
+        true,
+        tokenBaseProgram
+      )
+    );
+    expect(creatorBaseTokenAccount.amount > BigInt(0)).to.be.true;
+  }
+
+  it("Creator create spl token pool and first buy", async () => {
+    const quoteAmountBefore = await getCreatorQuoteAmount();
+    const virtualPool = await createPoolWithSplTokenAndFirstBuy(
+      context.banksClient,
+      program,
+      getCreatePoolParams(splTokenConfig, "SPL", {
+        amountIn: firstBuyAmount,
+        minimumAmountOut: new BN(1),
+      })
+    );
+    await expectFirstBuy(virtualPool, TOKEN_PROGRAM_ID, quoteAmountBefore);
+  });
+
+  it("Creator create token2022 pool and first buy", async () => {
+    const quoteAmountBefore = await getCreatorQuoteAmount();
+    const virtualPool = await createPoolWithToken2022AndFirstBuy(
+      context.banksClient,
+      program,
+      getCreatePoolParams(token2022Config, "T22", {
+        amountIn: firstBuyAmount,
+        minimumAmountOut: new BN(1),
+      })
+    );
+    await expectFirstBuy(
+      virtualPool,
+      TOKEN_2022_PROGRAM_ID,
+      quoteAmountBefore
+    );
+  });
+
+  it("Creator create pool without first buy", async () => {
+    const quoteAmountBefore = await getCreatorQuoteAmount();
+    const virtualPool = await createPoolWithSplTokenAndFirstBuy(
+      context.banksClient,
+      program,
+      getCreatePoolParams(splTokenConfig, "NONE", null)
+    );
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    expect(virtualPoolState.quoteReserve.isZero()).to.be.true;
+    expect((await getCreatorQuoteAmount()).toString()).eq(
+      quoteAmountBefore.toString()
+    );
+  });
+
+  it("First buy under the minimum amount out fails", async () => {
+    await expectThrowsProgramError(
+      program,
+      createPoolWithToken2022AndFirstBuy(
+        context.banksClient,
+        program,
+        getCreatePoolParams(token2022Config, "SLIP", {
+          amountIn: firstBuyAmount,
+          minimumAmountOut: new BN("18446744073709551615"),
+        })
+      ),
+      "exceededSlippage"
+    );
+  });
+});
@@ -135,6 +135,11 @@ describe("Full flow with spl-token", () => {
         feePercentage: 0,
         creatorFeePercentage: 0,
       },
+      migrationFillMode: 0,
+      maxActivationDelay: new BN(0),
+      buyLimitDuration: new BN(0),
+      maxBuyQuotePerSwap: new BN(0),
+      maxBuyQuotePerWallet: new BN(0),
       padding0: [],
       padding: [],
       curve: curves,
@@ -89,8 +89,13 @@ export type ConfigParameters = {
     feePercentage: number;
     creatorFeePercentage: number;
   };
+  migrationFillMode: number;
+  maxActivationDelay: BN;
+  buyLimitDuration: BN;
+  maxBuyQuotePerSwap: BN;
+  maxBuyQuotePerWallet: BN;
   padding0: number[];
-  padding1: BN[];
+  padding1: BN[]; // [u64; 3]
   curve: Array<LiquidityDistributionParameters>;
 };
 
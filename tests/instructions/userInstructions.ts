@@ -3,11 +3,14 @@ import {
   Keypair,
   PublicKey,
   SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   SYSVAR_RENT_PUBKEY,
   Transaction,
   TransactionInstruction,
 } from "@solana/web3.js";
 import {
+  deriveAllowlistEntry,
+  deriveBuyTracker,
   deriveMetadataAccount,
   derivePoolAddress,
   derivePoolAuthority,
@@ -54,6 +57,16 @@ export type InitializePoolParameters = {
   name: string;
   symbol: string;
   uri: string;
+  // null to activate the pool immediately
+  activationPoint?: BN | null;
+  // null to open trading to everyone at activation
+  allowlist?: AllowlistParameters | null;
+};
+
+export type AllowlistParameters = {
+  merkleRoot: number[];
+  endPoint: BN;
+  quoteCapPerWallet: BN;
 };
 export type CreatePoolSplTokenParams = {
   payer: Keypair;
@@ -84,7 +97,11 @@ export async function createPoolWithSplToken(
   const tokenProgram =
     configState.tokenType == 0 ? TOKEN_PROGRAM_ID : TOKEN_2022_PROGRAM_ID;
   const transaction = await program.methods
-    .initializeVirtualPoolWithSplToken(instructionParams)
+    .initializeVirtualPoolWithSplToken({
+      activationPoint: null,
+      allowlist: null,
+      ...instructionParams,
+    })
     .accountsPartial({
       config,
       baseMint: baseMintKP.publicKey,
@@ -123,7 +140,11 @@ export async function createPoolWithToken2022(
   const baseVault = deriveTokenVaultAddress(baseMintKP.publicKey, pool);
   const quoteVault = deriveTokenVaultAddress(quoteMint, pool);
   const transaction = await program.methods
-    .initializeVirtualPoolWithToken2022(instructionParams)
+    .initializeVirtualPoolWithToken2022({
+      activationPoint: null,
+      allowlist: null,
+      ...instructionParams,
+    })
     .accountsPartial({
       config,
       baseMint: baseMintKP.publicKey,
@@ -147,6 +168,164 @@ export async function createPoolWithToken2022(
   return pool;
 }
 
+export type FirstBuyParameters = {
+  amountIn: BN;
+  minimumAmountOut: BN;
+};
+
+export type CreatePoolAndFirstBuyParams = CreatePoolSplTokenParams & {
+  // paid from the creator quote token account, null to skip the first buy
+  firstBuy: FirstBuyParameters | null;
+};
+
+// Create the pool and buy for the creator in the same instruction, the
+// creator quote token account must exist
+export async function createPoolWithSplTokenAndFirstBuy(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: CreatePoolAndFirstBuyParams
+): Promise<PublicKey> {
+  const { payer, quoteMint, poolCreator, config, instructionParams, firstBuy } =
+    params;
+
+  const poolAuthority = derivePoolAuthority();
+  const baseMintKP = Keypair.generate();
+  const pool = derivePoolAddress(config, baseMintKP.publicKey, quoteMint);
+  const baseVault = deriveTokenVaultAddress(baseMintKP.publicKey, pool);
+  const quoteVault = deriveTokenVaultAddress(quoteMint, pool);
+
+  const mintMetadata = deriveMetadataAccount(baseMintKP.publicKey);
+
+  const transaction = await program.methods
+    .initializeVirtualPoolWithSplTokenAndFirstBuy(
+      {
+        activationPoint: null,
+        allowlist: null,
+        ...instructionParams,
+      },
+      firstBuy
+    )
+    .accountsPartial({
+      initializePool: {
+        config,
+        baseMint: baseMintKP.publicKey,
+        quoteMint,
+        pool,
+        payer: payer.publicKey,
+        creator: poolCreator.publicKey,
+        poolAuthority,
+        baseVault,
+        quoteVault,
+        mintMetadata,
+        metadataProgram: METAPLEX_PROGRAM_ID,
+        tokenQuoteProgram: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      creatorQuoteTokenAccount: getAssociatedTokenAddressSync(
+        quoteMint,
+        poolCreator.publicKey,
+        true,
+        TOKEN_PROGRAM_ID
+      ),
+      creatorBaseTokenAccount: getAssociatedTokenAddressSync(
+        baseMintKP.publicKey,
+        poolCreator.publicKey,
+        true,
+        TOKEN_PROGRAM_ID
+      ),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .preInstructions([
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }),
+    ])
+    .transaction();
+
+  transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
+  transaction.sign(payer, baseMintKP, poolCreator);
+
+  await processTransactionMaybeThrow(banksClient, transaction);
+
+  return pool;
+}
+
+export async function createPoolWithToken2022AndFirstBuy(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: CreatePoolAndFirstBuyParams
+): Promise<PublicKey> {
+  const { payer, quoteMint, poolCreator, config, instructionParams, firstBuy } =
+    params;
+
+  const poolAuthority = derivePoolAuthority();
+  const baseMintKP = Keypair.generate();
+  const pool = derivePoolAddress(config, baseMintKP.publicKey, quoteMint);
+  const baseVault = deriveTokenVaultAddress(baseMintKP.publicKey, pool);
+  const quoteVault = deriveTokenVaultAddress(quoteMint, pool);
+
+  const transaction = await program.methods
+    .initializeVirtualPoolWithToken2022AndFirstBuy(
+      {
+        activationPoint: null,
+        allowlist: null,
+        ...instructionParams,
+      },
+      firstBuy
+    )
+    .accountsPartial({
+      initializePool: {
+        config,
+        baseMint: baseMintKP.publicKey,
+        quoteMint,
+        pool,
+        payer: payer.publicKey,
+        creator: poolCreator.publicKey,
+        poolAuthority,
+        baseVault,
+        quoteVault,
+        tokenQuoteProgram: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      },
+      creatorQuoteTokenAccount: getAssociatedTokenAddressSync(
+        quoteMint,
+        poolCreator.publicKey,
+        true,
+        TOKEN_PROGRAM_ID
+      ),
+      creatorBaseTokenAccount: getAssociatedTokenAddressSync(
+        baseMintKP.publicKey,
+        poolCreator.publicKey,
+        true,
+        TOKEN_2022_PROGRAM_ID
+      ),
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .preInstructions([
+      ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }),
+    ])
+    .transaction();
+
+  transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
+  transaction.sign(payer, baseMintKP, poolCreator);
+
+  await processTransactionMaybeThrow(banksClient, transaction);
+
+  return pool;
+}
+
+// The program reads the instructions sysvar to reject several swaps on the
+// pool in one transaction while the rate limiter applies
+const SWAP_REMAINING_ACCOUNTS = [
+  {
+    isSigner: false,
+    isWritable: false,
+    pubkey: SYSVAR_INSTRUCTIONS_PUBKEY,
+  },
+];
+
 export type SwapParams = {
   config: PublicKey;
   payer: Keypair;
@@ -257,6 +436,7 @@ export async function swap(
       tokenQuoteProgram: TOKEN_PROGRAM_ID,
       referralTokenAccount,
     })
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
     .preInstructions(preInstructions)
     .postInstructions(postInstructions)
     .transaction();
@@ -369,6 +549,7 @@ export async function swapSimulate(
       tokenQuoteProgram: TOKEN_PROGRAM_ID,
       referralTokenAccount,
     })
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
     .transaction();
 
   transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
@@ -391,7 +572,378 @@ export async function swapSimulate(
   };
 }
 
-export async function createVirtualPoolMetadata(
+// Swap instruction alone, the payer token accounts must exist
+export async function getSwapInstruction(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: SwapParams
+): Promise<TransactionInstruction> {
+  const {
+    config,
+    payer,
+    pool,
+    inputTokenMint,
+    outputTokenMint,
+    amountIn,
+    minimumAmountOut,
+    referralTokenAccount,
+  } = params;
+
+  const poolState = await getVirtualPool(banksClient, program, pool);
+  const configState = await getConfig(banksClient, program, config);
+
+  const tokenBaseProgram =
+    configState.tokenType == 0 ? TOKEN_PROGRAM_ID : TOKEN_2022_PROGRAM_ID;
+
+  const isInputBaseMint = inputTokenMint.equals(poolState.baseMint);
+  const quoteMint = isInputBaseMint ? outputTokenMint : inputTokenMint;
+  const [inputTokenProgram, outputTokenProgram] = isInputBaseMint
+    ? [tokenBaseProgram, TOKEN_PROGRAM_ID]
+    : [TOKEN_PROGRAM_ID, tokenBaseProgram];
+
+  return program.methods
+    .swap({ amountIn, minimumAmountOut })
+    .accountsPartial({
+      poolAuthority: derivePoolAuthority(),
+      config,
+      pool,
+      inputTokenAccount: getAssociatedTokenAddressSync(
+        inputTokenMint,
+        payer.publicKey,
+        true,
+        inputTokenProgram
+      ),
+      outputTokenAccount: getAssociatedTokenAddressSync(
+        outputTokenMint,
+        payer.publicKey,
+        true,
+        outputTokenProgram
+      ),
+      baseVault: poolState.baseVault,
+      quoteVault: poolState.quoteVault,
+      baseMint: poolState.baseMint,
+      quoteMint,
+      payer: payer.publicKey,
+      tokenBaseProgram,
+      tokenQuoteProgram: TOKEN_PROGRAM_ID,
+      referralTokenAccount,
+    })
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
+    .instruction();
+}
+
+type SwapAccountsParams = Omit<SwapParams, "amountIn" | "minimumAmountOut">;
+
+// Accounts of `SwapCtx`, with the instructions creating the payer token
+// accounts if they don't exist
+async function getSwapAccounts(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: SwapAccountsParams
+) {
+  const {
+    config,
+    payer,
+    pool,
+    inputTokenMint,
+    outputTokenMint,
+    referralTokenAccount,
+  } = params;
+
+  const poolState = await getVirtualPool(banksClient, program, pool);
+  const configState = await getConfig(banksClient, program, config);
+
+  const tokenBaseProgram =
+    configState.tokenType == 0 ? TOKEN_PROGRAM_ID : TOKEN_2022_PROGRAM_ID;
+
+  const isInputBaseMint = inputTokenMint.equals(poolState.baseMint);
+  const quoteMint = isInputBaseMint ? outputTokenMint : inputTokenMint;
+  const [inputTokenProgram, outputTokenProgram] = isInputBaseMint
+    ? [tokenBaseProgram, TOKEN_PROGRAM_ID]
+    : [TOKEN_PROGRAM_ID, tokenBaseProgram];
+
+  const preInstructions: TransactionInstruction[] = [];
+  const [
+    { ata: inputTokenAccount, ix: createInputTokenXIx },
+    { ata: outputTokenAccount, ix: createOutputTokenYIx },
+  ] = await Promise.all([
+    getOrCreateAssociatedTokenAccount(
+      banksClient,
+      payer,
+      inputTokenMint,
+      payer.publicKey,
+      inputTokenProgram
+    ),
+    getOrCreateAssociatedTokenAccount(
+      banksClient,
+      payer,
+      outputTokenMint,
+      payer.publicKey,
+      outputTokenProgram
+    ),
+  ]);
+  createInputTokenXIx && preInstructions.push(createInputTokenXIx);
+  createOutputTokenYIx && preInstructions.push(createOutputTokenYIx);
+
+  return {
+    accounts: {
+      poolAuthority: derivePoolAuthority(),
+      config,
+      pool,
+      inputTokenAccount,
+      outputTokenAccount,
+      baseVault: poolState.baseVault,
+      quoteVault: poolState.quoteVault,
+      baseMint: poolState.baseMint,
+      quoteMint,
+      payer: payer.publicKey,
+      tokenBaseProgram,
+      tokenQuoteProgram: TOKEN_PROGRAM_ID,
+      referralTokenAccount,
+    },
+    preInstructions,
+  };
+}
+
+export type SwapExactOutParams = SwapAccountsParams & {
+  amountOut: BN;
+  maximumAmountIn: BN;
+};
+
+export async function swapExactOut(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: SwapExactOutParams
+) {
+  const { payer, amountOut, maximumAmountIn } = params;
+  const { accounts, preInstructions } = await getSwapAccounts(
+    banksClient,
+    program,
+    params
+  );
+  const transaction = await program.methods
+    .swapExactOut({ amountOut, maximumAmountIn })
+    .accountsPartial(accounts)
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
+    .preInstructions(preInstructions)
+    .transaction();
+
+  transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
+  transaction.sign(payer);
+
+  await processTransactionMaybeThrow(banksClient, transaction);
+}
+
+export type SwapWithPriceLimitParams = SwapParams & {
+  // the swap stops at this price, the unused input stays with the payer
+  sqrtPriceLimit: BN;
+};
+
+export async function swapWithPriceLimit(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: SwapWithPriceLimitParams
+) {
+  const { payer, amountIn, minimumAmountOut, sqrtPriceLimit } = params;
+  const { accounts, preInstructions } = await getSwapAccounts(
+    banksClient,
+    program,
+    params
+  );
+  const transaction = await program.methods
+    .swapWithPriceLimit({ amountIn, minimumAmountOut, sqrtPriceLimit })
+    .accountsPartial(accounts)
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
+    .preInstructions(preInstructions)
+    .transaction();
+
+  transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
+  transaction.sign(payer);
+
+  await processTransactionMaybeThrow(banksClient, transaction);
+}
+
+// Buy while the config buy limits apply, the quote spent by the payer is
+// tracked in its buy tracker
+export async function swapWithBuyTracker(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: SwapParams
+): Promise<PublicKey> {
+  const {
+    config,
+    payer,
+    pool,
+    inputTokenMint,
+    outputTokenMint,
+    amountIn,
+    minimumAmountOut,
+    referralTokenAccount,
+  } = params;
+
+  const poolAuthority = derivePoolAuthority();
+  const poolState = await getVirtualPool(banksClient, program, pool);
+  const configState = await getConfig(banksClient, program, config);
+
+  const tokenBaseProgram =
+    configState.tokenType == 0 ? TOKEN_PROGRAM_ID : TOKEN_2022_PROGRAM_ID;
+
+  const preInstructions: TransactionInstruction[] = [];
+  const [
+    { ata: inputTokenAccount, ix: createInputTokenXIx },
+    { ata: outputTokenAccount, ix: createOutputTokenYIx },
+  ] = await Promise.all([
+    getOrCreateAssociatedTokenAccount(
+      banksClient,
+      payer,
+      inputTokenMint,
+      payer.publicKey,
+      TOKEN_PROGRAM_ID
+    ),
+    getOrCreateAssociatedTokenAccount(
+      banksClient,
+      payer,
+      outputTokenMint,
+      payer.publicKey,
+      tokenBaseProgram
+    ),
+  ]);
+  createInputTokenXIx && preInstructions.push(createInputTokenXIx);
+  createOutputTokenYIx && preInstructions.push(createOutputTokenYIx);
+
+  if (inputTokenMint.equals(NATIVE_MINT) && !amountIn.isZero()) {
+    const wrapSOLIx = wrapSOLInstruction(
+      payer.publicKey,
+      inputTokenAccount,
+      BigInt(amountIn.toString())
+    );
+
+    preInstructions.push(...wrapSOLIx);
+  }
+
+  const buyTracker = deriveBuyTracker(pool, payer.publicKey);
+  const transaction = await program.methods
+    .swapWithBuyTracker({ amountIn, minimumAmountOut })
+    .accountsPartial({
+      swap: {
+        poolAuthority,
+        config,
+        pool,
+        inputTokenAccount,
+        outputTokenAccount,
+        baseVault: poolState.baseVault,
+        quoteVault: poolState.quoteVault,
+        baseMint: poolState.baseMint,
+        quoteMint: inputTokenMint,
+        payer: payer.publicKey,
+        tokenBaseProgram,
+        tokenQuoteProgram: TOKEN_PROGRAM_ID,
+        referralTokenAccount,
+      },
+      buyTracker,
+      rentPayer: payer.publicKey,
+    })
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
+    .preInstructions(preInstructions)
+    .transaction();
+
+  transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
+  transaction.sign(payer);
+
+  await processTransactionMaybeThrow(banksClient, transaction);
+
+  return buyTracker;
+}
+
+export type SwapAllowlistedParams = SwapParams & {
+  // proof of the payer in the allowlist merkle tree
+  proof: number[][];
+};
+
+// Buy in the allowlist phase, the quote spent by the payer is tracked in its
+// allowlist entry
+export async function swapAllowlisted(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  params: SwapAllowlistedParams
+): Promise<PublicKey> {
+  const {
+    config,
+    payer,
+    pool,
+    inputTokenMint,
+    outputTokenMint,
+    amountIn,
+    minimumAmountOut,
+    referralTokenAccount,
+    proof,
+  } = params;
+
+  const poolAuthority = derivePoolAuthority();
+  const poolState = await getVirtualPool(banksClient, program, pool);
+  const configState = await getConfig(banksClient, program, config);
+
+  const tokenBaseProgram =
+    configState.tokenType == 0 ? TOKEN_PROGRAM_ID : TOKEN_2022_PROGRAM_ID;
+
+  const preInstructions: TransactionInstruction[] = [];
+  const [
+    { ata: inputTokenAccount, ix: createInputTokenXIx },
+    { ata: outputTokenAccount, ix: createOutputTokenYIx },
+  ] = await Promise.all([
+    getOrCreateAssociatedTokenAccount(
+      banksClient,
+      payer,
+      inputTokenMint,
+      payer.publicKey,
+      TOKEN_PROGRAM_ID
+    ),
+    getOrCreateAssociatedTokenAccount(
+      banksClient,
+      payer,
+      outputTokenMint,
+      payer.publicKey,
+      tokenBaseProgram
+    ),
+  ]);
+  createInputTokenXIx && preInstructions.push(createInputTokenXIx);
+  createOutputTokenYIx && preInstructions.push(createOutputTokenYIx);
+
+  const allowlistEntry = deriveAllowlistEntry(pool, payer.publicKey);
+  const transaction = await program.methods
+    .swapAllowlisted({ amountIn, minimumAmountOut }, proof)
+    .accountsPartial({
+      swap: {
+        poolAuthority,
+        config,
+        pool,
+        inputTokenAccount,
+        outputTokenAccount,
+        baseVault: poolState.baseVault,
+        quoteVault: poolState.quoteVault,
+        baseMint: poolState.baseMint,
+        quoteMint: inputTokenMint,
+        payer: payer.publicKey,
+        tokenBaseProgram,
+        tokenQuoteProgram: TOKEN_PROGRAM_ID,
+        referralTokenAccount,
+      },
+      allowlistEntry,
+      rentPayer: payer.publicKey,
+    })
+    .remainingAccounts(SWAP_REMAINING_ACCOUNTS)
+    .preInstructions(preInstructions)
+    .transaction();
+
+  transaction.recentBlockhash = (await banksClient.getLatestBlockhash())[0];
+  transaction.sign(payer);
+
+  await processTransactionMaybeThrow(banksClient, transaction);
+
+  return allowlistEntry;
+}
+
+
   banksClient: BanksClient,
   program: VirtualCurveProgram,
   params: {
@@ -0,0 +1,175 @@
+import { ProgramTestContext } from "solana-bankrun";
+import {
+  createConfig,
+  CreateConfigParams,
+  createPoolWithSplToken,
+  getSwapInstruction,
+  swap,
+  SwapParams,
+} from "./instructions";
+import { VirtualCurveProgram } from "./utils/types";
+import { Keypair, PublicKey, Transaction } from "@solana/web3.js";
+import {
+  createVirtualCurveProgram,
+  designCurve,
+  expectThrowsProgramError,
+  fundSol,
+  processTransactionMaybeThrow,
+  startTest,
+} from "./utils";
+import { getVirtualPool } from "./utils/fetcher";
+import { createToken, mintSplTokenTo } from "./utils/token";
+import { BN } from "bn.js";
+
+describe("Rate limiter", () => {
+  let context: ProgramTestContext;
+  let admin: Keypair;
+  let operator: Keypair;
+  let partner: Keypair;
+  let user: Keypair;
+  let poolCreator: Keypair;
+  let program: VirtualCurveProgram;
+  let quoteMint: PublicKey;
+  let config: PublicKey;
+  let virtualPool: PublicKey;
+  let buyParams: SwapParams;
+
+  const referenceAmount = new BN(1_000_000_000);
+  const maxLimiterDuration = new BN(1_000); // slots
+
+  before(async () => {
+    context = await startTest();
+    admin = context.payer;
+    operator = Keypair.generate();
+    partner = Keypair.generate();
+    user = Keypair.generate();
+    poolCreator = Keypair.generate();
+    const receivers = [
+      operator.publicKey,
+      partner.publicKey,
+      user.publicKey,
+      poolCreator.publicKey,
+    ];
+    await fundSol(context.banksClient, admin, receivers);
+    program = createVirtualCurveProgram();
+
+    quoteMint = await createToken(
+      context.banksClient,
+      admin,
+      admin.publicKey,
+      9
+    );
+    await mintSplTokenTo(
+      context.banksClient,
+      user,
+      quoteMint,
+      admin,
+      user.publicKey,
+      10_000_000_000
+    );
+
+    const instructionParams = designCurve(
+      1_000_000_000, // total token supply
+      10, // percentage supply on migration
+      300, // migration quote threshold
+      0, // damm v1
+      6, // token base decimal
+      9, // token quote decimal
+      0, // creator trading fee percentage
+      0, // collect fee mode quote token
+      {
+        amountPerPeriod: new BN(0),
+        cliffDurationFromMigrationTime: new BN(0),
+        frequency: new BN(0),
+        numberOfPeriod: new BN(0),
+        cliffUnlockAmount: new BN(0),
+      }
+    );
+    // activation type slot
+    instructionParams.poolFees.baseFee = {
+      cliffFeeNumerator: new BN(10_000_000), // 1%
+      numberOfPeriod: 100, // 1% more per reference amount
+      periodFrequency: maxLimiterDuration,
+      reductionFactor: referenceAmount,
+      feeSchedulerMode: 2, // rate limiter
+    };
+    const params: CreateConfigParams = {
+      payer: partner,
+      leftoverReceiver: partner.publicKey,
+      feeClaimer: partner.publicKey,
+      quoteMint,
+      instructionParams,
+    };
+    config = await createConfig(context.banksClient, program, params);
+
+    virtualPool = await createPoolWithSplToken(context.banksClient, program, {
+      poolCreator,
+      payer: operator,
+      quoteMint,
+      config,
+      instructionParams: {
+        name: "test token spl",
+        symbol: "TEST",
+        uri: "abc.com",
+      },
+    });
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    buyParams = {
+      config,
+      payer: user,
+      pool: virtualPool,
+      inputTokenMint: quoteMint,
+      outputTokenMint: virtualPoolState.baseMint,
+      amountIn: referenceAmount,
+      minimumAmountOut: new BN(0),
+      referralTokenAccount: null,
+    };
+  });
+
+  async function splitBuy(minimumAmountOut: BN) {
+    const transaction = new Transaction().add(
+      await getSwapInstruction(context.banksClient, program, {
+        ...buyParams,
+        minimumAmountOut,
+      }),
+      await getSwapInstruction(context.banksClient, program, buyParams)
+    );
+    transaction.recentBlockhash = (
+      await context.banksClient.getLatestBlockhash()
+    )[0];
+    transaction.sign(user);
+    await processTransactionMaybeThrow(context.banksClient, transaction);
+  }
+
+  it("Single buy under the rate limiter", async () => {
+    // also creates the user token accounts
+    await swap(context.banksClient, program, buyParams);
+  });
+
+  it("Buy split into several swaps in one transaction fails", async () => {
+    await expectThrowsProgramError(
+      program,
+      splitBuy(new BN(0)),
+      "failToValidateSingleSwapInstruction"
+    );
+  });
+
+  it("Several swaps in one transaction after the rate limiter", async () => {
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    context.warpToSlot(
+      BigInt(
+        virtualPoolState.activationPoint.add(maxLimiterDuration).toString()
+      )
+    );
+    // a different amount, so the transaction isn't a duplicate of the failed one
+    await splitBuy(new BN(1));
+  });
+});
@@ -0,0 +1,178 @@
+import { ProgramTestContext } from "solana-bankrun";
+import {
+  createConfig,
+  CreateConfigParams,
+  createPoolWithSplToken,
+  swap,
+  SwapParams,
+} from "./instructions";
+import { VirtualCurveProgram } from "./utils/types";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  createVirtualCurveProgram,
+  designCurve,
+  expectThrowsProgramError,
+  fundSol,
+  startTest,
+} from "./utils";
+import { getVirtualPool } from "./utils/fetcher";
+import { createToken, mintSplTokenTo } from "./utils/token";
+import { expect } from "chai";
+import { BN } from "bn.js";
+
+describe("Scheduled activation", () => {
+  let context: ProgramTestContext;
+  let admin: Keypair;
+  let operator: Keypair;
+  let partner: Keypair;
+  let user: Keypair;
+  let poolCreator: Keypair;
+  let program: VirtualCurveProgram;
+  let quoteMint: PublicKey;
+  let config: PublicKey;
+  let virtualPool: PublicKey;
+  let activationPoint: BN;
+
+  const maxActivationDelay = new BN(1_000); // slots
+
+  before(async () => {
+    context = await startTest();
+    admin = context.payer;
+    operator = Keypair.generate();
+    partner = Keypair.generate();
+    user = Keypair.generate();
+    poolCreator = Keypair.generate();
+    const receivers = [
+      operator.publicKey,
+      partner.publicKey,
+      user.publicKey,
+      poolCreator.publicKey,
+    ];
+    await fundSol(context.banksClient, admin, receivers);
+    program = createVirtualCurveProgram();
+
+    quoteMint = await createToken(
+      context.banksClient,
+      admin,
+      admin.publicKey,
+      9
+    );
+    await mintSplTokenTo(
+      context.banksClient,
+      user,
+      quoteMint,
+      admin,
+      user.publicKey,
+      10_000_000_000
+    );
+  });
+
+  it("Partner create config with max activation delay", async () => {
+    const instructionParams = designCurve(
+      1_000_000_000, // total token supply
+      10, // percentage supply on migration
+      300, // migration quote threshold
+      0, // damm v1
+      6, // token base decimal
+      9, // token quote decimal
+      0, // creator trading fee percentage
+      1, // collect fee mode
+      {
+        amountPerPeriod: new BN(0),
+        cliffDurationFromMigrationTime: new BN(0),
+        frequency: new BN(0),
+        numberOfPeriod: new BN(0),
+        cliffUnlockAmount: new BN(0),
+      }
+    );
+    // activation type slot
+    instructionParams.maxActivationDelay = maxActivationDelay;
+    const params: CreateConfigParams = {
+      payer: partner,
+      leftoverReceiver: partner.publicKey,
+      feeClaimer: partner.publicKey,
+      quoteMint,
+      instructionParams,
+    };
+    config = await createConfig(context.banksClient, program, params);
+  });
+
+  it("Creator can't schedule activation over max activation delay", async () => {
+    const currentSlot = new BN(
+      (await context.banksClient.getClock()).slot.toString()
+    );
+    await expectThrowsProgramError(
+      program,
+      createPoolWithSplToken(context.banksClient, program, {
+        poolCreator,
+        payer: operator,
+        quoteMint,
+        config,
+        instructionParams: {
+          name: "test token spl",
+          symbol: "TEST",
+          uri: "abc.com",
+          activationPoint: currentSlot.add(maxActivationDelay).addn(1),
+        },
+      }),
+      "invalidActivationPoint"
+    );
+  });
+
+  it("Creator create pool with scheduled activation", async () => {
+    const currentSlot = new BN(
+      (await context.banksClient.getClock()).slot.toString()
+    );
+    activationPoint = currentSlot.addn(100);
+    virtualPool = await createPoolWithSplToken(context.banksClient, program, {
+      poolCreator,
+      payer: operator,
+      quoteMint,
+      config,
+      instructionParams: {
+        name: "test token spl",
+        symbol: "TEST",
+        uri: "abc.com",
+        activationPoint,
+      },
+    });
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    expect(virtualPoolState.activationPoint.toString()).eq(
+      activationPoint.toString()
+    );
+  });
+
+  it("User can only swap from the activation point", async () => {
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    const swapParams: SwapParams = {
+      config,
+      payer: user,
+      pool: virtualPool,
+      inputTokenMint: quoteMint,
+      outputTokenMint: virtualPoolState.baseMint,
+      amountIn: new BN(1_000_000_000),
+      minimumAmountOut: new BN(0),
+      referralTokenAccount: null,
+    };
+    await expectThrowsProgramError(
+      program,
+      swap(context.banksClient, program, swapParams),
+      "poolIsNotActivated"
+    );
+
+    context.warpToSlot(BigInt(activationPoint.toString()));
+    // a different amount, so the transaction isn't a duplicate of the failed one
+    await swap(context.banksClient, program, {
+      ...swapParams,
+      amountIn: new BN(2_000_000_000),
+    });
+  });
+});
@@ -0,0 +1,250 @@
+import { ProgramTestContext } from "solana-bankrun";
+import {
+  createConfig,
+  CreateConfigParams,
+  createPoolWithSplToken,
+  swap,
+  swapAllowlisted,
+  SwapParams,
+} from "./instructions";
+import { VirtualCurveProgram } from "./utils/types";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  AllowlistTree,
+  buildAllowlistTree,
+  createVirtualCurveProgram,
+  designCurve,
+  expectThrowsProgramError,
+  fundSol,
+  getTokenAccount,
+  startTest,
+} from "./utils";
+import { getAllowlistEntry, getVirtualPool } from "./utils/fetcher";
+import { createToken, mintSplTokenTo } from "./utils/token";
+import { getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { expect } from "chai";
+import { BN } from "bn.js";
+
+describe("Swap allowlisted", () => {
+  let context: ProgramTestContext;
+  let admin: Keypair;
+  let operator: Keypair;
+  let partner: Keypair;
+  let allowlistedUser: Keypair;
+  let otherAllowlistedUser: Keypair;
+  let user: Keypair;
+  let poolCreator: Keypair;
+  let program: VirtualCurveProgram;
+  let quoteMint: PublicKey;
+  let baseMint: PublicKey;
+  let config: PublicKey;
+  let virtualPool: PublicKey;
+  let allowlistTree: AllowlistTree;
+  let allowlistEndPoint: BN;
+
+  const quoteCapPerWallet = new BN(1_500_000_000);
+
+  before(async () => {
+    context = await startTest();
+    admin = context.payer;
+    operator = Keypair.generate();
+    partner = Keypair.generate();
+    allowlistedUser = Keypair.generate();
+    otherAllowlistedUser = Keypair.generate();
+    user = Keypair.generate();
+    poolCreator = Keypair.generate();
+    const receivers = [
+      operator.publicKey,
+      partner.publicKey,
+      allowlistedUser.publicKey,
+      otherAllowlistedUser.publicKey,
+      user.publicKey,
+      poolCreator.publicKey,
+    ];
+    await fundSol(context.banksClient, admin, receivers);
+    program = createVirtualCurveProgram();
+
+    quoteMint = await createToken(
+      context.banksClient,
+      admin,
+      admin.publicKey,
+      9
+    );
+    for (const wallet of [allowlistedUser, user]) {
+      await mintSplTokenTo(
+        context.banksClient,
+        wallet,
+        quoteMint,
+        admin,
+        wallet.publicKey,
+        10_000_000_000
+      );
+    }
+
+    const instructionParams = designCurve(
+      1_000_000_000, // total token supply
+      10, // percentage supply on migration
+      300, // migration quote threshold
+      0, // damm v1
+      6, // token base decimal
+      9, // token quote decimal
+      0, // creator trading fee percentage
+      0, // collect fee mode quote token
+      {
+        amountPerPeriod: new BN(0),
+        cliffDurationFromMigrationTime: new BN(0),
+        frequency: new BN(0),
+        numberOfPeriod: new BN(0),
+        cliffUnlockAmount: new BN(0),
+      }
+    );
+    // activation type slot
+    const params: CreateConfigParams = {
+      payer: partner,
+      leftoverReceiver: partner.publicKey,
+      feeClaimer: partner.publicKey,
+      quoteMint,
+      instructionParams,
+    };
+    config = await createConfig(context.banksClient, program, params);
+
+    allowlistTree = buildAllowlistTree([
+      allowlistedUser.publicKey,
+      otherAllowlistedUser.publicKey,
+    ]);
+    const currentSlot = new BN(
+      (await context.banksClient.getClock()).slot.toString()
+    );
+    allowlistEndPoint = currentSlot.addn(100);
+    virtualPool = await createPoolWithSplToken(context.banksClient, program, {
+      poolCreator,
+      payer: operator,
+      quoteMint,
+      config,
+      instructionParams: {
+        name: "test token allowlist",
+        symbol: "TEST",
+        uri: "abc.com",
+        allowlist: {
+          merkleRoot: allowlistTree.merkleRoot,
+          endPoint: allowlistEndPoint,
+          quoteCapPerWallet,
+        },
+      },
+    });
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    baseMint = virtualPoolState.baseMint;
+  });
+
+  function getBuyParams(payer: Keypair, amountIn: BN): SwapParams {
+    return {
+      config,
+      payer,
+      pool: virtualPool,
+      inputTokenMint: quoteMint,
+      outputTokenMint: baseMint,
+      amountIn,
+      minimumAmountOut: new BN(0),
+      referralTokenAccount: null,
+    };
+  }
+
+  it("Wallet can't buy with the proof of another wallet", async () => {
+    await expectThrowsProgramError(
+      program,
+      swapAllowlisted(context.banksClient, program, {
+        ...getBuyParams(user, new BN(1_000_000_000)),
+        proof: allowlistTree.getProof(allowlistedUser.publicKey),
+      }),
+      "invalidAllowlistProof"
+    );
+  });
+
+  it("Buys without allowlist proof fail in the allowlist phase", async () => {
+    for (const payer of [user, allowlistedUser]) {
+      await expectThrowsProgramError(
+        program,
+        swap(
+          context.banksClient,
+          program,
+          getBuyParams(payer, new BN(1_000_000_000))
+        ),
+        "poolIsInAllowlistPhase"
+      );
+    }
+  });
+
+  it("Allowlisted wallet buys up to the quote cap", async () => {
+    const proof = allowlistTree.getProof(allowlistedUser.publicKey);
+    const allowlistEntry = await swapAllowlisted(
+      context.banksClient,
+      program,
+      {
+        ...getBuyParams(allowlistedUser, new BN(1_000_000_000)),
+        proof,
+      }
+    );
+    const allowlistEntryState = await getAllowlistEntry(
+      context.banksClient,
+      program,
+      allowlistEntry
+    );
+    expect(allowlistEntryState.pool.toString()).eq(virtualPool.toString());
+    expect(allowlistEntryState.owner.toString()).eq(
+      allowlistedUser.publicKey.toString()
+    );
+    expect(allowlistEntryState.boughtQuoteAmount.toString()).eq(
+      "1000000000"
+    );
+
+    // both buys together are over the cap
+    await expectThrowsProgramError(
+      program,
+      swapAllowlisted(context.banksClient, program, {
+        ...getBuyParams(allowlistedUser, new BN(600_000_000)),
+        proof,
+      }),
+      "exceededAllowlistQuoteCap"
+    );
+  });
+
+  it("Anyone can sell in the allowlist phase", async () => {
+    const baseTokenAccount = await getTokenAccount(
+      context.banksClient,
+      getAssociatedTokenAddressSync(baseMint, allowlistedUser.publicKey)
+    );
+    await swap(context.banksClient, program, {
+      config,
+      payer: allowlistedUser,
+      pool: virtualPool,
+      inputTokenMint: baseMint,
+      outputTokenMint: quoteMint,
+      amountIn: new BN((baseTokenAccount.amount / BigInt(2)).toString()),
+      minimumAmountOut: new BN(0),
+      referralTokenAccount: null,
+    });
+  });
+
+  it("Anyone can buy after the allowlist phase", async () => {
+    context.warpToSlot(BigInt(allowlistEndPoint.toString()));
+    // a different amount, so the transaction isn't a duplicate of the failed one
+    await swap(
+      context.banksClient,
+      program,
+      getBuyParams(user, new BN(2_000_000_000))
+    );
+
+    await expectThrowsProgramError(
+      program,
+      swapAllowlisted(context.banksClient, program, {
+        ...getBuyParams(allowlistedUser, new BN(100_000_000)),
+        proof: allowlistTree.getProof(allowlistedUser.publicKey),
+      }),
+      "poolIsNotInAllowlistPhase"
+    );
+  });
+});
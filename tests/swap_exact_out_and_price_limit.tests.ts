@@ -0,0 +1,213 @@
+import { ProgramTestContext } from "solana-bankrun";
+import {
+  createConfig,
+  CreateConfigParams,
+  createPoolWithSplToken,
+  swapExactOut,
+  swapWithPriceLimit,
+} from "./instructions";
+import { VirtualCurveProgram } from "./utils/types";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  createVirtualCurveProgram,
+  designCurve,
+  expectThrowsProgramError,
+  fundSol,
+  getTokenAccount,
+  startTest,
+} from "./utils";
+import { getVirtualPool } from "./utils/fetcher";
+import { createToken, mintSplTokenTo } from "./utils/token";
+import { getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { expect } from "chai";
+import { BN } from "bn.js";
+
+describe("Swap exact out and with price limit", () => {
+  let context: ProgramTestContext;
+  let admin: Keypair;
+  let operator: Keypair;
+  let partner: Keypair;
+  let user: Keypair;
+  let poolCreator: Keypair;
+  let program: VirtualCurveProgram;
+  let quoteMint: PublicKey;
+  let baseMint: PublicKey;
+  let config: PublicKey;
+  let virtualPool: PublicKey;
+
+  before(async () => {
+    context = await startTest();
+    admin = context.payer;
+    operator = Keypair.generate();
+    partner = Keypair.generate();
+    user = Keypair.generate();
+    poolCreator = Keypair.generate();
+    const receivers = [
+      operator.publicKey,
+      partner.publicKey,
+      user.publicKey,
+      poolCreator.publicKey,
+    ];
+    await fundSol(context.banksClient, admin, receivers);
+    program = createVirtualCurveProgram();
+
+    quoteMint = await createToken(
+      context.banksClient,
+      admin,
+      admin.publicKey,
+      9
+    );
+    await mintSplTokenTo(
+      context.banksClient,
+      user,
+      quoteMint,
+      admin,
+      user.publicKey,
+      200_000_000_000
+    );
+
+    const instructionParams = designCurve(
+      1_000_000_000, // total token supply
+      10, // percentage supply on migration
+      300, // migration quote threshold
+      0, // damm v1
+      6, // token base decimal
+      9, // token quote decimal
+      0, // creator trading fee percentage
+      0, // collect fee mode quote token
+      {
+        amountPerPeriod: new BN(0),
+        cliffDurationFromMigrationTime: new BN(0),
+        frequency: new BN(0),
+        numberOfPeriod: new BN(0),
+        cliffUnlockAmount: new BN(0),
+      }
+    );
+    const params: CreateConfigParams = {
+      payer: partner,
+      leftoverReceiver: partner.publicKey,
+      feeClaimer: partner.publicKey,
+      quoteMint,
+      instructionParams,
+    };
+    config = await createConfig(context.banksClient, program, params);
+
+    virtualPool = await createPoolWithSplToken(context.banksClient, program, {
+      poolCreator,
+      payer: operator,
+      quoteMint,
+      config,
+      instructionParams: {
+        name: "test token spl",
+        symbol: "TEST",
+        uri: "abc.com",
+      },
+    });
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    baseMint = virtualPoolState.baseMint;
+  });
+
+  async function getUserAmount(mint: PublicKey): Promise<BN> {
+    const account = await getTokenAccount(
+      context.banksClient,
+      getAssociatedTokenAddressSync(mint, user.publicKey)
+    );
+    return new BN(account ? account.amount.toString() : 0);
+  }
+
+  const buyAccounts = () => ({
+    config,
+    payer: user,
+    pool: virtualPool,
+    inputTokenMint: quoteMint,
+    outputTokenMint: baseMint,
+    referralTokenAccount: null,
+  });
+
+  it("User buys an exact base amount", async () => {
+    const amountOut = new BN(1_000_000_000);
+    const maximumAmountIn = new BN(10_000_000_000);
+    const quoteAmountBefore = await getUserAmount(quoteMint);
+    const baseAmountBefore = await getUserAmount(baseMint);
+
+    await swapExactOut(context.banksClient, program, {
+      ...buyAccounts(),
+      amountOut,
+      maximumAmountIn,
+    });
+
+    const baseAmountAfter = await getUserAmount(baseMint);
+    expect(baseAmountAfter.sub(baseAmountBefore).toString()).eq(
+      amountOut.toString()
+    );
+    const quoteSpent = quoteAmountBefore.sub(await getUserAmount(quoteMint));
+    expect(quoteSpent.gtn(0)).to.be.true;
+    expect(quoteSpent.lte(maximumAmountIn)).to.be.true;
+  });
+
+  it("Exact out buy over the maximum amount in fails", async () => {
+    await expectThrowsProgramError(
+      program,
+      swapExactOut(context.banksClient, program, {
+        ...buyAccounts(),
+        amountOut: new BN(1_000_000_000),
+        maximumAmountIn: new BN(1),
+      }),
+      "exceededSlippage"
+    );
+  });
+
+  it("Buy stops at the price limit", async () => {
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    // 1% above the current sqrt price
+    const sqrtPriceLimit = virtualPoolState.sqrtPrice.muln(101).divn(100);
+    const amountIn = new BN(100_000_000_000);
+    const quoteAmountBefore = await getUserAmount(quoteMint);
+
+    await swapWithPriceLimit(context.banksClient, program, {
+      ...buyAccounts(),
+      amountIn,
+      minimumAmountOut: new BN(0),
+      sqrtPriceLimit,
+    });
+
+    const virtualPoolStateAfter = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    expect(virtualPoolStateAfter.sqrtPrice.toString()).eq(
+      sqrtPriceLimit.toString()
+    );
+    // only the consumed input is transferred
+    const quoteSpent = quoteAmountBefore.sub(await getUserAmount(quoteMint));
+    expect(quoteSpent.gtn(0)).to.be.true;
+    expect(quoteSpent.lt(amountIn)).to.be.true;
+  });
+
+  it("Buy with a price limit below the current price fails", async () => {
+    const virtualPoolState = await getVirtualPool(
+      context.banksClient,
+      program,
+      virtualPool
+    );
+    await expectThrowsProgramError(
+      program,
+      swapWithPriceLimit(context.banksClient, program, {
+        ...buyAccounts(),
+        amountIn: new BN(1_000_000_000),
+        minimumAmountOut: new BN(0),
+        sqrtPriceLimit: virtualPoolState.sqrtPrice.subn(1),
+      }),
+      "invalidSqrtPriceLimit"
+    );
+  });
+});
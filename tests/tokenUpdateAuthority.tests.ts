@@ -125,6 +125,11 @@ describe("Create pool with token2022", () => {
       tokenSupply: null,
       creatorTradingFeePercentage: 0,
       tokenUpdateAuthority: 0, // mutable
+      migrationFillMode: 0,
+      maxActivationDelay: new BN(0),
+      buyLimitDuration: new BN(0),
+      maxBuyQuotePerSwap: new BN(0),
+      maxBuyQuotePerWallet: new BN(0),
       padding0: [],
       padding: [],
       curve: curves,
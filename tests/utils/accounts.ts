@@ -65,6 +65,23 @@ export function deriveVirtualPoolMetadata(pool: PublicKey): PublicKey {
   )[0];
 }
 
+export function deriveBuyTracker(pool: PublicKey, owner: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("buy_tracker"), pool.toBuffer(), owner.toBuffer()],
+    DYNAMIC_BONDING_CURVE_PROGRAM_ID
+  )[0];
+}
+
+export function deriveAllowlistEntry(
+  pool: PublicKey,
+  owner: PublicKey
+): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("allowlist_entry"), pool.toBuffer(), owner.toBuffer()],
+    DYNAMIC_BONDING_CURVE_PROGRAM_ID
+  )[0];
+}
+
 export function deriveConfigAddress(index: BN): PublicKey {
   return PublicKey.findProgramAddressSync(
     [Buffer.from("config"), index.toArrayLike(Buffer, "le", 8)],
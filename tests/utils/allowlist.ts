@@ -0,0 +1,67 @@
+import { PublicKey } from "@solana/web3.js";
+import { createHash } from "crypto";
+
+// Same as the program: leaves and nodes use different prefixes, each pair is
+// hashed in sorted order so the proof doesn't need the side
+const LEAF_PREFIX = Buffer.from([0]);
+const NODE_PREFIX = Buffer.from([1]);
+
+export function getAllowlistLeaf(wallet: PublicKey): Buffer {
+  return createHash("sha256")
+    .update(LEAF_PREFIX)
+    .update(wallet.toBuffer())
+    .digest();
+}
+
+export function hashAllowlistNodes(left: Buffer, right: Buffer): Buffer {
+  const [first, second] =
+    Buffer.compare(left, right) <= 0 ? [left, right] : [right, left];
+  return createHash("sha256")
+    .update(NODE_PREFIX)
+    .update(first)
+    .update(second)
+    .digest();
+}
+
+export type AllowlistTree = {
+  merkleRoot: number[];
+  getProof: (wallet: PublicKey) => number[][];
+};
+
+// A node without a sibling moves up to the next level as is
+export function buildAllowlistTree(wallets: PublicKey[]): AllowlistTree {
+  const levels: Buffer[][] = [wallets.map(getAllowlistLeaf)];
+  while (levels[levels.length - 1].length > 1) {
+    const level = levels[levels.length - 1];
+    const nextLevel: Buffer[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      nextLevel.push(
+        i + 1 < level.length
+          ? hashAllowlistNodes(level[i], level[i + 1])
+          : level[i]
+      );
+    }
+    levels.push(nextLevel);
+  }
+
+  const getProof = (wallet: PublicKey) => {
+    let index = wallets.findIndex((w) => w.equals(wallet));
+    if (index < 0) {
+      throw new Error("wallet isn't allowlisted");
+    }
+    const proof: number[][] = [];
+    for (const level of levels.slice(0, -1)) {
+      const sibling = index % 2 == 0 ? index + 1 : index - 1;
+      if (sibling < level.length) {
+        proof.push(Array.from(level[sibling]));
+      }
+      index = Math.floor(index / 2);
+    }
+    return proof;
+  };
+
+  return {
+    merkleRoot: Array.from(levels[levels.length - 1][0]),
+    getProof,
+  };
+}
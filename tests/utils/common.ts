@@ -52,6 +52,7 @@ import {
 } from "./constants";
 import { BanksClient } from "solana-bankrun";
 import { ADMIN_USDC_ATA, LOCAL_ADMIN_KEYPAIR, USDC } from "./bankrun";
+import { assert, expect } from "chai";
 
 export type DynamicVault = IdlAccounts<Vault>["vault"];
 const BASE_ADDRESS = new PublicKey(
@@ -117,6 +118,23 @@ export async function processTransactionMaybeThrow(
   }
 }
 
+export async function expectThrowsProgramError(
+  program: VirtualCurveProgram,
+  promise: Promise<unknown>,
+  errorName: string
+) {
+  const errorCode = program.idl.errors.find((e) => e.name == errorName).code;
+  try {
+    await promise;
+  } catch (e) {
+    expect(e.message).include(
+      `custom program error: 0x${errorCode.toString(16)}`
+    );
+    return;
+  }
+  assert.fail(`expected error ${errorName}`);
+}
+
 export const wrapSOLInstruction = (
   from: PublicKey,
   to: PublicKey,
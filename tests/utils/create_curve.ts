@@ -473,6 +473,11 @@ export function designCurve(
       feePercentage: 0,
       creatorFeePercentage: 0,
     },
+    migrationFillMode: 0,
+    maxActivationDelay: new BN(0),
+    buyLimitDuration: new BN(0),
+    maxBuyQuotePerSwap: new BN(0),
+    maxBuyQuotePerWallet: new BN(0),
     padding0: [],
     padding1: [],
     curve,
@@ -627,6 +632,11 @@ export function designGraphCurve(
       feePercentage: 0,
       creatorFeePercentage: 0,
     },
+    migrationFillMode: 0,
+    maxActivationDelay: new BN(0),
+    buyLimitDuration: new BN(0),
+    maxBuyQuotePerSwap: new BN(0),
+    maxBuyQuotePerWallet: new BN(0),
     padding0: [],
     padding1: [],
     curve,
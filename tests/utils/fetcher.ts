@@ -1,6 +1,8 @@
 import { PublicKey } from "@solana/web3.js";
 import { BanksClient } from "solana-bankrun";
 import {
+  AllowlistEntry,
+  BuyTracker,
   ClaimFeeOperator,
   LockEscrow,
   MeteoraDammMigrationMetadata,
@@ -82,6 +84,15 @@ export async function getMeteoraDammMigrationMetadata(
   );
 }
 
+export async function getBuyTracker(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  buyTracker: PublicKey
+): Promise<BuyTracker> {
+  const account = await banksClient.getAccount(buyTracker);
+  return program.coder.accounts.decode("buyTracker", Buffer.from(account.data));
+}
+
 export async function getLockEscrow(
   banksClient: BanksClient,
   program: Program<DynamicAmm>,
@@ -90,3 +101,15 @@ export async function getLockEscrow(
   const account = await banksClient.getAccount(lockEscrow);
   return program.coder.accounts.decode("lockEscrow", Buffer.from(account.data));
 }
+
+export async function getAllowlistEntry(
+  banksClient: BanksClient,
+  program: VirtualCurveProgram,
+  allowlistEntry: PublicKey
+): Promise<AllowlistEntry> {
+  const account = await banksClient.getAccount(allowlistEntry);
+  return program.coder.accounts.decode(
+    "allowlistEntry",
+    Buffer.from(account.data)
+  );
+}
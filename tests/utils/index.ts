@@ -5,3 +5,4 @@ export * from "./types";
 export * from "./bankrun";
 export * from "./fetcher";
 export * from "./create_curve";
+export * from "./allowlist";
@@ -12,4 +12,7 @@ export type VirtualPoolMetadata =
 export type ClaimFeeOperator = IdlAccounts<DynamicBondingCurve>["claimFeeOperator"];
 export type MeteoraDammMigrationMetadata =
   IdlAccounts<DynamicBondingCurve>["meteoraDammMigrationMetadata"];
+export type BuyTracker = IdlAccounts<DynamicBondingCurve>["buyTracker"];
+export type AllowlistEntry =
+  IdlAccounts<DynamicBondingCurve>["allowlistEntry"];
 export type LockEscrow = IdlAccounts<DynamicAmm>["lockEscrow"];